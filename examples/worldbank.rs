@@ -12,18 +12,29 @@ fn main() {
         .join("../tests/data/gdp.csv");
     let dialect = Dialect {
         delimiter: b',',
+        multibyte_delimiter: None,
+        single_column: false,
         header: Header {
             has_header_row: true,
             num_preamble_rows: 4,
+            num_comment_preamble_rows: 0,
         },
         quote: Quote::Some(b'"'),
+        quote_style: QuoteStyle::Always,
+        escape: Escape::Disabled,
+        double_quote: true,
+        comment: Comment::Disabled,
+        terminator: Terminator::CRLF,
+        trailing_delimiter: false,
+        embedded_escapes: false,
+        trim: Trim::None,
+        blank_lines: BlankLinePolicy::Skip,
         flexible: false,
         is_utf8: true,
     };
     let mut reader = dialect.open_path(data_filepath).unwrap();
-    for result in reader.records() {
+    if let Some(result) = reader.records().next() {
         let record = result.unwrap();
         println!("{:?}", record);
-        break;
     }
 }
@@ -27,6 +27,9 @@ impl Chain {
     pub(crate) fn add_observation(&mut self, obs: usize) {
         self.observations.push(obs);
     }
+    pub(crate) fn observations(&self) -> &[usize] {
+        &self.observations
+    }
     pub(crate) fn viterbi(&mut self) -> ViterbiResults {
         if self.observations.is_empty() {
             return ViterbiResults {
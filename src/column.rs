@@ -0,0 +1,305 @@
+/*!
+Summaries of the sampled values of each column, kept up to date one value at a time, from which the
+details of the columns which depend on all of their values (e.g. date formats or value ranges) are
+inferred without holding on to the values.
+*/
+use std::borrow::Borrow;
+use std::sync::Arc;
+
+use crate::field_type::{
+    clock_duration_hours, infer_types, is_base64, is_integer_literal, is_non_finite,
+    parse_currency, parse_decimal, parse_prefixed_integer, strip_thousands, thousands_separator,
+    DateFormatCandidates, NegativeStyle, Type, TypeDetector, TypeOptions, TypeTally,
+    SEMANTIC_TYPES,
+};
+use crate::metadata::ValueRange;
+
+/// What the values of the columns are summarized with.
+pub(crate) struct ColumnOptions {
+    pub(crate) type_options: TypeOptions,
+    /// The custom type detectors, in the order they're tried.
+    pub(crate) detectors: Vec<Arc<dyn TypeDetector>>,
+    pub(crate) prefixed_integers: bool,
+    pub(crate) category_threshold: usize,
+}
+
+/// The smallest and largest values seen, as ordered by their keys. Ties keep the value seen first.
+#[derive(Debug, Clone)]
+pub(crate) struct ValueBounds<K> {
+    min: Option<(K, String)>,
+    max: Option<(K, String)>,
+}
+
+impl<K> Default for ValueBounds<K> {
+    fn default() -> ValueBounds<K> {
+        ValueBounds {
+            min: None,
+            max: None,
+        }
+    }
+}
+
+impl<K: PartialOrd> ValueBounds<K> {
+    pub(crate) fn add<Q>(&mut self, key: &Q, value: &str)
+    where
+        K: Borrow<Q>,
+        Q: PartialOrd + ToOwned<Owned = K> + ?Sized,
+    {
+        if self
+            .min
+            .as_ref()
+            .map_or(true, |(min, _)| key < min.borrow())
+        {
+            self.min = Some((key.to_owned(), value.to_string()));
+        }
+        if self
+            .max
+            .as_ref()
+            .map_or(true, |(max, _)| key > max.borrow())
+        {
+            self.max = Some((key.to_owned(), value.to_string()));
+        }
+    }
+
+    pub(crate) fn range(&self) -> Option<ValueRange> {
+        Some(ValueRange {
+            min: self.min.as_ref()?.1.clone(),
+            max: self.max.as_ref()?.1.clone(),
+        })
+    }
+}
+
+/// The currency symbol and negative style shared by the values of a column, if they're all
+/// currency amounts.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CurrencyStats {
+    pub(crate) symbol: Option<&'static str>,
+    pub(crate) negative: Option<NegativeStyle>,
+    // whether the values parsed so far agree on the symbol and the negative style
+    pub(crate) agree: bool,
+}
+
+/// What the values of an unsigned column say about it being a column of zero-padded codes.
+#[derive(Debug, Clone)]
+pub(crate) struct CodeStats {
+    pub(crate) any_padded: bool,
+    pub(crate) all_padded: bool,
+    first_len: Option<usize>,
+    pub(crate) same_len: bool,
+}
+
+/// The radix shared by the prefixed integers of a column, and their range.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RadixStats {
+    pub(crate) radix: Option<u32>,
+    pub(crate) same_radix: bool,
+    // the range of the values, including 0
+    pub(crate) min: i128,
+    pub(crate) max: i128,
+    pub(crate) bounds: ValueBounds<i128>,
+}
+
+/// The summary of the (non-missing) values of a column. Each part is only kept track of for the
+/// types the column can still turn out to be, as inferred from its values one at a time (e.g. date
+/// formats of date columns): `None` if the column can't be of that type, and emptied as soon as a
+/// value rules the type out.
+#[derive(Debug, Clone)]
+pub(crate) struct ColumnStats {
+    pub(crate) n_values: usize,
+    /// Whether every value matches each of the custom type detectors.
+    pub(crate) custom: Vec<bool>,
+    pub(crate) currency: Option<CurrencyStats>,
+    /// Whether every value has the structure of each of the semantic types.
+    pub(crate) semantic: Vec<bool>,
+    /// The most hours of the values written like clock times.
+    pub(crate) clock_hours: Option<u64>,
+    /// How often each byte occurs in the values, if they're all base64-encoded.
+    pub(crate) base64_counts: Option<Box<[usize; 256]>>,
+    pub(crate) code: Option<CodeStats>,
+    pub(crate) date_formats: Option<DateFormatCandidates>,
+    /// The distinct thousands separators of the numbers.
+    pub(crate) thousands_separators: Option<Vec<u8>>,
+    pub(crate) has_non_finite: bool,
+    pub(crate) radix: Option<RadixStats>,
+    /// The range of the integers (including 0).
+    pub(crate) integer_range: Option<(i128, i128)>,
+    pub(crate) integer_bounds: Option<ValueBounds<i128>>,
+    pub(crate) all_integer_literals: bool,
+    /// The range of the numbers, if they all parse as written (i.e. without thousands separators).
+    pub(crate) decimal_range: Option<(f64, f64)>,
+    pub(crate) float_bounds: Option<ValueBounds<f64>>,
+    #[cfg(feature = "phone")]
+    pub(crate) all_phone_numbers: bool,
+    pub(crate) type_tally: TypeTally,
+    pub(crate) text_bounds: Option<ValueBounds<String>>,
+    /// The distinct values, in the order they first occur, unless there are too many.
+    pub(crate) categories: Option<Vec<String>>,
+    pub(crate) constant: Option<String>,
+    pub(crate) is_constant: bool,
+}
+
+impl ColumnStats {
+    /// A summary of no values yet, of a column whose values are all of type `ty`.
+    pub(crate) fn new(ty: Type, options: &ColumnOptions) -> ColumnStats {
+        let is_text = ty == Type::Text;
+        let is_integer = matches!(ty, Type::Unsigned | Type::Signed);
+        let is_number = is_integer || ty == Type::Float;
+        ColumnStats {
+            n_values: 0,
+            custom: vec![true; options.detectors.len()],
+            currency: is_text.then(|| CurrencyStats {
+                agree: true,
+                ..CurrencyStats::default()
+            }),
+            semantic: vec![is_text; SEMANTIC_TYPES.len()],
+            clock_hours: matches!(ty, Type::Time | Type::Text).then_some(0),
+            base64_counts: is_text.then(|| Box::new([0; 256])),
+            code: (ty == Type::Unsigned).then_some(CodeStats {
+                any_padded: false,
+                all_padded: true,
+                first_len: None,
+                same_len: true,
+            }),
+            date_formats: matches!(ty, Type::Date | Type::DateTime | Type::Time)
+                .then(|| DateFormatCandidates::new(&options.type_options)),
+            thousands_separators: is_number.then(Vec::new),
+            has_non_finite: false,
+            radix: (is_text && options.prefixed_integers).then(|| RadixStats {
+                same_radix: true,
+                ..RadixStats::default()
+            }),
+            integer_range: is_integer.then_some((0, 0)),
+            integer_bounds: is_integer.then(ValueBounds::default),
+            all_integer_literals: true,
+            decimal_range: is_number.then_some((f64::MAX, f64::MIN)),
+            float_bounds: (ty == Type::Float).then(ValueBounds::default),
+            #[cfg(feature = "phone")]
+            all_phone_numbers: is_text || is_number,
+            type_tally: TypeTally::default(),
+            // numbers may turn out to be text (e.g. codes)
+            text_bounds: (is_text || is_number).then(ValueBounds::default),
+            categories: Some(vec![]),
+            constant: None,
+            is_constant: true,
+        }
+    }
+
+    /// Adds a (non-missing) value of the column to the summary.
+    pub(crate) fn add(&mut self, value: &str, options: &ColumnOptions) {
+        let type_options = &options.type_options;
+        let decimal_separator = type_options.decimal_separator;
+        let thousands_separators = &type_options.thousands_separators;
+        let trimmed = value.trim();
+        self.n_values += 1;
+
+        for (matches, detector) in self.custom.iter_mut().zip(&options.detectors) {
+            *matches = *matches && detector.matches(trimmed.as_bytes());
+        }
+        if let Some(currency) = &mut self.currency {
+            match parse_currency(value) {
+                Some((symbol, negative)) => {
+                    if symbol.is_some() && currency.symbol.is_none() {
+                        currency.symbol = symbol;
+                    }
+                    if negative.is_some() && currency.negative.is_none() {
+                        currency.negative = negative;
+                    }
+                    currency.agree &= symbol.map_or(true, |symbol| currency.symbol == Some(symbol))
+                        && negative.map_or(true, |negative| currency.negative == Some(negative));
+                }
+                None => self.currency = None,
+            }
+        }
+        for (matches, &(is_type, _)) in self.semantic.iter_mut().zip(&SEMANTIC_TYPES) {
+            *matches = *matches && is_type(trimmed);
+        }
+        if let Some(max_hours) = self.clock_hours {
+            self.clock_hours = clock_duration_hours(trimmed).map(|hours| hours.max(max_hours));
+        }
+        if let Some(counts) = &mut self.base64_counts {
+            if is_base64(trimmed) {
+                for b in value.trim_end_matches('=').bytes() {
+                    counts[usize::from(b)] += 1;
+                }
+            } else {
+                self.base64_counts = None;
+            }
+        }
+        if let Some(code) = &mut self.code {
+            if !trimmed.is_empty() {
+                let is_padded = trimmed.len() > 1 && trimmed.starts_with('0');
+                code.any_padded |= is_padded;
+                code.all_padded &= is_padded;
+                code.same_len &= *code.first_len.get_or_insert(trimmed.len()) == trimmed.len();
+            }
+        }
+        if let Some(date_formats) = &mut self.date_formats {
+            date_formats.add(trimmed, type_options);
+        }
+        if let Some(separators) = &mut self.thousands_separators {
+            if let Some(separator) =
+                thousands_separator(trimmed, decimal_separator, thousands_separators)
+            {
+                if !separators.contains(&separator) {
+                    separators.push(separator);
+                }
+            }
+        }
+        self.has_non_finite |= is_non_finite(value);
+        if let Some(radix) = &mut self.radix {
+            match parse_prefixed_integer(trimmed) {
+                Some((value_radix, integer)) => {
+                    radix.same_radix &= *radix.radix.get_or_insert(value_radix) == value_radix;
+                    radix.min = radix.min.min(integer);
+                    radix.max = radix.max.max(integer);
+                    radix.bounds.add(&integer, trimmed);
+                }
+                None => self.radix = None,
+            }
+        }
+        if let (Some((min, max)), Some(bounds)) =
+            (&mut self.integer_range, &mut self.integer_bounds)
+        {
+            let number = strip_thousands(trimmed, decimal_separator, thousands_separators);
+            if let Ok(integer) = number.parse::<i128>() {
+                *min = integer.min(*min);
+                *max = integer.max(*max);
+                bounds.add(&integer, trimmed);
+            }
+        }
+        self.all_integer_literals &= is_integer_literal(value);
+        if let Some((min, max)) = self.decimal_range {
+            self.decimal_range = parse_decimal(trimmed, decimal_separator)
+                .map(|number| (number.min(min), number.max(max)));
+        }
+        if let Some(bounds) = &mut self.float_bounds {
+            let number = strip_thousands(trimmed, decimal_separator, thousands_separators);
+            if let Some(number) =
+                parse_decimal(&number, decimal_separator).filter(|number| !number.is_nan())
+            {
+                bounds.add(&number, trimmed);
+            }
+        }
+        #[cfg(feature = "phone")]
+        if self.all_phone_numbers {
+            self.all_phone_numbers = crate::field_type::is_phone_number(trimmed);
+        }
+        self.type_tally.add(infer_types(value, type_options));
+        if let Some(bounds) = &mut self.text_bounds {
+            bounds.add(trimmed, trimmed);
+        }
+        if let Some(categories) = &mut self.categories {
+            if !categories.iter().any(|category| category == trimmed) {
+                if categories.len() == options.category_threshold {
+                    self.categories = None;
+                } else {
+                    categories.push(trimmed.to_string());
+                }
+            }
+        }
+        match &self.constant {
+            Some(constant) => self.is_constant &= constant == trimmed,
+            None => self.constant = Some(trimmed.to_string()),
+        }
+    }
+}
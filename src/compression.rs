@@ -0,0 +1,41 @@
+/*!
+Detection of compressed files.
+*/
+use std::io::{self, Read};
+
+use crate::metadata::Compression;
+
+// The magic bytes each compressed format starts with.
+const MAGIC_BYTES: [(&[u8], Compression); 6] = [
+    (&[0x1F, 0x8B], Compression::Gzip),
+    (&[0x28, 0xB5, 0x2F, 0xFD], Compression::Zstd),
+    (b"BZh", Compression::Bzip2),
+    (&[0xFD, b'7', b'z', b'X', b'Z', 0x00], Compression::Xz),
+    // the stream identifier chunk
+    (b"\xFF\x06\x00\x00sNaPpY", Compression::Snappy),
+    // the local header of the first entry
+    (b"PK\x03\x04", Compression::Zip),
+];
+
+/// The compression format of a file starting with `sample`, identified by its magic bytes (a CSV
+/// file can't start with any of them, as each has control characters or isn't valid UTF-8, save
+/// for bzip2's `BZh` which is followed by a block size digit and a binary block header).
+pub(crate) fn detect_compression(sample: &[u8]) -> Option<Compression> {
+    MAGIC_BYTES
+        .iter()
+        .find(|(magic, compression)| {
+            sample.starts_with(magic)
+                && (*compression != Compression::Bzip2
+                    || sample.get(3).is_some_and(|b| (b'1'..=b'9').contains(b))
+                        && sample.get(4..10) == Some(&[0x31, 0x41, 0x59, 0x26, 0x53, 0x59]))
+        })
+        .map(|&(_, compression)| compression)
+}
+
+/// The compression format of the file `reader` reads from, if it's compressed; `reader` is left
+/// at the position following the bytes examined.
+pub(crate) fn read_compression<R: Read>(reader: R) -> io::Result<Option<Compression>> {
+    let mut sample = Vec::with_capacity(10);
+    reader.take(10).read_to_end(&mut sample)?;
+    Ok(detect_compression(&sample))
+}
@@ -0,0 +1,386 @@
+/*!
+Detection and decoding of character encodings.
+
+The detection is built in (and always enabled) rather than delegated to `chardetng` and
+`encoding_rs`, which this crate doesn't depend on: it only tells apart the encodings listed in
+[`Encoding`](../metadata/enum.Encoding.html), all of which it decodes, from byte order marks, the
+patterns of UTF-16 NUL bytes, and which characters the non-ASCII bytes of single-byte encodings
+would be.
+*/
+use std::io::{self, Read, Seek, SeekFrom};
+
+use crate::metadata::{Encoding, EncodingCandidate, InvalidUtf8, Utf8ErrorLocation};
+
+// Fraction of the bytes at even (or odd) positions which must be NUL for BOM-less text to be
+// considered UTF-16 (ASCII characters, which are most of a CSV file, have a NUL byte).
+const MIN_UTF16_NUL_FRACTION: f64 = 0.3;
+
+// Fraction of the bytes which may be NUL or control characters (other than the whitespace and
+// separator characters delimited files use) for a file to be considered text.
+const MAX_CONTROL_FRACTION: f64 = 0.05;
+
+// Number of offsets of the offending bytes reported for a file which isn't text.
+const MAX_REPORTED_OFFSETS: usize = 8;
+
+const UTF8_BOM: [u8; 3] = [0xEF, 0xBB, 0xBF];
+
+/// The byte order mark at the start of `sample` (identified by its encoding), if any.
+pub(crate) fn detect_bom(sample: &[u8]) -> Option<Encoding> {
+    if sample.starts_with(&UTF8_BOM) {
+        Some(Encoding::Utf8)
+    } else if sample.starts_with(&[0xFF, 0xFE]) {
+        Some(Encoding::Utf16Le)
+    } else if sample.starts_with(&[0xFE, 0xFF]) {
+        Some(Encoding::Utf16Be)
+    } else {
+        None
+    }
+}
+
+/// The candidate encodings of a file starting with `sample`, from the likeliest: that of its byte
+/// order mark if it has one, UTF-16 if many of the bytes at every other position (and few of the
+/// others) are NUL, UTF-8 if it's valid UTF-8, and otherwise the single-byte encodings in which
+/// its non-ASCII bytes are plausible characters.
+pub(crate) fn rank_encodings(sample: &[u8]) -> Vec<EncodingCandidate> {
+    let certain = |encoding| {
+        vec![EncodingCandidate {
+            encoding,
+            confidence: 1.0,
+        }]
+    };
+    match detect_encoding(sample) {
+        Encoding::Windows1252 => rank_single_byte_encodings(sample),
+        encoding => certain(encoding),
+    }
+}
+
+// The single-byte encodings, ranked by the fraction of the non-ASCII bytes of `sample` which they
+// decode to plausible characters (ties going to windows-1252, the more common one).
+fn rank_single_byte_encodings(sample: &[u8]) -> Vec<EncodingCandidate> {
+    let n_non_ascii = sample.iter().filter(|&&b| b >= 0x80).count().max(1);
+    let plausibility = |decode: fn(u8) -> char| {
+        sample
+            .iter()
+            .filter(|&&b| b >= 0x80 && is_plausible(decode(b)))
+            .count() as f64
+            / n_non_ascii as f64
+    };
+    let mut candidates = [
+        (Encoding::Windows1252, plausibility(decode_windows_1252)),
+        (Encoding::Iso8859_15, plausibility(decode_iso_8859_15)),
+    ];
+    // a stable sort, so windows-1252 stays first on a tie
+    candidates.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let total = candidates.iter().map(|&(_, score)| score).sum::<f64>();
+    if total == 0.0 {
+        return vec![EncodingCandidate {
+            encoding: Encoding::Windows1252,
+            confidence: 1.0,
+        }];
+    }
+    candidates
+        .iter()
+        .filter(|&&(_, score)| score > 0.0)
+        .map(|&(encoding, score)| EncodingCandidate {
+            encoding,
+            confidence: score / total,
+        })
+        .collect()
+}
+
+// Whether a character is likely in text: not a control character, nor one of the symbols of
+// Latin-1 which ISO-8859-15 replaced because they're rarely used.
+fn is_plausible(chr: char) -> bool {
+    !chr.is_control() && !matches!(chr, '¤' | '¦' | '¨' | '´' | '¸')
+}
+
+/// Decode text in the provided encoding: a single-byte encoding (windows-1252 or ISO-8859-15) byte
+/// by byte, and any other as UTF-8 (replacing invalid sequences), as UTF-16 text is decoded before
+/// it's parsed.
+pub(crate) fn decode_text(bytes: &[u8], encoding: Encoding) -> String {
+    match encoding {
+        Encoding::Windows1252 => bytes
+            .iter()
+            .map(|&byte| decode_windows_1252(byte))
+            .collect(),
+        Encoding::Iso8859_15 => bytes.iter().map(|&byte| decode_iso_8859_15(byte)).collect(),
+        _ => String::from_utf8_lossy(bytes).into_owned(),
+    }
+}
+
+fn decode_windows_1252(byte: u8) -> char {
+    match byte {
+        0x80..=0x9F => WINDOWS_1252_C1[usize::from(byte - 0x80)],
+        _ => char::from(byte),
+    }
+}
+
+fn decode_iso_8859_15(byte: u8) -> char {
+    match byte {
+        0xA4 => '€',
+        0xA6 => 'Š',
+        0xA8 => 'š',
+        0xB4 => 'Ž',
+        0xB8 => 'ž',
+        0xBC => 'Œ',
+        0xBD => 'œ',
+        0xBE => 'Ÿ',
+        _ => char::from(byte),
+    }
+}
+
+// The likeliest encoding of a file starting with `sample`, taking windows-1252 for any single-byte
+// encoding (see `rank_encodings`).
+fn detect_encoding(sample: &[u8]) -> Encoding {
+    if let Some(bom) = detect_bom(sample) {
+        return bom;
+    }
+    let n_pairs = sample.len() / 2;
+    if n_pairs > 0 {
+        let nul_fraction = |parity: usize| {
+            sample
+                .iter()
+                .skip(parity)
+                .step_by(2)
+                .filter(|&&b| b == 0)
+                .count() as f64
+                / n_pairs as f64
+        };
+        let (n_even, n_odd) = (nul_fraction(0), nul_fraction(1));
+        if n_odd >= MIN_UTF16_NUL_FRACTION && n_even < MIN_UTF16_NUL_FRACTION / 10.0 {
+            return Encoding::Utf16Le;
+        }
+        if n_even >= MIN_UTF16_NUL_FRACTION && n_odd < MIN_UTF16_NUL_FRACTION / 10.0 {
+            return Encoding::Utf16Be;
+        }
+    }
+    if is_utf8(sample) {
+        Encoding::Utf8
+    } else {
+        Encoding::Windows1252
+    }
+}
+
+/// If too many of the bytes of `sample` (from a file which isn't UTF-16) are NUL or control
+/// characters for it to be text, the offsets of the first few of them.
+pub(crate) fn binary_byte_offsets(sample: &[u8]) -> Option<Vec<usize>> {
+    let is_control = |b: u8| matches!(b, 0x00..=0x08 | 0x0E..=0x1B | 0x7F);
+    let n_control = sample.iter().filter(|&&b| is_control(b)).count();
+    if n_control == 0 || n_control as f64 <= MAX_CONTROL_FRACTION * sample.len() as f64 {
+        return None;
+    }
+    Some(
+        sample
+            .iter()
+            .enumerate()
+            .filter(|&(_, &b)| is_control(b))
+            .map(|(offset, _)| offset)
+            .take(MAX_REPORTED_OFFSETS)
+            .collect(),
+    )
+}
+
+/// The invalid UTF-8 sequences in `sample`, the start of a file (a character cut off at its end
+/// isn't counted).
+pub(crate) fn find_invalid_utf8(sample: &[u8]) -> InvalidUtf8 {
+    let mut invalid_utf8 = InvalidUtf8 {
+        locations: vec![],
+        count: 0,
+    };
+    let (mut pos, mut line, mut line_counted_to) = (0, 1, 0);
+    while let Err(e) = std::str::from_utf8(&sample[pos..]) {
+        let Some(error_len) = e.error_len() else {
+            break;
+        };
+        let offset = pos + e.valid_up_to();
+        invalid_utf8.count += 1;
+        if invalid_utf8.locations.len() < MAX_REPORTED_OFFSETS {
+            line += bytecount::count(&sample[line_counted_to..offset], b'\n');
+            line_counted_to = offset;
+            invalid_utf8.locations.push(Utf8ErrorLocation {
+                line,
+                offset: offset as u64,
+            });
+        }
+        pos = offset + error_len;
+    }
+    invalid_utf8
+}
+
+// Whether `sample` is valid UTF-8, except maybe for a character cut off at its end.
+fn is_utf8(sample: &[u8]) -> bool {
+    match std::str::from_utf8(sample) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none(),
+    }
+}
+
+// Characters of the bytes 0x80 to 0x9F in windows-1252 (the other bytes are the same as in
+// Latin-1, i.e. their code points); the unassigned ones are mapped to their code points as well.
+const WINDOWS_1252_C1: [char; 32] = [
+    '\u{20AC}', '\u{81}', '\u{201A}', '\u{192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{2C6}', '\u{2030}', '\u{160}', '\u{2039}', '\u{152}', '\u{8D}', '\u{17D}', '\u{8F}',
+    '\u{90}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{2DC}', '\u{2122}', '\u{161}', '\u{203A}', '\u{153}', '\u{9D}', '\u{17E}', '\u{178}',
+];
+
+/// A reader decoding a file in one of the encodings [`Encoding`](metadata/enum.Encoding.html)
+/// describes to UTF-8 (save for Shift-JIS), so that the records of a [`csv`](https://docs.rs/csv)
+/// reader are valid UTF-8. A byte order mark is dropped, and invalid sequences are replaced with
+/// U+FFFD. Files already in UTF-8 (without a byte order mark) are read as-is.
+///
+/// Seeking is only supported relative to the start of the stream (positions are in terms of the
+/// decoded bytes), as for [`DelimiterRewriter`](struct.DelimiterRewriter.html).
+#[derive(Debug)]
+pub struct Decoder<R> {
+    inner: R,
+    encoding: Encoding,
+    // bytes read from `inner`, but not yet decoded
+    pending: Vec<u8>,
+    // decoded bytes, not yet returned (starting at `output_pos`)
+    output: Vec<u8>,
+    output_pos: usize,
+    // position within the decoded stream
+    pos: u64,
+    // whether the start of the stream (which may be a byte order mark) is yet to be decoded
+    at_start: bool,
+    eof: bool,
+}
+
+impl<R: Read> Decoder<R> {
+    /// Create a new `Decoder`, decoding `encoding`.
+    pub fn new(inner: R, encoding: Encoding) -> Decoder<R> {
+        Decoder {
+            inner,
+            encoding,
+            pending: vec![],
+            output: vec![],
+            output_pos: 0,
+            pos: 0,
+            at_start: true,
+            eof: false,
+        }
+    }
+
+    /// Unwrap this `Decoder`, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    fn fill_output(&mut self) -> io::Result<()> {
+        self.output.clear();
+        self.output_pos = 0;
+        while self.output.is_empty() && !self.eof {
+            let mut chunk = [0; 1 << 12];
+            let n_read = self.inner.read(&mut chunk)?;
+            if n_read == 0 {
+                self.eof = true;
+            }
+            self.pending.extend_from_slice(&chunk[..n_read]);
+            if self.at_start {
+                // wait for the whole byte order mark (if there's one)
+                if self.pending.len() < UTF8_BOM.len() && !self.eof {
+                    continue;
+                }
+                self.at_start = false;
+                if detect_bom(&self.pending) == Some(self.encoding) {
+                    let bom_len = if self.encoding == Encoding::Utf8 {
+                        3
+                    } else {
+                        2
+                    };
+                    self.pending.drain(..bom_len);
+                }
+            }
+            match self.encoding {
+                Encoding::Utf8 => {
+                    self.output.append(&mut self.pending);
+                }
+                Encoding::Windows1252 | Encoding::Iso8859_15 => {
+                    let decode = if self.encoding == Encoding::Windows1252 {
+                        decode_windows_1252
+                    } else {
+                        decode_iso_8859_15
+                    };
+                    for &byte in &self.pending {
+                        push_char(&mut self.output, decode(byte));
+                    }
+                    self.pending.clear();
+                }
+                Encoding::Utf16Le | Encoding::Utf16Be => self.decode_utf16(),
+            }
+        }
+        Ok(())
+    }
+
+    fn decode_utf16(&mut self) {
+        let big_endian = self.encoding == Encoding::Utf16Be;
+        let units = self
+            .pending
+            .chunks_exact(2)
+            .map(|pair| {
+                if big_endian {
+                    u16::from_be_bytes([pair[0], pair[1]])
+                } else {
+                    u16::from_le_bytes([pair[0], pair[1]])
+                }
+            })
+            .collect::<Vec<_>>();
+        // a high surrogate cut off by the end of the chunk is kept for the next round
+        let n_units = match units.last() {
+            Some(0xD800..=0xDBFF) if !self.eof => units.len() - 1,
+            _ => units.len(),
+        };
+        for chr in char::decode_utf16(units[..n_units].iter().copied()) {
+            push_char(&mut self.output, chr.unwrap_or(char::REPLACEMENT_CHARACTER));
+        }
+        self.pending.drain(..2 * n_units);
+        if self.eof && !self.pending.is_empty() {
+            // a lone trailing byte
+            self.pending.clear();
+            push_char(&mut self.output, char::REPLACEMENT_CHARACTER);
+        }
+    }
+}
+
+fn push_char(output: &mut Vec<u8>, chr: char) {
+    let mut buf = [0; 4];
+    output.extend_from_slice(chr.encode_utf8(&mut buf).as_bytes());
+}
+
+impl<R: Read> Read for Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.output_pos == self.output.len() {
+            self.fill_output()?;
+        }
+        let available = &self.output[self.output_pos..];
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.output_pos += n;
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl<R: Read + Seek> Seek for Decoder<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match pos {
+            SeekFrom::Start(offset) => {
+                self.inner.seek(SeekFrom::Start(0))?;
+                self.pending.clear();
+                self.output.clear();
+                self.output_pos = 0;
+                self.pos = 0;
+                self.at_start = true;
+                self.eof = false;
+                io::copy(&mut self.by_ref().take(offset), &mut io::sink())?;
+                Ok(self.pos)
+            }
+            SeekFrom::Current(0) => Ok(self.pos),
+            _ => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "Decoder only supports seeking from the start of the stream",
+            )),
+        }
+    }
+}
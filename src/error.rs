@@ -5,6 +5,8 @@ use std::error::Error;
 use std::fmt;
 use std::io;
 
+use crate::metadata::{Compression, InvalidUtf8};
+
 /// An error that occurs while examining a CSV data file.
 #[derive(Debug)]
 pub enum SnifferError {
@@ -14,6 +16,18 @@ pub enum SnifferError {
     Csv(csv::Error),
     /// A CSV sniffing error
     SniffingFailed(String),
+    /// The dialect uses a multi-byte delimiter, which the csv crate doesn't support directly
+    MultiByteDelimiter(Vec<u8>),
+    /// A dialect string couldn't be parsed
+    InvalidDialect(String),
+    /// The file is compressed in a format which can't be decompressed (without enabling a
+    /// feature)
+    UnsupportedCompression(Compression),
+    /// The file doesn't look like text (e.g. it's an image), given the NUL or control bytes at
+    /// the provided offsets (the first few of many)
+    NotTextual(Vec<usize>),
+    /// The sample isn't valid UTF-8 (with `Utf8Handling::Strict`)
+    InvalidUtf8(InvalidUtf8),
 }
 
 /// Ease-of-use `Result` type with a `SnifferError`.
@@ -25,6 +39,35 @@ impl fmt::Display for SnifferError {
             SnifferError::Io(ref err) => write!(f, "IO error: {err}"),
             SnifferError::Csv(ref err) => write!(f, "{err}"),
             SnifferError::SniffingFailed(ref s) => write!(f, "Sniffing failed: {s}"),
+            SnifferError::InvalidDialect(ref s) => write!(f, "Invalid dialect: {s}"),
+            SnifferError::UnsupportedCompression(compression) => {
+                write!(f, "Decompressing {compression} files is not supported")?;
+                match compression {
+                    Compression::Gzip => write!(f, " without the `gzip` feature")?,
+                    Compression::Snappy => write!(f, " without the `snappy` feature")?,
+                    Compression::Zip => write!(f, " without the `zip` feature")?,
+                    _ => {}
+                }
+                Ok(())
+            }
+            SnifferError::NotTextual(ref offsets) => write!(
+                f,
+                "Not a text file: NUL or control bytes at offsets {}, ...",
+                offsets
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            SnifferError::InvalidUtf8(ref invalid_utf8) => {
+                write!(f, "Invalid UTF-8: {invalid_utf8}")
+            }
+            SnifferError::MultiByteDelimiter(ref delim) => write!(
+                f,
+                "Multi-byte delimiter {} is not supported by the csv crate; use \
+                 Dialect::open_rewritten_reader instead",
+                String::from_utf8_lossy(delim)
+            ),
         }
     }
 }
@@ -34,7 +77,12 @@ impl Error for SnifferError {
         match *self {
             SnifferError::Io(ref err) => Some(err),
             SnifferError::Csv(ref err) => Some(err),
-            SnifferError::SniffingFailed(_) => None,
+            SnifferError::SniffingFailed(_)
+            | SnifferError::MultiByteDelimiter(_)
+            | SnifferError::InvalidDialect(_)
+            | SnifferError::UnsupportedCompression(_)
+            | SnifferError::NotTextual(_)
+            | SnifferError::InvalidUtf8(_) => None,
         }
     }
 }
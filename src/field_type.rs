@@ -1,12 +1,19 @@
+use std::borrow::Cow;
 use std::fmt;
+use std::net::{Ipv4Addr, Ipv6Addr};
 
+use crate::column::ValueBounds;
+use crate::json::is_json_container;
+use crate::locale::translate_month_names;
+use crate::metadata::ValueRange;
 use crate::sniffer::DATE_PREFERENCE;
 use bitflags::bitflags;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime};
 use csv::StringRecord;
 use qsv_dateparser::parse_with_preference;
 
 /// Argument used when calling `date_preference` on `Sniffer`.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum DatePreference {
     /// DMY format, dd/mm/yyyy, dd/mm/yy
     DmyFormat,
@@ -14,19 +21,90 @@ pub enum DatePreference {
     MdyFormat,
 }
 
+/// Pairs of (true, false) values recognized as booleans (case-insensitively), unless more are
+/// provided.
+pub(crate) const BOOLEAN_LEXICON: [(&str, &str); 5] = [
+    ("true", "false"),
+    ("yes", "no"),
+    ("y", "n"),
+    ("t", "f"),
+    ("1", "0"),
+];
+
+/// Values recognized as missing values (case-insensitively), unless others are provided. Empty
+/// (or blank) fields are always missing values.
+pub(crate) const NULL_TOKENS: [&str; 5] = ["NA", "N/A", "NULL", "-", "."];
+
+/// Options used when inferring field types.
+#[derive(Debug, Clone)]
+pub(crate) struct TypeOptions {
+    /// Decimal separator of floating-point fields.
+    pub(crate) decimal_separator: u8,
+    /// Pairs of (true, false) values recognized as booleans, in lowercase.
+    pub(crate) booleans: Vec<(String, String)>,
+    /// Values recognized as missing values (besides empty fields).
+    pub(crate) null_tokens: Vec<String>,
+    /// Additional formats of dates, datetimes and times.
+    pub(crate) date_formats: Vec<String>,
+    /// Characters which may group the digits of numbers in threes.
+    pub(crate) thousands_separators: Vec<u8>,
+    /// Names of the months in dates, unless they're in English.
+    pub(crate) month_names: Option<&'static [&'static str; 12]>,
+    /// Whether non-finite values (`NaN`, `inf`, `-Infinity`) are floats.
+    pub(crate) non_finite_floats: bool,
+}
+
+impl TypeOptions {
+    /// `s` with its month names (if any) in English, so that it can be parsed as a date.
+    pub(crate) fn in_english<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        match self.month_names {
+            Some(month_names) => translate_month_names(s, month_names),
+            None => Cow::Borrowed(s),
+        }
+    }
+
+    /// Whether `s` is a missing value (ignoring surrounding whitespace).
+    pub(crate) fn is_null(&self, s: &str) -> bool {
+        let s = s.trim();
+        s.is_empty()
+            || self
+                .null_tokens
+                .iter()
+                .any(|token| token.eq_ignore_ascii_case(s))
+    }
+
+    /// Whether `s` is one of the values of a boolean pair.
+    fn is_boolean(&self, s: &str) -> bool {
+        let s = s.to_lowercase();
+        self.booleans
+            .iter()
+            .any(|(true_value, false_value)| s == *true_value || s == *false_value)
+    }
+
+    /// Whether all of the (lowercase) `values` of a column belong to the same boolean pair.
+    pub(crate) fn is_boolean_column(&self, values: &[String]) -> bool {
+        self.booleans.iter().any(|(true_value, false_value)| {
+            values
+                .iter()
+                .all(|value| value == true_value || value == false_value)
+        })
+    }
+}
+
 bitflags! {
     /// Possible guesses for the field type. Implemented as a bitflag struct (see
     /// [`bitflags`](https://docs.rs/bitflags/)).
     #[derive(Default, Clone, Copy)]
     pub(crate) struct TypeGuesses: u32 {
-        const BOOLEAN   = 0b00000001;
-        const UNSIGNED  = 0b00000010;
-        const SIGNED    = 0b00000100;
-        const FLOAT     = 0b00001000;
-        const DATE      = 0b00010000;
-        const DATETIME  = 0b00100000;
-        const TEXT      = 0b01000000;
-        const NULL      = 0b10000000;
+        const BOOLEAN   = 0b000000001;
+        const UNSIGNED  = 0b000000010;
+        const SIGNED    = 0b000000100;
+        const FLOAT     = 0b000001000;
+        const DATE      = 0b000010000;
+        const DATETIME  = 0b000100000;
+        const TEXT      = 0b001000000;
+        const NULL      = 0b010000000;
+        const TIME      = 0b100000000;
     }
 }
 
@@ -62,6 +140,10 @@ impl TypeGuesses {
         else if self.contains(TypeGuesses::DATE) {
             Type::Date
         }
+        // try time of day
+        else if self.contains(TypeGuesses::TIME) {
+            Type::Time
+        }
         // doesn't fit anything else, it's a text field
         else {
             Type::Text
@@ -76,56 +158,970 @@ impl TypeGuesses {
     }
 }
 
-pub(crate) fn infer_types(s: &str) -> TypeGuesses {
-    if s.is_empty() {
-        // empty fields can be of any type; or rather, of no known type
+pub(crate) fn infer_types(s: &str, options: &TypeOptions) -> TypeGuesses {
+    let decimal_separator = options.decimal_separator;
+    if options.is_null(s) {
+        // empty fields (and other missing values) can be of any type; or rather, of no known type
         return TypeGuesses::all();
     }
     let mut guesses = TypeGuesses::default();
     guesses |= TypeGuesses::TEXT;
 
-    if s.parse::<u64>().is_ok() {
+    // numbers are parsed without their thousands separators (if any)
+    let number = strip_thousands(s, decimal_separator, &options.thousands_separators);
+    if number.parse::<u64>().is_ok() {
         guesses |= TypeGuesses::UNSIGNED;
     }
-    if s.parse::<i64>().is_ok() {
+    if number.parse::<i64>().is_ok() {
         guesses |= TypeGuesses::SIGNED;
     }
-    if infer_boolean(s) {
+    if options.is_boolean(s) {
         guesses |= TypeGuesses::BOOLEAN;
     }
-    if s.parse::<f64>().is_ok() {
+    // integers too large for 64 bits are neither floats (which would lose precision) nor dates
+    let is_big_integer = is_integer_literal(&number)
+        && !guesses.intersects(TypeGuesses::UNSIGNED | TypeGuesses::SIGNED);
+    if is_big_integer {
+        return guesses;
+    }
+    if is_decimal_literal(&number, decimal_separator)
+        && parse_decimal(&number, decimal_separator).is_some()
+    {
         guesses |= TypeGuesses::FLOAT;
     }
+    // the date parser accepts some spellings of `NaN` and infinity, which would make their columns
+    // look like dates to the header-row comparison
+    if is_non_finite(s) {
+        if options.non_finite_floats {
+            guesses |= TypeGuesses::FLOAT;
+        }
+        return guesses;
+    }
+    let date = options.in_english(s);
     if let Ok(parsed_date) = parse_with_preference(
-        s,
+        &date,
         matches!(
             DATE_PREFERENCE.with(|preference| *preference.borrow()),
             DatePreference::DmyFormat
         ),
     ) {
         // get date in rfc3339 format, if it ends with "T00:00:00+00:00"
-        // its a Date type, otherwise, its DateTime.
-        if parsed_date.to_rfc3339().ends_with("T00:00:00+00:00") {
+        // its a Date type, otherwise, its DateTime (as are values with a UTC offset, even if they
+        // happen to be at midnight UTC).
+        if parsed_date.to_rfc3339().ends_with("T00:00:00+00:00") && !has_utc_offset(&date) {
             guesses |= TypeGuesses::DATE;
         } else {
             guesses |= TypeGuesses::DATETIME;
         }
     }
+    if infer_time(s) {
+        guesses |= TypeGuesses::TIME;
+    }
+    // the additional formats, ISO week and ordinal dates, and dates in the preferred day/month
+    // order, which the date parser doesn't know (e.g. `2023-W05-1`, `2023-123` or `25.04.2021`)
+    let day_month_formats = match DATE_PREFERENCE.with(|preference| *preference.borrow()) {
+        DatePreference::DmyFormat => DMY_FORMATS,
+        DatePreference::MdyFormat => MDY_FORMATS,
+    };
+    let is_date = guesses.intersects(TypeGuesses::DATE | TypeGuesses::DATETIME);
+    if let Some(date_guess) = options
+        .date_formats
+        .iter()
+        .map(String::as_str)
+        // chrono also accepts, say, `2016-12` as an ordinal date
+        .chain(
+            ISO_WEEK_ORDINAL_FORMATS
+                .into_iter()
+                .filter(|format| round_trips(&date, format)),
+        )
+        .chain(day_month_formats.iter().copied().filter(|_| !is_date))
+        .find_map(|format| date_guess(&date, format))
+    {
+        guesses |= date_guess;
+    }
     guesses
 }
 
-#[inline]
-fn infer_boolean(s: &str) -> bool {
-    let first5chars: String = s.chars().take(5).collect();
-    let lower_s = first5chars.to_lowercase();
-    matches!(
-        lower_s.as_str(),
-        "0" | "1" | "t" | "f" | "y" | "n" | "true" | "false" | "yes" | "no"
-    )
+/// The guess for `s` if it's a date, datetime or time in the provided format.
+fn date_guess(s: &str, format: &str) -> Option<TypeGuesses> {
+    if DateTime::parse_from_str(s, format).is_ok()
+        || NaiveDateTime::parse_from_str(s, format).is_ok()
+    {
+        Some(TypeGuesses::DATETIME)
+    } else if NaiveDate::parse_from_str(s, format).is_ok() {
+        Some(TypeGuesses::DATE)
+    } else if NaiveTime::parse_from_str(s, format).is_ok() {
+        Some(TypeGuesses::TIME)
+    } else {
+        None
+    }
+}
+
+/// Whether `s` is a time of day (`HH:MM[:SS[.fff]]`, optionally in 12-hour format with AM/PM).
+fn infer_time(s: &str) -> bool {
+    // chrono accepts single-digit minutes and seconds, which aren't likely to be times
+    let mut parts = s.split(':');
+    let is_hour = parts.next().is_some_and(|hour| {
+        (1..=2).contains(&hour.len()) && hour.bytes().all(|b| b.is_ascii_digit())
+    });
+    let mut n_parts = 1;
+    for part in parts {
+        n_parts += 1;
+        let digits = part.bytes().take_while(u8::is_ascii_digit).count();
+        if digits != 2 {
+            return false;
+        }
+    }
+    is_hour
+        && (2..=3).contains(&n_parts)
+        && TIME_FORMATS
+            .iter()
+            .any(|format| NaiveTime::parse_from_str(s, format).is_ok())
+}
+
+/// Whether `s` is an integer (of any size), e.g. `-42` or `123456789012345678901234567890`.
+pub(crate) fn is_integer_literal(s: &str) -> bool {
+    let digits = s.strip_prefix(['+', '-']).unwrap_or(s);
+    !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// The radix and value of `s` if it's a hexadecimal (`0x1A2B`) or octal (`0o777`) integer,
+/// possibly negative.
+pub(crate) fn parse_prefixed_integer(s: &str) -> Option<(u32, i128)> {
+    let (negative, unsigned) = match s.strip_prefix('-') {
+        Some(unsigned) => (true, unsigned),
+        None => (false, s),
+    };
+    let prefixed = unsigned.strip_prefix('0')?;
+    let (radix, digits) = match prefixed.strip_prefix(['x', 'X']) {
+        Some(digits) => (16, digits),
+        None => (8, prefixed.strip_prefix(['o', 'O'])?),
+    };
+    // `from_str_radix` would accept a sign after the prefix
+    if digits.is_empty() || !digits.chars().all(|chr| chr.is_digit(radix)) {
+        return None;
+    }
+    let value = i128::from_str_radix(digits, radix).ok()?;
+    Some((radix, if negative { -value } else { value }))
+}
+
+// Characters recognized as thousands separators (other than the decimal separator), unless
+// specified.
+pub(crate) const THOUSANDS_SEPARATORS: [u8; 4] = [b',', b'.', b' ', b'\''];
+
+/// The thousands separator grouping the integer part of the number `s` (e.g. `,` in
+/// `1,234,567.89`, or `.` in `1.234.567,89`), if its digits are consistently grouped in threes
+/// by one of the `separators`.
+pub(crate) fn thousands_separator(s: &str, decimal_separator: u8, separators: &[u8]) -> Option<u8> {
+    let is_digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    let unsigned = s.strip_prefix(['+', '-']).unwrap_or(s);
+    let integer_part = match unsigned.split_once(char::from(decimal_separator)) {
+        Some((integer_part, fraction)) if is_digits(fraction) => integer_part,
+        Some(_) => return None,
+        None => unsigned,
+    };
+    separators
+        .iter()
+        .copied()
+        .filter(|&separator| separator != decimal_separator)
+        .find(|&separator| {
+            let mut groups = integer_part.split(char::from(separator));
+            let first_group_ok = groups
+                .next()
+                .is_some_and(|group| group.len() <= 3 && is_digits(group));
+            let mut n_groups = 0;
+            let groups_ok = groups.all(|group| {
+                n_groups += 1;
+                group.len() == 3 && is_digits(group)
+            });
+            first_group_ok && groups_ok && n_groups > 0
+        })
+}
+
+/// The number `s` without its thousands separators, if its digits are grouped.
+pub(crate) fn strip_thousands<'a>(
+    s: &'a str,
+    decimal_separator: u8,
+    separators: &[u8],
+) -> Cow<'a, str> {
+    match thousands_separator(s, decimal_separator, separators) {
+        Some(separator) => Cow::Owned(s.replace(char::from(separator), "")),
+        None => Cow::Borrowed(s),
+    }
 }
 
-pub(crate) fn infer_record_types(record: &StringRecord) -> Vec<TypeGuesses> {
-    record.iter().map(infer_types).collect()
+/// Whether `s` is a non-finite floating-point value: `NaN`, or an infinity (`inf` or `Infinity`,
+/// possibly signed), in any case.
+pub(crate) fn is_non_finite(s: &str) -> bool {
+    let s = s.trim();
+    let unsigned = s.strip_prefix(['+', '-']).unwrap_or(s);
+    s.eq_ignore_ascii_case("nan")
+        || unsigned.eq_ignore_ascii_case("inf")
+        || unsigned.eq_ignore_ascii_case("infinity")
+}
+
+/// Whether `s` is written as a decimal number with the provided decimal separator, optionally in
+/// scientific notation (e.g. `-1.5`, `.5`, `1.2e-5`, `1E+10` or `3e8`). Unlike `f64` parsing, this
+/// rules out `inf` and `NaN`; identifiers such as `12E45F` and exponents without digits (`1e`)
+/// aren't numbers either.
+pub(crate) fn is_decimal_literal(s: &str, decimal_separator: u8) -> bool {
+    let is_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    let unsigned = s.strip_prefix(['+', '-']).unwrap_or(s);
+    let (mantissa, exponent) = match unsigned.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (unsigned, None),
+    };
+    let (integer_part, fraction) = mantissa
+        .split_once(char::from(decimal_separator))
+        .unwrap_or((mantissa, ""));
+    let is_mantissa = is_digits(integer_part)
+        && is_digits(fraction)
+        && !(integer_part.is_empty() && fraction.is_empty());
+    let is_exponent = exponent.map_or(true, |exponent| {
+        let digits = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+        !digits.is_empty() && is_digits(digits)
+    });
+    is_mantissa && is_exponent
+}
+
+/// Parses a number using the provided decimal separator. If the separator isn't a period, the
+/// number can't contain any periods (these would be thousands separators, or a different locale).
+pub(crate) fn parse_decimal(s: &str, decimal_separator: u8) -> Option<f64> {
+    if decimal_separator == b'.' {
+        s.parse().ok()
+    } else if s.contains('.') {
+        None
+    } else {
+        s.replacen(char::from(decimal_separator), ".", 1)
+            .parse()
+            .ok()
+    }
+}
+
+pub(crate) fn infer_record_types(record: &StringRecord, options: &TypeOptions) -> Vec<TypeGuesses> {
+    record
+        .iter()
+        .map(|field| infer_types(field, options))
+        .collect()
+}
+
+// Candidate formats of date and datetime columns (as strftime-style specifiers), in the order
+// they're tried. Two-digit years are tried before four-digit ones, since `%Y` also accepts two
+// digits. Formats which depend on the day/month order are listed separately.
+const DATE_FORMATS: [&str; 22] = [
+    "%Y-%m-%d",
+    "%Y/%m/%d",
+    "%Y-%m-%dT%H:%M:%S%:z",
+    "%Y-%m-%dT%H:%M:%S%.f%:z",
+    "%Y-%m-%dT%H:%M:%SZ",
+    "%Y-%m-%dT%H:%M:%S%.fZ",
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S%z",
+    "%Y-%m-%d %H:%M:%S%:z",
+    "%Y-%m-%d %H:%M:%S%.f%:z",
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%d %H:%M",
+    "%Y/%m/%d %H:%M:%S",
+    "%d %B %Y",
+    "%d %b %Y",
+    "%B %d, %Y",
+    "%b %d, %Y",
+    "%a, %d %b %Y %H:%M:%S %z",
+    "%Y%m%dT%H%M%S",
+    "%Y%m%dT%H%M%SZ",
+];
+// ISO 8601 week dates (e.g. `2023-W05-1`, the Monday of week 5) and ordinal dates (`2023-123`,
+// the 123rd day of 2023).
+const ISO_WEEK_ORDINAL_FORMATS: [&str; 3] = ["%G-W%V-%u", "%GW%V%u", "%Y-%j"];
+const MDY_FORMATS: [&str; 10] = [
+    "%m/%d/%y",
+    "%m/%d/%Y",
+    "%m-%d-%y",
+    "%m-%d-%Y",
+    "%m/%d/%y %H:%M",
+    "%m/%d/%Y %H:%M",
+    "%m/%d/%Y %H:%M:%S",
+    "%m/%d/%Y %I:%M %p",
+    "%m/%d/%Y %I:%M:%S %p",
+    "%m.%d.%Y",
+];
+const DMY_FORMATS: [&str; 10] = [
+    "%d/%m/%y",
+    "%d/%m/%Y",
+    "%d-%m-%y",
+    "%d-%m-%Y",
+    "%d/%m/%y %H:%M",
+    "%d/%m/%Y %H:%M",
+    "%d/%m/%Y %H:%M:%S",
+    "%d/%m/%Y %I:%M %p",
+    "%d/%m/%Y %I:%M:%S %p",
+    "%d.%m.%Y",
+];
+
+// Candidate formats of time columns, in the order they're tried.
+const TIME_FORMATS: [&str; 7] = [
+    "%H:%M",
+    "%H:%M:%S",
+    "%H:%M:%S%.f",
+    "%I:%M %p",
+    "%I:%M:%S %p",
+    "%I:%M%p",
+    "%I:%M:%S%p",
+];
+
+/// Whether `s` is a date, datetime or time in the provided format.
+fn parses_as_date(s: &str, format: &str) -> bool {
+    NaiveDate::parse_from_str(s, format).is_ok()
+        || NaiveDateTime::parse_from_str(s, format).is_ok()
+        || DateTime::parse_from_str(s, format).is_ok()
+        || NaiveTime::parse_from_str(s, format).is_ok()
+}
+
+/// Whether formatting `s` (once parsed) in the provided format reproduces it exactly. Parsing is
+/// lenient (e.g. `%z` also accepts `+02:00`, and `%d` accepts `7`), so this tells apart the
+/// formats which parse a value from the one it was written in.
+fn round_trips(s: &str, format: &str) -> bool {
+    if let Ok(datetime) = DateTime::parse_from_str(s, format) {
+        datetime.format(format).to_string() == s
+    } else if let Ok(datetime) = NaiveDateTime::parse_from_str(s, format) {
+        datetime.format(format).to_string() == s
+    } else if let Ok(date) = NaiveDate::parse_from_str(s, format) {
+        date.format(format).to_string() == s
+    } else if let Ok(time) = NaiveTime::parse_from_str(s, format) {
+        time.format(format).to_string() == s
+    } else {
+        false
+    }
+}
+
+/// `s` parsed as a date, datetime or time in the provided format, as a datetime which sorts in the
+/// same order as the values (datetimes with a UTC offset are in UTC, dates are at midnight, and
+/// times are on an arbitrary day), along with whether it round-trips (as by `round_trips`), if
+/// that's to be checked.
+fn parse_date_round_trip(
+    s: &str,
+    format: &str,
+    check_round_trip: bool,
+) -> Option<(NaiveDateTime, bool)> {
+    if let Ok(datetime) = DateTime::parse_from_str(s, format) {
+        Some((
+            datetime.naive_utc(),
+            check_round_trip && datetime.format(format).to_string() == s,
+        ))
+    } else if let Ok(datetime) = NaiveDateTime::parse_from_str(s, format) {
+        Some((
+            datetime,
+            check_round_trip && datetime.format(format).to_string() == s,
+        ))
+    } else if let Ok(date) = NaiveDate::parse_from_str(s, format) {
+        Some((
+            date.and_time(NaiveTime::MIN),
+            check_round_trip && date.format(format).to_string() == s,
+        ))
+    } else {
+        let time = NaiveTime::parse_from_str(s, format).ok()?;
+        Some((
+            NaiveDate::default().and_time(time),
+            check_round_trip && time.format(format).to_string() == s,
+        ))
+    }
+}
+
+/// Whether values in the provided format carry a UTC offset (or are in UTC, as in
+/// `2020-01-01T10:00:00Z`).
+pub(crate) fn is_timezone_aware(format: &str) -> bool {
+    format.contains('z') || format.ends_with('Z')
+}
+
+/// Whether `s` is a datetime with a UTC offset.
+fn has_utc_offset(s: &str) -> bool {
+    DATE_FORMATS
+        .iter()
+        .filter(|format| is_timezone_aware(format))
+        .any(|format| parses_as_date(s, format))
+}
+
+/// The candidate formats of a date, datetime or time column, narrowed down to the ones parsing
+/// every (non-empty) value as the values are added. The additional formats are tried (in order)
+/// first, then the built-in ones, with the formats matching the date preference before the other
+/// day/month order.
+#[derive(Debug, Clone)]
+pub(crate) struct DateFormatCandidates {
+    preference: DatePreference,
+    candidates: Vec<DateFormatCandidate>,
+    n_values: usize,
+}
+
+#[derive(Debug, Clone)]
+struct DateFormatCandidate {
+    format: String,
+    // whether all of the values are written exactly in the format
+    round_trips: bool,
+    bounds: ValueBounds<NaiveDateTime>,
+}
+
+impl DateFormatCandidates {
+    pub(crate) fn new(options: &TypeOptions) -> DateFormatCandidates {
+        let preference = DATE_PREFERENCE.with(|preference| *preference.borrow());
+        let (preferred, other) = match preference {
+            DatePreference::DmyFormat => (DMY_FORMATS, MDY_FORMATS),
+            DatePreference::MdyFormat => (MDY_FORMATS, DMY_FORMATS),
+        };
+        let candidates = options
+            .date_formats
+            .iter()
+            .map(String::as_str)
+            .chain(
+                DATE_FORMATS
+                    .iter()
+                    .chain(&ISO_WEEK_ORDINAL_FORMATS)
+                    .chain(&preferred)
+                    .chain(&other)
+                    .chain(&TIME_FORMATS)
+                    .copied(),
+            )
+            .map(|format| DateFormatCandidate {
+                format: format.to_string(),
+                round_trips: true,
+                bounds: ValueBounds::default(),
+            })
+            .collect();
+        DateFormatCandidates {
+            preference,
+            candidates,
+            n_values: 0,
+        }
+    }
+
+    /// The candidates of a column known to be in the provided format.
+    pub(crate) fn only(format: &str) -> DateFormatCandidates {
+        DateFormatCandidates {
+            preference: DATE_PREFERENCE.with(|preference| *preference.borrow()),
+            candidates: vec![DateFormatCandidate {
+                format: format.to_string(),
+                round_trips: true,
+                bounds: ValueBounds::default(),
+            }],
+            n_values: 0,
+        }
+    }
+
+    /// Drops the candidates which don't parse the (trimmed) value, which is then kept track of in
+    /// the ones left.
+    pub(crate) fn add(&mut self, value: &str, options: &TypeOptions) {
+        if self.candidates.is_empty() {
+            return;
+        }
+        let date = options.in_english(value);
+        if date.is_empty() {
+            return;
+        }
+        self.n_values += 1;
+        self.candidates.retain_mut(|candidate| {
+            let Some((key, round_trips)) =
+                parse_date_round_trip(&date, &candidate.format, candidate.round_trips)
+            else {
+                return false;
+            };
+            candidate.round_trips = round_trips;
+            candidate.bounds.add(&key, value);
+            true
+        });
+    }
+
+    /// The first candidate format which parses all of the values, preferring the formats in which
+    /// all of them are written exactly.
+    ///
+    /// For formats which depend on the day/month order, also returns the order, and whether it was
+    /// only chosen because of the date preference (i.e. the values would fit the other order too).
+    pub(crate) fn format(&self) -> Option<(String, Option<(DatePreference, bool)>)> {
+        if self.n_values == 0 {
+            return None;
+        }
+        let (preferred, other, other_preference) = match self.preference {
+            DatePreference::DmyFormat => (DMY_FORMATS, MDY_FORMATS, DatePreference::MdyFormat),
+            DatePreference::MdyFormat => (MDY_FORMATS, DMY_FORMATS, DatePreference::DmyFormat),
+        };
+        let format = self.best()?.format.as_str();
+        let order = if preferred.contains(&format) {
+            let fits_other = self
+                .candidates
+                .iter()
+                .any(|candidate| other.contains(&candidate.format.as_str()));
+            Some((self.preference, fits_other))
+        } else if other.contains(&format) {
+            Some((other_preference, false))
+        } else {
+            None
+        };
+        Some((format.to_string(), order))
+    }
+
+    /// The smallest and largest of the values, in the format chosen for them.
+    pub(crate) fn range(&self) -> Option<ValueRange> {
+        self.best()?.bounds.range()
+    }
+
+    fn best(&self) -> Option<&DateFormatCandidate> {
+        self.candidates
+            .iter()
+            .find(|candidate| candidate.round_trips)
+            .or(self.candidates.first())
+    }
+}
+
+/// How negative currency amounts are written.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NegativeStyle {
+    /// With a minus sign (e.g. `-$5.00` or `$-5.00`)
+    Minus,
+    /// In parentheses, as in accounting (e.g. `($5.00)`)
+    Parentheses,
+}
+
+// Currency symbols recognized before or after amounts, longest first.
+const CURRENCY_SYMBOLS: [&str; 18] = [
+    "US$", "CA$", "A$", "R$", "$", "€", "£", "¥", "₹", "₩", "₽", "₺", "₪", "₫", "₱", "₦", "฿", "¢",
+];
+
+/// Parses `s` as a currency amount, returning its currency symbol, and how it's written as a
+/// negative amount (if it is). The amount may have thousands separators and either decimal
+/// separator. Negative amounts in parentheses may leave out the symbol (e.g. `(1,000.00)`).
+pub(crate) fn parse_currency(s: &str) -> Option<(Option<&'static str>, Option<NegativeStyle>)> {
+    let s = s.trim();
+    let (s, parenthesized) = match s.strip_prefix('(').and_then(|s| s.strip_suffix(')')) {
+        Some(inner) => (inner.trim(), true),
+        None => (s, false),
+    };
+    let (s, leading_minus) = match s.strip_prefix('-') {
+        Some(rest) => (rest.trim_start(), true),
+        None => (s, false),
+    };
+    let (symbol, amount) = CURRENCY_SYMBOLS
+        .iter()
+        .find_map(|&symbol| {
+            s.strip_prefix(symbol)
+                .map(|amount| (Some(symbol), amount.trim_start()))
+                .or_else(|| {
+                    s.strip_suffix(symbol)
+                        .map(|amount| (Some(symbol), amount.trim_end()))
+                })
+        })
+        .or(parenthesized.then_some((None, s)))?;
+    let (amount, minus) = match amount.strip_prefix('-') {
+        Some(amount) if !leading_minus => (amount, true),
+        _ => (amount, leading_minus),
+    };
+    match (is_amount(amount), minus, parenthesized) {
+        (false, _, _) | (_, true, true) => None,
+        (true, true, false) => Some((symbol, Some(NegativeStyle::Minus))),
+        (true, false, true) => Some((symbol, Some(NegativeStyle::Parentheses))),
+        (true, false, false) => Some((symbol, None)),
+    }
+}
+
+/// Whether a (trimmed) text value has a given structure.
+pub(crate) type TextMatcher = fn(&str) -> bool;
+
+/// Types of text values with a recognizable structure, and how to recognize them (given trimmed
+/// values). Text columns whose values all have one of these structures are inferred as its type,
+/// the first one fitting if several do.
+pub(crate) const SEMANTIC_TYPES: [(TextMatcher, Type); 12] = [
+    (is_percentage, Type::Percent),
+    (is_uuid, Type::Uuid),
+    (is_email, Type::Email),
+    (is_url, Type::Url),
+    (|s| s.parse::<Ipv4Addr>().is_ok(), Type::Ipv4),
+    (|s| s.parse::<Ipv6Addr>().is_ok(), Type::Ipv6),
+    (is_mac_address, Type::MacAddress),
+    (is_semver, Type::SemVer),
+    (is_json_container, Type::Json),
+    (is_wkt, Type::Wkt),
+    (is_iso_duration, Type::Duration),
+    (is_unit_duration, Type::Duration),
+];
+
+/// Whether `s` is a percentage (e.g. `12.5%`, `-3 %` or `12,5%`).
+fn is_percentage(s: &str) -> bool {
+    s.trim()
+        .strip_suffix('%')
+        .map(|number| number.trim_end())
+        .map(|number| number.strip_prefix(['+', '-']).unwrap_or(number))
+        .is_some_and(is_amount)
+}
+
+/// Whether `s` is a UUID in the canonical 8-4-4-4-12 hexadecimal format, with or without braces
+/// (e.g. `{123e4567-e89b-12d3-a456-426614174000}`).
+fn is_uuid(s: &str) -> bool {
+    let s = s
+        .strip_prefix('{')
+        .and_then(|s| s.strip_suffix('}'))
+        .unwrap_or(s);
+    let groups = s.split('-').collect::<Vec<_>>();
+    groups.len() == 5
+        && groups
+            .iter()
+            .zip([8, 4, 4, 4, 12])
+            .all(|(group, len)| group.len() == len && group.bytes().all(|b| b.is_ascii_hexdigit()))
+}
+
+/// Whether `s` is an email address (e.g. `jane.doe+csv@example.co.uk`). Only the overall structure
+/// is checked: a local part, and a domain name with a top-level domain of letters.
+fn is_email(s: &str) -> bool {
+    let Some((local_part, domain)) = s.split_once('@') else {
+        return false;
+    };
+    let is_local_part = !local_part.is_empty()
+        && local_part
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-/=?^_`{|}~.".contains(&b));
+    let is_domain = domain.split_once('.').is_some()
+        && domain.split('.').all(is_domain_label)
+        && domain
+            .rsplit('.')
+            .next()
+            .is_some_and(|tld| tld.len() >= 2 && tld.bytes().all(|b| b.is_ascii_alphabetic()));
+    is_local_part && is_domain
+}
+
+/// Whether `s` is a label of a domain name (e.g. `example` or `my-site`).
+fn is_domain_label(s: &str) -> bool {
+    !s.is_empty()
+        && !s.starts_with('-')
+        && !s.ends_with('-')
+        && s.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-')
+}
+
+/// Whether `s` is a URL with a scheme and a host (e.g. `https://example.com/path?query`).
+fn is_url(s: &str) -> bool {
+    let Some((scheme, rest)) = s.split_once("://") else {
+        return false;
+    };
+    let is_scheme = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"+-.".contains(&b));
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    // ignore any user info and port
+    let host = authority.rsplit('@').next().unwrap_or_default();
+    let host = match host.rsplit_once(':') {
+        Some((host, port)) if port.bytes().all(|b| b.is_ascii_digit()) => host,
+        _ => host,
+    };
+    let is_host =
+        host.split('.').all(is_domain_label) || host.starts_with('[') && host.ends_with(']');
+    is_scheme && is_host && !s.contains(char::is_whitespace)
+}
+
+/// Whether `s` is a MAC address: six pairs of hexadecimal digits separated by colons or hyphens
+/// (e.g. `00:1A:2b:3C:4d:5E`), or three groups of four separated by periods (`001a.2b3c.4d5e`).
+fn is_mac_address(s: &str) -> bool {
+    let is_hex_groups = |separator: char, n_groups: usize, group_len: usize| {
+        let groups = s.split(separator).collect::<Vec<_>>();
+        groups.len() == n_groups
+            && groups.iter().all(|group| {
+                group.len() == group_len && group.bytes().all(|b| b.is_ascii_hexdigit())
+            })
+    };
+    is_hex_groups(':', 6, 2) || is_hex_groups('-', 6, 2) || is_hex_groups('.', 3, 4)
+}
+
+/// Whether `s` is a semantic version: `MAJOR.MINOR.PATCH` (numbers without leading zeros), possibly
+/// prefixed with `v`, and followed by a pre-release (`-rc.1`) and build metadata (`+build.5`).
+fn is_semver(s: &str) -> bool {
+    let is_number = |part: &str| {
+        !part.is_empty()
+            && part.bytes().all(|b| b.is_ascii_digit())
+            && (part == "0" || !part.starts_with('0'))
+    };
+    let is_identifiers = |part: &str| {
+        part.split('.').all(|identifier| {
+            !identifier.is_empty()
+                && identifier
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'-')
+        })
+    };
+    let s = s.strip_prefix('v').unwrap_or(s);
+    let (s, build) = match s.split_once('+') {
+        Some((s, build)) => (s, Some(build)),
+        None => (s, None),
+    };
+    let (version, pre_release) = match s.split_once('-') {
+        Some((version, pre_release)) => (version, Some(pre_release)),
+        None => (s, None),
+    };
+    let numbers = version.split('.').collect::<Vec<_>>();
+    numbers.len() == 3
+        && numbers.iter().all(|number| is_number(number))
+        && pre_release.map_or(true, is_identifiers)
+        && build.map_or(true, is_identifiers)
+}
+
+// Shortest values considered binary data when base64-encoded.
+const MIN_BASE64_LEN: usize = 16;
+
+// Lowest entropy (in bits per character) of base64-encoded binary data. Random bytes have almost 6
+// bits per character (and even the repetitive headers of file formats have over 4), while words
+// which happen to be valid base64 have less than 4.
+const MIN_BASE64_ENTROPY: f64 = 4.0;
+
+/// Whether `s` is long enough to be binary data, and is base64-encoded in either the standard or
+/// the URL-safe alphabet, padded to a multiple of 4 characters.
+pub(crate) fn is_base64(s: &str) -> bool {
+    let data = s.trim_end_matches('=');
+    s.len() >= MIN_BASE64_LEN
+        && s.len() % 4 == 0
+        && s.len() - data.len() <= 2
+        && (data
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'+' || b == b'/')
+            || data
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_'))
+}
+
+/// Whether characters occurring as often as `counts` (indexed by byte, not counting padding) have
+/// the entropy of base64-encoded binary data (rather than, say, of long words).
+pub(crate) fn has_base64_entropy(counts: &[usize; 256]) -> bool {
+    let n_chars: usize = counts.iter().sum();
+    let entropy: f64 = counts
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / n_chars as f64;
+            -p * p.log2()
+        })
+        .sum();
+    entropy >= MIN_BASE64_ENTROPY
+}
+
+/// Whether `s` is an ISO 8601 duration (e.g. `PT2H30M`, `P3DT4H` or `P1W`).
+fn is_iso_duration(s: &str) -> bool {
+    let Some(duration) = s.strip_prefix('P') else {
+        return false;
+    };
+    let (date_part, time_part) = match duration.split_once('T') {
+        Some((date_part, time_part)) => (date_part, Some(time_part)),
+        None => (duration, None),
+    };
+    let n_date_components = iso_duration_components(date_part, &['Y', 'M', 'W', 'D']);
+    let n_time_components =
+        time_part.map(|time_part| iso_duration_components(time_part, &['H', 'M', 'S']));
+    match (n_date_components, n_time_components) {
+        (Some(n_date_components), None) => n_date_components > 0,
+        // the time part can't be empty
+        (Some(_), Some(Some(n_time_components))) => n_time_components > 0,
+        _ => false,
+    }
+}
+
+// The number of components (e.g. `2H`) of a part of an ISO 8601 duration, if they're numbers
+// followed by the provided units, in order.
+fn iso_duration_components(s: &str, units: &[char]) -> Option<usize> {
+    let mut rest = s;
+    let mut units = units.iter();
+    let mut n_components = 0;
+    while !rest.is_empty() {
+        let number_len = rest.find(|c: char| !(c.is_ascii_digit() || c == '.' || c == ','))?;
+        if number_len == 0 {
+            return None;
+        }
+        let unit = rest[number_len..].chars().next()?;
+        units.find(|&&other| other == unit)?;
+        rest = &rest[number_len + unit.len_utf8()..];
+        n_components += 1;
+    }
+    Some(n_components)
+}
+
+// Names of the units of durations (e.g. `3d 4h`), from the largest to the smallest.
+const DURATION_UNITS: [&[&str]; 6] = [
+    &["w", "wk", "wks", "week", "weeks"],
+    &["d", "day", "days"],
+    &["h", "hr", "hrs", "hour", "hours"],
+    &["m", "min", "mins", "minute", "minutes"],
+    &["s", "sec", "secs", "second", "seconds"],
+    &["ms", "msec", "msecs", "millisecond", "milliseconds"],
+];
+
+/// Whether `s` is a duration made up of numbers followed by units, from the largest to the
+/// smallest (e.g. `3d 4h`, `1h30m` or `2 hours, 5 minutes`).
+fn is_unit_duration(s: &str) -> bool {
+    let s = s.to_lowercase();
+    let mut rest = s.as_str();
+    let mut units = DURATION_UNITS.iter();
+    let mut n_components = 0;
+    while !rest.is_empty() {
+        let number_len = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        if !rest[..number_len].contains(|c: char| c.is_ascii_digit()) {
+            return false;
+        }
+        let after_number = rest[number_len..].trim_start();
+        let unit_len = after_number
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(after_number.len());
+        let unit = &after_number[..unit_len];
+        if units.find(|names| names.contains(&unit)).is_none() {
+            return false;
+        }
+        rest = after_number[unit_len..]
+            .trim_start()
+            .trim_start_matches(',')
+            .trim_start();
+        n_components += 1;
+    }
+    n_components > 0
+}
+
+/// The number of hours of `s` if it's a duration written like a clock time (e.g. `01:23:45`,
+/// `27:15:00` or `100:00:00.5`), whose hours aren't limited to a day.
+pub(crate) fn clock_duration_hours(s: &str) -> Option<u64> {
+    let is_sexagesimal =
+        |s: &str| s.len() == 2 && s.bytes().all(|b| b.is_ascii_digit()) && s.as_bytes()[0] < b'6';
+    let mut parts = s.split(':');
+    let (hours, minutes, seconds) = (parts.next()?, parts.next()?, parts.next()?);
+    let whole_seconds = match seconds.split_once('.') {
+        Some((whole_seconds, fraction))
+            if !fraction.is_empty() && fraction.bytes().all(|b| b.is_ascii_digit()) =>
+        {
+            whole_seconds
+        }
+        Some(_) => return None,
+        None => seconds,
+    };
+    if parts.next().is_some() || !is_sexagesimal(minutes) || !is_sexagesimal(whole_seconds) {
+        return None;
+    }
+    hours
+        .parse()
+        .ok()
+        .filter(|_| hours.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Whether `s` looks like a phone number: either in E.164 format (e.g. `+14155552671`), or with 7
+/// to 15 digits grouped by spaces, hyphens, periods or parentheses (e.g. `(415) 555-2671`,
+/// `+44 20 7946 0958` or `020.7946.0958`).
+#[cfg(feature = "phone")]
+pub(crate) fn is_phone_number(s: &str) -> bool {
+    let (international, number) = match s.strip_prefix('+') {
+        Some(number) => (true, number),
+        None => (false, s),
+    };
+    let n_digits = number.bytes().filter(u8::is_ascii_digit).count();
+    let is_grouped = number.contains([' ', '-', '.', '(']);
+    // at most one area code in parentheses, e.g. `(415)`
+    let is_area_code_ok = match (number.find('('), number.find(')')) {
+        (None, None) => true,
+        (Some(open), Some(close)) => {
+            open < close
+                && number.matches('(').count() == 1
+                && number.matches(')').count() == 1
+                && number[open + 1..close].bytes().all(|b| b.is_ascii_digit())
+        }
+        _ => false,
+    };
+    number.starts_with(|c: char| c.is_ascii_digit() || c == '(')
+        && number.ends_with(|c: char| c.is_ascii_digit())
+        && number
+            .bytes()
+            .all(|b| b.is_ascii_digit() || b" -.()".contains(&b))
+        && (7..=15).contains(&n_digits)
+        && is_area_code_ok
+        && (is_grouped || international && !number.starts_with('0'))
+}
+
+// Geometry types of WKT values.
+const WKT_GEOMETRIES: [&str; 7] = [
+    "GEOMETRYCOLLECTION",
+    "MULTILINESTRING",
+    "MULTIPOLYGON",
+    "MULTIPOINT",
+    "LINESTRING",
+    "POLYGON",
+    "POINT",
+];
+
+/// Whether `s` is a geometry in well-known text (e.g. `POINT (30 10)` or
+/// `POLYGON ((30 10, 40 40, 20 40, 30 10))`), optionally with an SRID (`SRID=4326;POINT (30 10)`).
+fn is_wkt(s: &str) -> bool {
+    let s = match s.split_once(';') {
+        Some((srid, geometry)) if srid.to_ascii_uppercase().starts_with("SRID=") => geometry,
+        _ => s,
+    };
+    let upper = s.trim().to_ascii_uppercase();
+    let Some((geometry, rest)) = WKT_GEOMETRIES
+        .iter()
+        .find_map(|geometry| upper.strip_prefix(geometry).map(|rest| (*geometry, rest)))
+    else {
+        return false;
+    };
+    let rest = rest.trim_start();
+    let rest = ["ZM", "Z", "M"]
+        .iter()
+        .find_map(|dimensions| rest.strip_prefix(dimensions))
+        .unwrap_or(rest)
+        .trim_start();
+    if rest == "EMPTY" {
+        return true;
+    }
+    // parentheses must be balanced, around coordinates (or, in collections, other geometries)
+    let mut depth = 0usize;
+    let is_balanced = rest.chars().all(|c| {
+        match c {
+            '(' => depth += 1,
+            ')' if depth > 0 => depth -= 1,
+            ')' => return false,
+            _ => {}
+        }
+        true
+    });
+    let is_coordinates = |c: char| c.is_ascii_digit() || " ,.+-eE()".contains(c);
+    rest.starts_with('(')
+        && rest.ends_with(')')
+        && is_balanced
+        && depth == 0
+        && (geometry == "GEOMETRYCOLLECTION" || rest.chars().all(is_coordinates))
+}
+
+/// Whether `s` is an unsigned number with either decimal separator (and possibly thousands
+/// separators), as written in currency amounts and percentages.
+fn is_amount(s: &str) -> bool {
+    // rule out anything else `f64` parses (e.g. `inf` or `1e5`)
+    s.starts_with(|c: char| c.is_ascii_digit())
+        && s.ends_with(|c: char| c.is_ascii_digit())
+        && s.bytes()
+            .all(|b| b.is_ascii_digit() || b".,' ".contains(&b))
+        && [b'.', b','].iter().any(|&decimal_separator| {
+            let number = strip_thousands(s, decimal_separator, &THOUSANDS_SEPARATORS);
+            parse_decimal(&number, decimal_separator).is_some()
+        })
+}
+
+/// A detector of a domain-specific type (e.g. NHS numbers or FIPS codes), registered with
+/// [`Sniffer::register_type_detector`](struct.Sniffer.html#method.register_type_detector).
+/// Columns whose values all match a detector are inferred as
+/// [`Type::Custom`](enum.Type.html#variant.Custom), with the detector's name. Detectors are
+/// `Send` and `Sync`, so that sniffers (which share them with their clones) are too.
+pub trait TypeDetector: fmt::Debug + Send + Sync {
+    /// Name of the detected type (e.g. `"nhs_number"`).
+    fn name(&self) -> &'static str;
+
+    /// Priority of this detector: if a column matches several detectors, it's inferred as the type
+    /// of the one with the highest priority (or of the first one registered, if tied). Detectors
+    /// take precedence over the built-in types, whatever their priority.
+    ///
+    /// Defaults to 0.
+    fn priority(&self) -> i32 {
+        0
+    }
+
+    /// Whether a (non-missing) value, without surrounding whitespace, is of the detected type.
+    fn matches(&self, value: &[u8]) -> bool;
 }
 
 /// The valid field types for fields in a CSV record.
@@ -137,7 +1133,8 @@ pub enum Type {
     Signed,
     /// Text (any field can be a type)
     Text,
-    /// Boolean (true / false or 0 / 1)
+    /// Boolean (true / false, yes / no, y / n, t / f or 1 / 0, or any pair provided with
+    /// [`Sniffer::boolean_values`](struct.Sniffer.html#method.boolean_values))
     Boolean,
     /// Floating-point
     Float,
@@ -145,9 +1142,143 @@ pub enum Type {
     Date,
     /// DateTime
     DateTime,
+    /// Time of day (e.g. `14:30`, `14:30:05.250` or `2:30 PM`)
+    Time,
+    /// Currency amount (e.g. `$1,234.56`, `€ 12,00` or `(1,000.00 €)`), with the currency symbol
+    /// shared by every value, and how negative amounts are written (`None` if there aren't any)
+    Currency {
+        /// Currency symbol (e.g. `$` or `€`)
+        symbol: &'static str,
+        /// How negative amounts are written
+        negative: Option<NegativeStyle>,
+    },
+    /// Percentage (e.g. `12.5%`), to be scaled by 1/100 when loaded as a number
+    Percent,
+    /// UUID (e.g. `123e4567-e89b-12d3-a456-426614174000`, with or without braces)
+    Uuid,
+    /// Email address (e.g. `jane.doe@example.com`)
+    Email,
+    /// URL, with a scheme and a host (e.g. `https://example.com/path`)
+    Url,
+    /// IPv4 address (e.g. `192.168.0.1`)
+    Ipv4,
+    /// IPv6 address (e.g. `2001:db8::ff00:42:8329`)
+    Ipv6,
+    /// MAC address (e.g. `00:1A:2B:3C:4D:5E`, `00-1A-2B-3C-4D-5E` or `001a.2b3c.4d5e`)
+    MacAddress,
+    /// JSON object or array (e.g. `{"tags": ["a", "b"]}`)
+    Json,
+    /// Duration (e.g. `27:15:00`, `PT2H30M` or `3d 4h`)
+    Duration,
+    /// Semantic version (e.g. `1.2.3`, `v2.0.0-rc.1` or `1.0.0+build.5`)
+    SemVer,
+    /// Geometry in well-known text (e.g. `POINT (30 10)` or `POLYGON ((30 10, 40 40, 20 40, 30 10))`)
+    Wkt,
+    /// Binary data, base64-encoded (e.g. `iVBORw0KGgoAAAANSUhEUgAAAAEAAAAB`)
+    Binary,
+    /// Type recognized by a custom detector (see
+    /// [`Sniffer::register_type_detector`](struct.Sniffer.html#method.register_type_detector)),
+    /// with its name
+    Custom(&'static str),
     /// Null
     NULL,
 }
+// Types in fallback order (narrowest first), with the guesses they correspond to.
+const FALLBACK_CHAIN: [(TypeGuesses, Type); 8] = [
+    (TypeGuesses::BOOLEAN, Type::Boolean),
+    (TypeGuesses::UNSIGNED, Type::Unsigned),
+    (TypeGuesses::SIGNED, Type::Signed),
+    (TypeGuesses::FLOAT, Type::Float),
+    (TypeGuesses::DATETIME, Type::DateTime),
+    (TypeGuesses::DATE, Type::Date),
+    (TypeGuesses::TIME, Type::Time),
+    (TypeGuesses::TEXT, Type::Text),
+];
+
+// Smallest fraction of the values of a column a type must fit to be in its fallback chain.
+const MIN_TYPE_FIT: f64 = 0.5;
+
+/// Counts of the guesses of (the values of) a column, from which its fallback chain and the types
+/// mixed in it are found.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TypeTally {
+    n_guesses: usize,
+    // guesses fitting each type of the fallback chain
+    n_fitting: [usize; FALLBACK_CHAIN.len()],
+    // guesses of each narrowest type, in the order the types first occur
+    n_narrowest: Vec<(Type, usize)>,
+}
+
+impl TypeTally {
+    pub(crate) fn add(&mut self, guess: TypeGuesses) {
+        self.n_guesses += 1;
+        let dates = TypeGuesses::DATETIME | TypeGuesses::DATE | TypeGuesses::TIME;
+        for (n_fitting, &(flag, _)) in self.n_fitting.iter_mut().zip(&FALLBACK_CHAIN) {
+            if guess.contains(flag) && !(dates.contains(flag) && guess.contains(TypeGuesses::FLOAT))
+            {
+                *n_fitting += 1;
+            }
+        }
+        let narrowest = FALLBACK_CHAIN
+            .iter()
+            .find(|&&(flag, ty)| {
+                guess.contains(flag) && !(ty == Type::Boolean && guess.contains(TypeGuesses::FLOAT))
+            })
+            .map_or(Type::Text, |&(_, ty)| ty);
+        match self.n_narrowest.iter_mut().find(|(ty, _)| *ty == narrowest) {
+            Some((_, count)) => *count += 1,
+            None => self.n_narrowest.push((narrowest, 1)),
+        }
+    }
+
+    /// The types fitting at least half of the guesses, in fallback order, with the fraction of the
+    /// guesses each one fits. Numbers, which could also be timestamps, don't count towards dates
+    /// and times.
+    pub(crate) fn fits(&self) -> Vec<(Type, f64)> {
+        if self.n_guesses == 0 {
+            return vec![];
+        }
+        FALLBACK_CHAIN
+            .iter()
+            .zip(self.n_fitting)
+            .map(|(&(_, ty), n_fitting)| (ty, n_fitting as f64 / self.n_guesses as f64))
+            .filter(|&(_, fraction)| fraction >= MIN_TYPE_FIT)
+            .collect()
+    }
+
+    /// The types mixed in the column: the narrowest type in fallback order of each of the guesses,
+    /// with the fraction of the guesses of that type, from the most common. Numbers are counted as
+    /// the widest numeric type among them (so `1` and `2.5` are both floats), and never as
+    /// booleans.
+    pub(crate) fn union(&self) -> Vec<(Type, f64)> {
+        let numbers = [Type::Unsigned, Type::Signed, Type::Float];
+        let widest_number = numbers
+            .iter()
+            .rev()
+            .find(|ty| {
+                self.n_narrowest
+                    .iter()
+                    .any(|(narrowest, _)| narrowest == *ty)
+            })
+            .copied();
+        let mut union: Vec<(Type, usize)> = vec![];
+        for &(mut ty, count) in &self.n_narrowest {
+            if numbers.contains(&ty) {
+                ty = widest_number.unwrap_or(ty);
+            }
+            match union.iter_mut().find(|(member, _)| *member == ty) {
+                Some((_, total)) => *total += count,
+                None => union.push((ty, count)),
+            }
+        }
+        union.sort_by(|(_, a), (_, b)| b.cmp(a));
+        union
+            .into_iter()
+            .map(|(ty, count)| (ty, count as f64 / self.n_guesses as f64))
+            .collect()
+    }
+}
+
 pub(crate) fn get_best_types(guesses: &[TypeGuesses]) -> Vec<Type> {
     guesses.iter().map(|guess| guess.best()).collect()
 }
@@ -164,6 +1295,21 @@ impl fmt::Display for Type {
                 Type::Float => "Float",
                 Type::Date => "Date",
                 Type::DateTime => "DateTime",
+                Type::Time => "Time",
+                Type::Currency { .. } => "Currency",
+                Type::Percent => "Percent",
+                Type::Uuid => "Uuid",
+                Type::Email => "Email",
+                Type::Url => "Url",
+                Type::Ipv4 => "Ipv4",
+                Type::Ipv6 => "Ipv6",
+                Type::MacAddress => "MacAddress",
+                Type::Json => "Json",
+                Type::Binary => "Binary",
+                Type::Wkt => "Wkt",
+                Type::Duration => "Duration",
+                Type::SemVer => "SemVer",
+                Type::Custom(name) => name,
                 Type::NULL => "NULL",
             }
         )
@@ -0,0 +1,48 @@
+/*!
+Reading of gzip-compressed files.
+*/
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+
+use flate2::read::MultiGzDecoder;
+
+use crate::sample::{read_sample, skip_start, SampleSize, SkipStart};
+
+/// The decompressed start of a gzip-compressed file, long enough to fill a sample of the provided
+/// size (`SampleSize::All` decompresses the whole file). Unless the whole file is decompressed,
+/// the bytes after the last line break (a line cut off) are dropped. The sample starts past the
+/// skipped start of the file (if any); returns it along with the numbers of bytes and lines
+/// skipped.
+pub(crate) fn decompress_sample<R: Read>(
+    reader: R,
+    sample_size: SampleSize,
+    skip: Option<SkipStart>,
+) -> io::Result<(Vec<u8>, (u64, usize))> {
+    let mut reader = BufReader::new(MultiGzDecoder::new(reader));
+    let skipped = match skip {
+        Some(skip) => skip_start(&mut reader, skip)?,
+        None => (0, 0),
+    };
+    let (mut sample, len) = read_sample(&mut reader, sample_size)?;
+    sample.truncate(len);
+    Ok((sample, skipped))
+}
+
+/// A reader over the decompressed contents of the gzip-compressed file at `path`, with its first
+/// `n_skip` lines skipped.
+pub(crate) fn open_decompressed(
+    path: &Path,
+    n_skip: usize,
+    record_end: u8,
+) -> io::Result<BufReader<MultiGzDecoder<File>>> {
+    let mut reader = BufReader::new(MultiGzDecoder::new(File::open(path)?));
+    let mut line = vec![];
+    for _ in 0..n_skip {
+        line.clear();
+        if reader.read_until(record_end, &mut line)? == 0 {
+            break;
+        }
+    }
+    Ok(reader)
+}
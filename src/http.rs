@@ -0,0 +1,265 @@
+/*!
+Fetching the start (and the end) of a file over HTTP, with a minimal client: plain `http://` URLs
+only, as there's no TLS support, following a few redirects and failing requests which stall.
+*/
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+// How long connecting, and each read or write, may take before the request fails.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+// Redirects followed before giving up on a request.
+const MAX_REDIRECTS: usize = 5;
+
+/// The bytes of a file a request asks for with a `Range` header.
+#[derive(Debug, Clone, Copy)]
+enum Range {
+    /// The first bytes of the file (`bytes=0-N`).
+    Start(usize),
+    /// The last bytes of the file (`bytes=-N`).
+    End(u64),
+}
+
+/// The body of the response to a `GET` request for `url`, asking for its first `len` bytes only
+/// (if `len` is given) with a `Range` header. A server which ignores the header sends the whole
+/// file, of which only as much as is read is downloaded.
+pub(crate) fn get(url: &str, len: Option<usize>) -> io::Result<Body> {
+    let response = request(url, len.map(Range::Start))?;
+    if response.status != 200 && response.status != 206 {
+        return Err(request_failed(url, &response.status_line));
+    }
+    Ok(response.body)
+}
+
+/// The last (at most) `len` bytes of the file at `url`, along with their offset in the file,
+/// asked for with a `Range` header. `None` if the server doesn't send just them (ignoring the
+/// header, say), rather than downloading the whole file.
+pub(crate) fn get_tail(url: &str, len: u64) -> io::Result<Option<(u64, Vec<u8>)>> {
+    let response = request(url, Some(Range::End(len)))?;
+    // e.g. `bytes 1000-1999/2000`
+    let start = response
+        .content_range
+        .as_deref()
+        .and_then(|range| range.strip_prefix("bytes "))
+        .and_then(|range| range.split_once('-'))
+        .and_then(|(start, _)| start.trim().parse::<u64>().ok());
+    let (206, Some(start)) = (response.status, start) else {
+        return Ok(None);
+    };
+    let mut tail = vec![];
+    response.body.take(len).read_to_end(&mut tail)?;
+    Ok(Some((start, tail)))
+}
+
+struct Response {
+    status: u16,
+    status_line: String,
+    content_range: Option<String>,
+    body: Body,
+}
+
+// Sends a `GET` request for `url` (following redirects), returning the response once its headers
+// are read.
+fn request(url: &str, range: Option<Range>) -> io::Result<Response> {
+    let mut url = url.to_string();
+    for _ in 0..=MAX_REDIRECTS {
+        let (authority, path) = split_url(&url)?;
+        let mut stream = connect(authority)?;
+        let mut request = format!(
+            "GET {path} HTTP/1.1\r\nHost: {authority}\r\nAccept-Encoding: identity\r\n\
+             Connection: close\r\n"
+        );
+        match range {
+            Some(Range::Start(len)) => {
+                request.push_str(&format!("Range: bytes=0-{}\r\n", len.saturating_sub(1)));
+            }
+            Some(Range::End(len)) => request.push_str(&format!("Range: bytes=-{len}\r\n")),
+            None => {}
+        }
+        request.push_str("\r\n");
+        stream.write_all(request.as_bytes())?;
+
+        let mut reader = BufReader::new(stream);
+        let mut status_line = String::new();
+        reader.read_line(&mut status_line)?;
+        let status_line = status_line.trim_end().to_string();
+        let status = status_line
+            .split_whitespace()
+            .nth(1)
+            .and_then(|status| status.parse::<u16>().ok())
+            .ok_or_else(|| invalid_data(format!("invalid HTTP status line: {status_line}")))?;
+        let (mut content_length, mut chunked) = (None, false);
+        let (mut content_range, mut location) = (None, None);
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 {
+                return Err(invalid_data(
+                    "HTTP response ended within its headers".to_string(),
+                ));
+            }
+            let header = line.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            let Some((name, value)) = header.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.parse::<u64>().ok();
+            } else if name.eq_ignore_ascii_case("transfer-encoding") {
+                chunked = value.eq_ignore_ascii_case("chunked");
+            } else if name.eq_ignore_ascii_case("content-range") {
+                content_range = Some(value.to_string());
+            } else if name.eq_ignore_ascii_case("location") {
+                location = Some(value.to_string());
+            }
+        }
+        if matches!(status, 301 | 302 | 303 | 307 | 308) {
+            let location = location.ok_or_else(|| {
+                invalid_data(format!("HTTP redirect without a location: {status_line}"))
+            })?;
+            url = resolve(authority, path, &location);
+            continue;
+        }
+        let body = if chunked {
+            Body::Chunked {
+                reader,
+                chunk_left: 0,
+                done: false,
+            }
+        } else {
+            Body::Plain(reader.take(content_length.unwrap_or(u64::MAX)))
+        };
+        return Ok(Response {
+            status,
+            status_line,
+            content_range,
+            body,
+        });
+    }
+    Err(io::Error::other(format!(
+        "HTTP request for {url} failed: more than {MAX_REDIRECTS} redirects"
+    )))
+}
+
+// The authority (host and port) and path of an `http://` URL.
+fn split_url(url: &str) -> io::Result<(&str, &str)> {
+    let rest = url
+        .strip_prefix("http://")
+        .ok_or_else(|| invalid_input(format!("unsupported URL (only http:// is): {url}")))?;
+    let (authority, path) = match rest.find('/') {
+        Some(pos) => rest.split_at(pos),
+        None => (rest, "/"),
+    };
+    if authority.is_empty() {
+        return Err(invalid_input(format!("URL without a host: {url}")));
+    }
+    Ok((authority, path))
+}
+
+// The URL a redirect to `location` from `path` at `authority` leads to.
+fn resolve(authority: &str, path: &str, location: &str) -> String {
+    if location.contains("://") {
+        location.to_string()
+    } else if let Some(host_path) = location.strip_prefix("//") {
+        format!("http://{host_path}")
+    } else if location.starts_with('/') {
+        format!("http://{authority}{location}")
+    } else {
+        // relative to the directory of the path
+        let dir = &path[..path.rfind('/').map_or(0, |pos| pos + 1)];
+        format!("http://{authority}{dir}{location}")
+    }
+}
+
+// Connects to `authority` (on port 80 unless it has one), trying each of its addresses in turn.
+fn connect(authority: &str) -> io::Result<TcpStream> {
+    let address = if authority.contains(':') {
+        authority.to_string()
+    } else {
+        format!("{authority}:80")
+    };
+    let mut last_err = None;
+    for address in address.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&address, TIMEOUT) {
+            Ok(stream) => {
+                stream.set_read_timeout(Some(TIMEOUT))?;
+                stream.set_write_timeout(Some(TIMEOUT))?;
+                return Ok(stream);
+            }
+            Err(err) => last_err = Some(err),
+        }
+    }
+    Err(last_err.unwrap_or_else(|| invalid_input(format!("no address for host: {authority}"))))
+}
+
+fn request_failed(url: &str, status_line: &str) -> io::Error {
+    io::Error::other(format!("HTTP request for {url} failed: {status_line}"))
+}
+
+fn invalid_input(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, msg)
+}
+
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// The body of an HTTP response.
+pub(crate) enum Body {
+    Plain(io::Take<BufReader<TcpStream>>),
+    Chunked {
+        reader: BufReader<TcpStream>,
+        // bytes left in the current chunk
+        chunk_left: u64,
+        done: bool,
+    },
+}
+
+impl Read for Body {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Body::Plain(reader) => reader.read(buf),
+            Body::Chunked {
+                reader,
+                chunk_left,
+                done,
+            } => {
+                if *done || buf.is_empty() {
+                    return Ok(0);
+                }
+                if *chunk_left == 0 {
+                    let mut line = String::new();
+                    // the line break ending the previous chunk
+                    reader.read_line(&mut line)?;
+                    if line.trim().is_empty() {
+                        line.clear();
+                        reader.read_line(&mut line)?;
+                    }
+                    let size = line.trim().split(';').next().unwrap_or_default();
+                    *chunk_left = u64::from_str_radix(size, 16)
+                        .map_err(|_| invalid_data(format!("invalid HTTP chunk size: {size}")))?;
+                    if *chunk_left == 0 {
+                        *done = true;
+                        return Ok(0);
+                    }
+                }
+                let max = buf
+                    .len()
+                    .min(usize::try_from(*chunk_left).unwrap_or(usize::MAX));
+                let n_read = reader.read(&mut buf[..max])?;
+                if n_read == 0 {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "HTTP response ended within a chunk",
+                    ));
+                }
+                *chunk_left -= n_read as u64;
+                Ok(n_read)
+            }
+        }
+    }
+}
@@ -0,0 +1,176 @@
+/*!
+Validation of JSON documents embedded in fields.
+*/
+
+// Nesting depth beyond which values aren't considered JSON (which bounds the recursion).
+const MAX_DEPTH: usize = 128;
+
+/// Whether `s` is a JSON object or array (e.g. `{"tags": ["a", "b"]}`).
+pub(crate) fn is_json_container(s: &str) -> bool {
+    let s = s.trim();
+    if !s.starts_with(['{', '[']) {
+        return false;
+    }
+    let mut parser = Parser {
+        bytes: s.as_bytes(),
+        pos: 0,
+    };
+    parser.value(0) && {
+        parser.skip_whitespace();
+        parser.pos == parser.bytes.len()
+    }
+}
+
+// A recursive descent parser, which only checks the syntax.
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        let is_next = self.peek() == Some(byte);
+        if is_next {
+            self.pos += 1;
+        }
+        is_next
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_digits(&mut self) -> usize {
+        let start = self.pos;
+        while self.peek().is_some_and(|b| b.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        self.pos - start
+    }
+
+    fn value(&mut self, depth: usize) -> bool {
+        if depth > MAX_DEPTH {
+            return false;
+        }
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'{') => self.object(depth),
+            Some(b'[') => self.array(depth),
+            Some(b'"') => self.string(),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(b't') => self.literal(b"true"),
+            Some(b'f') => self.literal(b"false"),
+            Some(b'n') => self.literal(b"null"),
+            _ => false,
+        }
+    }
+
+    fn object(&mut self, depth: usize) -> bool {
+        self.pos += 1;
+        self.skip_whitespace();
+        if self.eat(b'}') {
+            return true;
+        }
+        loop {
+            self.skip_whitespace();
+            if !self.string() {
+                return false;
+            }
+            self.skip_whitespace();
+            if !self.eat(b':') || !self.value(depth + 1) {
+                return false;
+            }
+            self.skip_whitespace();
+            if self.eat(b'}') {
+                return true;
+            }
+            if !self.eat(b',') {
+                return false;
+            }
+        }
+    }
+
+    fn array(&mut self, depth: usize) -> bool {
+        self.pos += 1;
+        self.skip_whitespace();
+        if self.eat(b']') {
+            return true;
+        }
+        loop {
+            if !self.value(depth + 1) {
+                return false;
+            }
+            self.skip_whitespace();
+            if self.eat(b']') {
+                return true;
+            }
+            if !self.eat(b',') {
+                return false;
+            }
+        }
+    }
+
+    fn string(&mut self) -> bool {
+        if !self.eat(b'"') {
+            return false;
+        }
+        while let Some(byte) = self.peek() {
+            self.pos += 1;
+            match byte {
+                b'"' => return true,
+                b'\\' => match self.peek() {
+                    Some(b'"' | b'\\' | b'/' | b'b' | b'f' | b'n' | b'r' | b't') => self.pos += 1,
+                    Some(b'u') => {
+                        let is_code_point = self
+                            .bytes
+                            .get(self.pos + 1..self.pos + 5)
+                            .is_some_and(|hex| hex.iter().all(u8::is_ascii_hexdigit));
+                        if !is_code_point {
+                            return false;
+                        }
+                        self.pos += 5;
+                    }
+                    _ => return false,
+                },
+                // control characters must be escaped
+                0x00..=0x1F => return false,
+                _ => {}
+            }
+        }
+        false
+    }
+
+    fn number(&mut self) -> bool {
+        self.eat(b'-');
+        // no leading zeros
+        if !self.eat(b'0') && self.skip_digits() == 0 {
+            return false;
+        }
+        if self.eat(b'.') && self.skip_digits() == 0 {
+            return false;
+        }
+        if self.eat(b'e') || self.eat(b'E') {
+            if !self.eat(b'+') {
+                self.eat(b'-');
+            }
+            if self.skip_digits() == 0 {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn literal(&mut self, literal: &[u8]) -> bool {
+        let is_literal = self.bytes[self.pos..].starts_with(literal);
+        if is_literal {
+            self.pos += literal.len();
+        }
+        is_literal
+    }
+}
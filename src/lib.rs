@@ -14,19 +14,111 @@ Alternatively, the [`Sniffer::sniff_path`](struct.Sniffer.html#method.sniff_path
 [`Metadata`](metadata/struct.Metadata.html) object containing the deduced details about the
 underlying CSV input.
 
+Input which can't be rewound, such as standard input, can be sniffed with
+[`Sniffer::sniff_reader_unseekable`](struct.Sniffer.html#method.sniff_reader_unseekable) or
+[`Sniffer::open_reader_unseekable`](struct.Sniffer.html#method.open_reader_unseekable), which
+buffer the sample instead of reading it twice.
+[`Sniffer::sniff_replayable`](struct.Sniffer.html#method.sniff_replayable) also returns a
+[`ReplayReader`](struct.ReplayReader.html), which replays the sample before the rest of the input
+(so that `Dialect::open_reader` can skip its preamble), or hands the sample back as bytes; it works
+for files as well, so that the start of a file isn't read twice. Input which can't be read without
+blocking (such as an upload to an asynchronous web service) can be pushed into a
+[`SampleBuffer`](struct.SampleBuffer.html) as it arrives, and sniffed once there's enough of it,
+whatever the async runtime. With the `http` feature, files at
+`http://` URLs can be sniffed with [`Sniffer::sniff_url`](struct.Sniffer.html#method.sniff_url),
+which only downloads the start of the file (and its end, to detect footer rows).
+
+To debug a file that was sniffed incorrectly,
+[`Sniffer::sniff_with_diagnostics`](struct.Sniffer.html#method.sniff_with_diagnostics) also
+returns the scored candidate dialects that were considered, and why each one was rejected.
+
+Files which are appended to continuously (such as logs) can be re-sniffed with
+[`Sniffer::resniff_appended`](struct.Sniffer.html#method.resniff_appended), which only examines
+the data appended since they were last sniffed, and merges it into their previous metadata.
+
+With the `gzip` feature, gzip-compressed files can be sniffed with `Sniffer::sniff_path` (they're
+recognized by their magic bytes, whatever they're named) and opened with
+[`Dialect::open_gzip_path`](metadata/struct.Dialect.html#method.open_gzip_path). The same goes for
+Snappy-framed files (`*.sz`, as qsv writes them) with the `snappy` feature, and
+[`Dialect::open_snappy_path`](metadata/struct.Dialect.html#method.open_snappy_path). CSV files
+within ZIP archives can be sniffed with
+[`Sniffer::sniff_zip_path`](struct.Sniffer.html#method.sniff_zip_path) (with the `zip` feature).
+Files compressed with zstd, bzip2 or xz are recognized too, but fail with
+`SnifferError::UnsupportedCompression` rather than being sniffed as binary data.
+
+Files that might not be delimited at all can be examined with
+[`Sniffer::sniff_layout_path`](struct.Sniffer.html#method.sniff_layout_path) or
+[`Sniffer::sniff_layout_reader`](struct.Sniffer.html#method.sniff_layout_reader), which also
+recognize fixed-width (whitespace-aligned) files.
+
 This sniffer detects the following metadata about a CSV file:
 
-* Delimiter -- byte character between fields in a record
+* Delimiter -- byte character between fields in a record (or a multi-byte sequence such as `||`),
+  or none for single-column files
 * Has a header row? -- whether or not the first row of the data file provdes column headers
 * Number of preamble rows -- number of rows in a CSV file before the data starts (occasionally used
-in data files to introduce the data)
+  in data files to introduce the data), including comment-prefixed lines such as
+  `# Generated: ...`
 * Quote -- byte character (either ", ', or `) used to quote fields, or that the file has no quotes
+* Quote style -- whether every field is quoted, only fields that need it, only non-numeric fields,
+  or no fields at all
+* Escape -- byte character used to escape quotes within quoted fields (backslash), if any
+* Double quote -- whether quotes within quoted fields are escaped by doubling them
+* Comment -- byte character (either #, / or ;) that starts a comment line, or that the file has no
+  comments
+* Terminator -- line terminator used to end records (CRLF, LF, CR, a mix of these, or the ASCII
+  record separator)
+* Trim -- whether the header row and/or data fields are padded with whitespace (e.g.
+  `value , other`), which should be trimmed
+* Embedded escapes -- whether unquoted fields encode special characters as C-style escape sequences
+  (e.g. a literal `\n`), which need to be unescaped after reading
+* Blank lines -- whether blank lines within the data separate groups of records (and should be
+  skipped), or are records with an empty field
 * Flexible -- whether or not records are all of the same length
-* Is utf8-encoded? -- whether the file is utf-8 encoded
+* Number of footer rows -- number of rows at the end of a CSV file after the data ends (e.g. totals
+  or `END OF REPORT`)
+* Is utf8-encoded? -- whether the file is utf-8 encoded, and if not, the lines and byte offsets of
+  the first few invalid sequences (along with their number in the sample)
+* Encoding -- the character encoding of the file (UTF-8, UTF-16, windows-1252 or ISO-8859-15), along with the other candidate encodings and their confidences when it's ambiguous;
+  UTF-16 files are decoded to be sniffed, and by the readers `Sniffer::open_reader` returns (the
+  detection is built in, without depending on `chardetng` or `encoding_rs`, so other encodings
+  aren't recognized)
+* Byte order mark -- whether the file starts with a (UTF-8 or UTF-16) byte order mark, which is
+  stripped from the field names
+* Is ASCII? -- whether the sample is pure ASCII (so consumers can skip UTF-8 decoding)
+* Has multiline fields? -- whether any quoted field contains a line break (so the file can't be
+  processed line by line)
 * Number of delimiter/fields -- maximum number of delimiters in each row (and therefore number of fields in
-each row)
-* Field names - the name of each field
+  each row)
+* Field names - the name of each field (also as raw bytes, for names not in UTF-8)
 * Types -- the inferred data type of each field in the data table
+* Type confidence -- the fraction of the values of each field which fit its type, and its fallback
+  chain of types (e.g. `Date(0.95) → Text(1.00)` for a field which is mostly dates)
+* Type unions -- optionally, the types mixed in each field, with the fraction of its values of
+  each type (e.g. `[Unsigned(0.70), Text(0.30)]`)
+* Date formats -- the format of each date, datetime and time field (e.g. `%Y-%m-%d` or `%d/%m/%Y`)
+* Timezones -- whether each datetime field is timezone-aware (has a UTC offset) or naive
+* Integer widths -- the narrowest integer type (`i32`, `i64` or `u64`) holding every value of each
+  integer field, or that the values are too large for 64 bits
+* Radixes -- optionally, whether each integer field is written in hexadecimal (`0x1A2B`) or octal
+  (`0o777`)
+* Codes -- whether each field holds zero-padded numeric codes (e.g. ZIP codes like `00420`), typed
+  as text so the leading zeros are kept
+* Categories -- the distinct values of each field with only a few of them (e.g. `open`, `closed`)
+* Constant values -- the value of each field which has the same value in every row
+* Value ranges -- the smallest and largest values of each numeric, date and text field
+* Phone numbers -- which fields hold phone numbers (with the `phone` feature)
+* Coordinates -- which numeric fields hold latitudes and longitudes
+* Number formats -- the decimal and thousands separators of each numeric field (e.g. `1,234.5` or
+  `1.234,5`)
+* Non-finite values -- whether each floating-point field has `NaN` or infinite values
+* Nullability -- whether each field has missing values (e.g. empty, `NA` or `NULL`), and which
+  ones
+* Column quoting -- whether the values of each field are always, never, or only sometimes quoted
+* Empty values -- whether the empty values of each field are unquoted (`a,,b`), quoted (`a,"",b`),
+  or both
+* Decimal separator -- whether floating-point fields use `.` or `,` (as in many European files,
+  which pair `,` decimals with `;` delimiters)
 
 See [`Metadata`](metadata/struct.Metadata.html) for full information about what the sniffer returns.
 
@@ -90,12 +182,43 @@ pub mod error;
 pub mod metadata;
 
 mod sniffer;
-pub use sniffer::Sniffer;
+pub use sniffer::{Sniffer, Strictness, Utf8Handling};
 
 mod sample;
-pub use sample::SampleSize;
+pub use sample::{SampleBuffer, SampleSize, SkipStart};
 
 mod field_type;
-pub use field_type::{DatePreference, Type};
+pub use field_type::{DatePreference, NegativeStyle, Type, TypeDetector};
+
+mod column;
 
 mod snip;
+
+mod replay;
+pub use replay::ReplayReader;
+
+mod json;
+
+mod compression;
+
+mod encoding;
+pub use encoding::Decoder;
+
+#[cfg(feature = "gzip")]
+mod gzip;
+#[cfg(feature = "http")]
+mod http;
+#[cfg(feature = "snappy")]
+mod snappy;
+#[cfg(feature = "snappy")]
+pub use snappy::SnappyDecoder;
+#[cfg(feature = "zip")]
+mod zip;
+#[cfg(feature = "zip")]
+pub use zip::ZipEntryReader;
+
+mod locale;
+pub use locale::Locale;
+
+mod rewrite;
+pub use rewrite::DelimiterRewriter;
@@ -0,0 +1,195 @@
+/*!
+Locale conventions for numbers and dates.
+*/
+use std::borrow::Cow;
+
+use crate::field_type::DatePreference;
+
+const ENGLISH_MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+const GERMAN_MONTHS: [&str; 12] = [
+    "Januar",
+    "Februar",
+    "März",
+    "April",
+    "Mai",
+    "Juni",
+    "Juli",
+    "August",
+    "September",
+    "Oktober",
+    "November",
+    "Dezember",
+];
+const FRENCH_MONTHS: [&str; 12] = [
+    "janvier",
+    "février",
+    "mars",
+    "avril",
+    "mai",
+    "juin",
+    "juillet",
+    "août",
+    "septembre",
+    "octobre",
+    "novembre",
+    "décembre",
+];
+const SPANISH_MONTHS: [&str; 12] = [
+    "enero",
+    "febrero",
+    "marzo",
+    "abril",
+    "mayo",
+    "junio",
+    "julio",
+    "agosto",
+    "septiembre",
+    "octubre",
+    "noviembre",
+    "diciembre",
+];
+const ITALIAN_MONTHS: [&str; 12] = [
+    "gennaio",
+    "febbraio",
+    "marzo",
+    "aprile",
+    "maggio",
+    "giugno",
+    "luglio",
+    "agosto",
+    "settembre",
+    "ottobre",
+    "novembre",
+    "dicembre",
+];
+const PORTUGUESE_MONTHS: [&str; 12] = [
+    "janeiro",
+    "fevereiro",
+    "março",
+    "abril",
+    "maio",
+    "junho",
+    "julho",
+    "agosto",
+    "setembro",
+    "outubro",
+    "novembro",
+    "dezembro",
+];
+
+/// Argument used when calling `locale` on `Sniffer`: the conventions numbers and dates are written
+/// in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Locale {
+    /// English (United States): `1,234.5`, MDY dates
+    EnUs,
+    /// English (United Kingdom): `1,234.5`, DMY dates
+    EnGb,
+    /// German: `1.234,5`, DMY dates, German month names
+    De,
+    /// German (Switzerland): `1'234.5`, DMY dates, German month names
+    DeCh,
+    /// French: `1 234,5`, DMY dates, French month names
+    Fr,
+    /// Spanish: `1.234,5`, DMY dates, Spanish month names
+    Es,
+    /// Italian: `1.234,5`, DMY dates, Italian month names
+    It,
+    /// Portuguese (Brazil): `1.234,5`, DMY dates, Portuguese month names
+    PtBr,
+}
+
+impl Locale {
+    pub(crate) fn decimal_separator(self) -> u8 {
+        match self {
+            Locale::EnUs | Locale::EnGb | Locale::DeCh => b'.',
+            Locale::De | Locale::Fr | Locale::Es | Locale::It | Locale::PtBr => b',',
+        }
+    }
+
+    pub(crate) fn thousands_separator(self) -> u8 {
+        match self {
+            Locale::EnUs | Locale::EnGb => b',',
+            Locale::DeCh => b'\'',
+            Locale::Fr => b' ',
+            Locale::De | Locale::Es | Locale::It | Locale::PtBr => b'.',
+        }
+    }
+
+    pub(crate) fn date_preference(self) -> DatePreference {
+        match self {
+            Locale::EnUs => DatePreference::MdyFormat,
+            _ => DatePreference::DmyFormat,
+        }
+    }
+
+    /// Names of the months, unless they're in English.
+    pub(crate) fn month_names(self) -> Option<&'static [&'static str; 12]> {
+        match self {
+            Locale::EnUs | Locale::EnGb => None,
+            Locale::De | Locale::DeCh => Some(&GERMAN_MONTHS),
+            Locale::Fr => Some(&FRENCH_MONTHS),
+            Locale::Es => Some(&SPANISH_MONTHS),
+            Locale::It => Some(&ITALIAN_MONTHS),
+            Locale::PtBr => Some(&PORTUGUESE_MONTHS),
+        }
+    }
+}
+
+/// `s` with the month names in it (e.g. `März`, or abbreviations such as `Okt`) replaced by their
+/// English names, which dates are parsed in.
+pub(crate) fn translate_month_names<'a>(s: &'a str, month_names: &[&str; 12]) -> Cow<'a, str> {
+    let mut translated = String::new();
+    // end of the part of `s` already copied to `translated`
+    let mut copied = 0;
+    let mut word_start = None;
+    for (i, c) in s.char_indices().chain([(s.len(), ' ')]) {
+        match (word_start, c.is_alphabetic()) {
+            (None, true) => word_start = Some(i),
+            (Some(start), false) => {
+                if let Some(month) = month_number(&s[start..i], month_names) {
+                    translated.push_str(&s[copied..start]);
+                    translated.push_str(ENGLISH_MONTHS[month]);
+                    copied = i;
+                }
+                word_start = None;
+            }
+            _ => {}
+        }
+    }
+    if copied == 0 {
+        return Cow::Borrowed(s);
+    }
+    translated.push_str(&s[copied..]);
+    Cow::Owned(translated)
+}
+
+// The (0-based) number of the month named `word`, or abbreviated to `word` (with at least three
+// letters, and unambiguously).
+fn month_number(word: &str, month_names: &[&str; 12]) -> Option<usize> {
+    let word = word.to_lowercase();
+    let names = month_names.map(str::to_lowercase);
+    names.iter().position(|name| *name == word).or_else(|| {
+        let mut abbreviated = names
+            .iter()
+            .enumerate()
+            .filter(|(_, name)| word.chars().count() >= 3 && name.starts_with(&word));
+        match (abbreviated.next(), abbreviated.next()) {
+            (Some((month, _)), None) => Some(month),
+            _ => None,
+        }
+    })
+}
@@ -3,13 +3,31 @@ CSV metadata types.
 */
 use std::fmt;
 use std::fs::File;
+#[cfg(any(feature = "gzip", feature = "snappy", feature = "zip"))]
+use std::io::BufReader;
 use std::io::{Read, Seek, Write};
 use std::path::Path;
+use std::str::FromStr;
 
 use csv::{Reader, ReaderBuilder};
+#[cfg(feature = "gzip")]
+use flate2::read::MultiGzDecoder;
 use tabwriter::TabWriter;
 
-use crate::{error::Result, field_type::Type, snip::snip_preamble};
+#[cfg(feature = "gzip")]
+use crate::gzip;
+#[cfg(feature = "snappy")]
+use crate::snappy::{self, SnappyDecoder};
+#[cfg(feature = "zip")]
+use crate::zip::{self, ZipEntryReader};
+use crate::{
+    chain::{STATE_STEADYFLEX, STATE_STEADYSTRICT},
+    encoding::Decoder,
+    error::{Result, SnifferError},
+    field_type::{DatePreference, Type},
+    rewrite::DelimiterRewriter,
+    snip::snip_preamble,
+};
 
 /// Primary CSV metadata. Generated by
 /// [`Sniffer::sniff_path`](../struct.Sniffer.html#method.sniff_path) or
@@ -19,27 +37,190 @@ use crate::{error::Result, field_type::Type, snip::snip_preamble};
 pub struct Metadata {
     /// [`Dialect`](struct.Dialect.html) subtype.
     pub dialect: Dialect,
+    /// Character encoding of the file, which files not in UTF-8 should be transcoded from before
+    /// being read (its `Display` is the standard label of the encoding, e.g. `windows-1252`).
+    pub encoding: Encoding,
+    /// The encodings the file may be in, from the likeliest (which is `encoding`), e.g.
+    /// `[windows-1252(0.75), ISO-8859-15(0.25)]` for a file whose non-ASCII bytes make sense in
+    /// both. Unambiguous encodings (e.g. UTF-8) are the only candidate, with a confidence of 1.
+    pub encoding_candidates: Vec<EncodingCandidate>,
+    /// Byte order mark at the start of the file (identified by its encoding), if any, which isn't
+    /// part of the first field name.
+    pub bom: Option<Encoding>,
+    /// Where the sample isn't valid UTF-8, for a file which isn't (`dialect.is_utf8` is false)
+    /// and isn't UTF-16; `None` otherwise.
+    pub invalid_utf8: Option<InvalidUtf8>,
+    /// The name of the entry sniffed, for a CSV file within a ZIP archive (see
+    /// [`Sniffer::sniff_zip_path`](../struct.Sniffer.html#method.sniff_zip_path)).
+    pub zip_entry: Option<String>,
+    /// Whether the sampled bytes are all ASCII (so that the file can be processed without
+    /// decoding multi-byte characters, as far as the sample goes).
+    pub is_ascii: bool,
+    /// Number of bytes of the file in the sample sniffed (as much of the file as the
+    /// [sample size](../struct.Sniffer.html#method.sample_size) covers, or all of it). For a sample
+    /// of `SampleSize::Duration`, this is how much could be sniffed within the time budget, and for
+    /// one of `SampleSize::Adaptive`, how much was needed for a confident answer.
+    pub sampled_bytes: u64,
     /// Average record length (in bytes).
     pub avg_record_len: usize,
     /// (Maximum) number of fields per record.
     pub num_fields: usize,
     /// field/column names
     pub fields: Vec<String>,
+    /// field/column names, as the bytes they're written with (e.g. in windows-1252, where the
+    /// names in `fields` are lossily converted to UTF-8); UTF-16 files are decoded to UTF-8 first
+    pub fields_raw: Vec<Vec<u8>>,
     /// Inferred field types.
     pub types: Vec<Type>,
+    /// Inferred format of each date, datetime and time field, as strftime-style specifiers (e.g.
+    /// `%Y-%m-%d`, `%d/%m/%Y %H:%M` or `%I:%M %p`). `None` for other fields, or if no single format parses all
+    /// of the sampled values.
+    pub date_formats: Vec<Option<String>>,
+    /// Whether each datetime field is timezone-aware or naive (based on its
+    /// [date format](#structfield.date_formats)). `None` for other fields, or if the date format
+    /// isn't known.
+    pub timezones: Vec<Option<Timezone>>,
+    /// The day/month order of each date and datetime field whose
+    /// [date format](#structfield.date_formats) depends on it (e.g. `%d/%m/%Y`). `None` for
+    /// other fields.
+    pub date_orders: Vec<Option<DateOrder>>,
+    /// Whether each field has missing values in the sample (empty fields, or one of the
+    /// [null tokens](../struct.Sniffer.html#method.null_tokens)).
+    pub nullable: Vec<bool>,
+    /// The distinct missing values of each field in the sample, as written (e.g. `""` and `NA`).
+    pub null_tokens: Vec<Vec<String>>,
+    /// The narrowest integer type holding every sampled value of each integer field. Fields of
+    /// integers too large for `i64` and `u64` are inferred as `Text`, with a width of
+    /// `IntegerWidth::Big`. `None` for other fields.
+    pub integer_widths: Vec<Option<IntegerWidth>>,
+    /// The radix of each integer field whose values are hexadecimal (`0x1A2B`) or octal (`0o777`),
+    /// which are only inferred as integers when enabled with
+    /// [`Sniffer::prefixed_integers`](../struct.Sniffer.html#method.prefixed_integers). `None` for
+    /// other fields.
+    pub radixes: Vec<Option<Radix>>,
+    /// The smallest and largest (non-missing) values of each field in the sample: numerically for
+    /// numeric fields, chronologically for date, datetime and time fields, and lexicographically
+    /// for text fields. `None` for other fields, and for fields without any values.
+    pub value_ranges: Vec<Option<ValueRange>>,
+    /// The decimal and thousands separators of each numeric field (e.g. `1,234,567.89` or
+    /// `1.234.567,89`). `None` for other fields.
+    pub number_formats: Vec<Option<NumberFormat>>,
+    /// Whether each floating-point field has non-finite values (`NaN`, `inf` or `-Infinity`), which
+    /// not every format can hold.
+    pub has_non_finite: Vec<bool>,
+    /// Whether each field holds zero-padded codes (e.g. ZIP codes or account numbers like `00420`)
+    /// rather than numbers: values which are all zero-padded, or some of which are and all of
+    /// which are as wide. Such fields are typed as `Text`, so that the leading zeros are kept.
+    pub looks_like_code: Vec<bool>,
+    /// The distinct values of each categorical field (in the order they first occur), i.e. of each
+    /// field with repeated values, but no more distinct values than the category threshold (see
+    /// [`Sniffer::category_threshold`](../struct.Sniffer.html#method.category_threshold)). `None`
+    /// for other fields.
+    pub categories: Vec<Option<Vec<String>>>,
+    /// The value of each constant field, i.e. of each field whose values are all the same (missing
+    /// values aside). `None` for other fields, and for fields without any values.
+    pub constant_values: Vec<Option<String>>,
+    /// Whether each field holds phone numbers (e.g. `+14155552671` or `(415) 555-2671`). Only
+    /// detected with the `phone` feature (all `false` otherwise).
+    pub phone_numbers: Vec<bool>,
+    /// The coordinate held by each field of latitudes or longitudes, i.e. by each numeric field in
+    /// the range of its coordinate, which is either named after it (e.g. `lat` or
+    /// `pickup_longitude`), or is a pair of adjacent (latitude, longitude) floating-point fields.
+    /// `None` for other fields.
+    pub coordinates: Vec<Option<Coordinate>>,
+    /// The fraction of the (non-missing) values of each field which are of its inferred type.
+    pub type_confidences: Vec<f64>,
+    /// The fallback chain of each field: the types fitting at least half of its values, from the
+    /// narrowest to `Text`, e.g. `Date(0.92) → Text(1.00)` for a field which is mostly dates (but
+    /// is inferred as `Text`, since some of its values aren't dates). Only the general types are in
+    /// chains (not, say, `Currency` or `Uuid`).
+    pub type_fallbacks: Vec<Vec<TypeFit>>,
+    /// The types mixed in each field, with the fraction of its (non-missing) values of each type,
+    /// from the most common, e.g. `[Unsigned(0.70), Text(0.30)]` for a field inferred as `Text`
+    /// although most of its values are integers. Only reported when enabled with
+    /// [`Sniffer::type_unions`](../struct.Sniffer.html#method.type_unions), and `None` for fields
+    /// whose values are all of a single type.
+    pub type_unions: Vec<Option<Vec<TypeFit>>>,
+    /// How the values of each field were quoted in the sample (all `Never` for unquoted files).
+    pub column_quoting: Vec<ColumnQuoting>,
+    /// How the empty values of each field were written in the sample: unquoted (`a,,b`), quoted
+    /// (`a,"",b`), or both.
+    pub empty_values: Vec<EmptyValues>,
+    /// Decimal separator used by floating-point fields (`.` or `,`).
+    pub decimal_separator: u8,
+    /// Number of blank lines within the data (see
+    /// [`Dialect::blank_lines`](struct.Dialect.html#structfield.blank_lines)).
+    pub num_blank_lines: usize,
+    /// Number of rows at the end of the file that aren't part of the data (e.g. totals, or
+    /// `END OF REPORT`), including any blank lines among them.
+    pub num_footer_rows: usize,
+    /// Whether any sampled (quoted) field contains a line break, in which case the file can't be
+    /// processed line by line.
+    pub has_multiline_fields: bool,
+    /// The first records of the data (after any preamble and header rows), parsed with the
+    /// sniffed dialect, as many as [`Sniffer::preview_records`](../struct.Sniffer.html#method.preview_records)
+    /// asks for (none by default), e.g. to show a preview of the file before importing it.
+    pub preview: Vec<Vec<String>>,
+    /// [`DialectConfidence`](struct.DialectConfidence.html) in the inferred dialect.
+    pub confidence: DialectConfidence,
 }
 impl fmt::Display for Metadata {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         writeln!(f, "Metadata")?;
         writeln!(f, "========")?;
         writeln!(f, "{}", self.dialect)?;
+        writeln!(f, "{}", self.confidence)?;
+        if let Some(ref zip_entry) = self.zip_entry {
+            writeln!(f, "ZIP entry: {zip_entry}")?;
+        }
+        writeln!(f, "Encoding: {}", self.encoding)?;
+        if self.encoding_candidates.len() > 1 {
+            writeln!(
+                f,
+                "Encoding candidates: {}",
+                self.encoding_candidates
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )?;
+        }
+        writeln!(f, "Has byte order mark?: {}", self.bom.is_some())?;
+        writeln!(f, "Is ASCII?: {}", self.is_ascii)?;
+        if let Some(ref invalid_utf8) = self.invalid_utf8 {
+            writeln!(f, "Invalid UTF-8: {invalid_utf8}")?;
+        }
+        writeln!(f, "Bytes sampled: {}", self.sampled_bytes)?;
         writeln!(f, "Average record length (bytes): {}", self.avg_record_len)?;
         writeln!(f, "Number of fields: {}", self.num_fields)?;
+        writeln!(
+            f,
+            "Decimal separator: {}",
+            char::from(self.decimal_separator)
+        )?;
+        writeln!(f, "Number of blank lines: {}", self.num_blank_lines)?;
+        writeln!(f, "Number of footer rows: {}", self.num_footer_rows)?;
+        writeln!(f, "Has multiline fields?: {}", self.has_multiline_fields)?;
         writeln!(f, "Fields:")?;
 
         let mut tabwtr = TabWriter::new(vec![]);
 
         for (i, ty) in self.types.iter().enumerate() {
+            let ty = match self.date_formats.get(i) {
+                Some(Some(format)) => {
+                    let mut notes = vec![format.clone()];
+                    if let Some(Some(Timezone::Aware)) = self.timezones.get(i) {
+                        notes.push("timezone-aware".to_string());
+                    }
+                    if let Some(Some(order)) = self.date_orders.get(i) {
+                        if order.from_preference {
+                            notes.push("ambiguous day/month order".to_string());
+                        }
+                    }
+                    format!("{ty} ({})", notes.join(", "))
+                }
+                _ => ty.to_string(),
+            };
             writeln!(
                 &mut tabwtr,
                 "\t{}:\t{}\t{}",
@@ -62,27 +243,512 @@ impl fmt::Display for Metadata {
         Ok(())
     }
 }
+impl Metadata {
+    /// Use the sniffed dialect to open a file specified by provided path, decoding it from its
+    /// sniffed encoding if `decode` (see [`open_reader`](#method.open_reader)).
+    pub fn open_path<P: AsRef<Path>>(
+        &self,
+        path: P,
+        decode: bool,
+    ) -> Result<Reader<Decoder<File>>> {
+        self.open_reader(File::open(path)?, decode)
+    }
+
+    /// Use the sniffed dialect to create a `Reader` (from the [`csv`](https://docs.rs/csv) crate)
+    /// using the provided reader. If `decode`, a file not in UTF-8 is decoded from its sniffed
+    /// encoding, so that the records are valid UTF-8 (otherwise it's read as-is, save for a UTF-8
+    /// byte order mark). Fails if unable to read from the reader, or if the dialect uses a
+    /// multi-byte delimiter.
+    pub fn open_reader<R: Read + Seek>(&self, rdr: R, decode: bool) -> Result<Reader<Decoder<R>>> {
+        let encoding = if decode {
+            self.encoding
+        } else {
+            Encoding::Utf8
+        };
+        self.dialect.open_reader(Decoder::new(rdr, encoding))
+    }
+
+    /// The name of the [preset dialect](struct.Dialect.html#method.preset) the sniffed dialect
+    /// most resembles, or `None` if no preset uses the same delimiter.
+    ///
+    /// Presets are scored on the delimiter, quoting, escaping and line terminator. Unquoted files
+    /// are compatible with any quote character and with minimal quoting. Ties are broken in the
+    /// order the presets are listed in (so `excel` is reported rather than `rfc4180`, which
+    /// describes the same format).
+    pub fn closest_preset(&self) -> Option<&'static str> {
+        let dialect = &self.dialect;
+        let mut closest: Option<(&'static str, usize)> = None;
+        for name in PRESETS {
+            // safety: all the preset names are valid
+            let preset = Dialect::preset(name).unwrap();
+            if dialect.delimiter != preset.delimiter
+                || dialect.multibyte_delimiter.is_some()
+                || dialect.single_column
+            {
+                continue;
+            }
+            let score = [
+                dialect.quote == preset.quote || dialect.quote == Quote::None,
+                dialect.quote_style == preset.quote_style
+                    || (dialect.quote_style == QuoteStyle::Never
+                        && preset.quote_style == QuoteStyle::Minimal),
+                dialect.escape == preset.escape,
+                dialect.double_quote == preset.double_quote,
+                dialect.terminator == preset.terminator,
+            ]
+            .into_iter()
+            .filter(|&matches| matches)
+            .count();
+            if closest.map_or(true, |(_, best_score)| score > best_score) {
+                closest = Some((name, score));
+            }
+        }
+        closest.map(|(name, _)| name)
+    }
+}
+
+// Names of the preset dialects, in order of precedence.
+const PRESETS: [&str; 4] = ["excel", "excel-tab", "unix", "rfc4180"];
+
+/// Confidence scores (from 0.0 to 1.0) for the components of an inferred
+/// [`Dialect`](struct.Dialect.html). Low scores mean the choice was close to a coin-flip (e.g. two
+/// delimiters scored similarly). Components specified on the `Sniffer` have a score of 1.0.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct DialectConfidence {
+    /// Confidence in the delimiter.
+    pub delimiter: f64,
+    /// Confidence in the quote character (or that there are no quotes).
+    pub quote: f64,
+    /// Confidence in whether or not there is a header row.
+    pub header: f64,
+    /// Confidence in whether or not the records are flexible.
+    pub flexible: f64,
+}
+impl fmt::Display for DialectConfidence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Dialect confidence:")?;
+        writeln!(f, "\tDelimiter: {:.2}", self.delimiter)?;
+        writeln!(f, "\tQuote: {:.2}", self.quote)?;
+        writeln!(f, "\tHeader: {:.2}", self.header)?;
+        writeln!(f, "\tFlexible: {:.2}", self.flexible)
+    }
+}
+
+/// Diagnostics about how the dialect was chosen, returned by
+/// [`Sniffer::sniff_with_diagnostics`](../struct.Sniffer.html#method.sniff_with_diagnostics).
+#[derive(Debug, Clone, PartialEq)]
+pub struct SniffDiagnostics {
+    /// The candidate dialects (one per delimiter found in the sample) considered when choosing the
+    /// delimiter, from best to worst. If the delimiter was already known (specified, or found from
+    /// the quoting), this only holds that delimiter.
+    pub candidates: Vec<DialectCandidate>,
+}
+impl fmt::Display for SniffDiagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Candidate dialects:")?;
+
+        let mut tabwtr = TabWriter::new(vec![]);
+        writeln!(
+            &mut tabwtr,
+            "\tDelimiter\tState\tProbability\tMax frequency\tPreamble rows\tRejected"
+        )
+        .unwrap_or_default();
+        for candidate in &self.candidates {
+            writeln!(
+                &mut tabwtr,
+                "\t{}\t{:?}\t{:.4}\t{}\t{}\t{}",
+                match candidate.delimiter {
+                    b' ' => "space".to_string(),
+                    delim => delim.escape_ascii().to_string(),
+                },
+                candidate.state,
+                candidate.probability,
+                candidate.max_frequency,
+                candidate.num_preamble_rows,
+                match candidate.rejection {
+                    Some(rejection) => rejection.to_string(),
+                    None => "no (chosen)".into(),
+                }
+            )
+            .unwrap_or_default();
+        }
+        // safety: we just wrote to the tabwriter, so it should be ok to flush and unwrap the inner
+        // vec, which only holds the utf-8 strings written above
+        tabwtr.flush().unwrap();
+        let table = simdutf8::basic::from_utf8(&tabwtr.into_inner().unwrap())
+            .unwrap()
+            .to_string();
+        write!(f, "{table}")
+    }
+}
+
+/// A candidate dialect considered when choosing the delimiter, scored by the Markov chain of its
+/// delimiter's frequency in each sampled row.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DialectCandidate {
+    /// Candidate delimiter.
+    pub delimiter: u8,
+    /// Final state of the candidate's Markov chain.
+    pub state: ChainState,
+    /// Probability of the final state of the candidate's Markov chain.
+    pub probability: f64,
+    /// Maximum number of times the delimiter occurred in a row.
+    pub max_frequency: usize,
+    /// Number of preamble rows, if this delimiter were chosen.
+    pub num_preamble_rows: usize,
+    /// Why this candidate was rejected (`None` for the chosen candidate).
+    pub rejection: Option<Rejection>,
+}
+
+/// The final state of a candidate delimiter's Markov chain. Candidates are ranked by state (in
+/// this order), then by probability.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ChainState {
+    /// The delimiter occurs the same number of times in every row.
+    SteadyStrict,
+    /// The delimiter occurs in every row, but not always the same number of times.
+    SteadyFlexible,
+    /// The delimiter occurs too irregularly to be plausible.
+    Unsteady,
+}
+impl ChainState {
+    pub(crate) fn from_state(state: usize) -> ChainState {
+        match state {
+            STATE_STEADYSTRICT => ChainState::SteadyStrict,
+            STATE_STEADYFLEX => ChainState::SteadyFlexible,
+            _ => ChainState::Unsteady,
+        }
+    }
+}
+
+/// Why a candidate dialect was rejected.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rejection {
+    /// The delimiter's Markov chain never settled into a steady state.
+    Unsteady,
+    /// Another candidate had a better state or a higher probability.
+    Outscored,
+    /// Another delimiter made more fields parse as numbers (e.g. `;` with `,` decimals, rather
+    /// than `,`).
+    DecimalSeparator,
+    /// The delimiter (a space) only pads the fields around another delimiter.
+    Padding,
+    /// The delimiter is a letter or a digit, which are never plausible delimiters (unless listed
+    /// in the [`delimiter_candidates`](../struct.Sniffer.html#method.delimiter_candidates)).
+    Alphanumeric,
+    /// The file is too small (a row or two) to tell delimiters apart by their frequencies, and
+    /// another delimiter was more plausible, by its punctuation class and by how much the first
+    /// row looks like a header.
+    Punctuation,
+}
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match *self {
+                Rejection::Unsteady => "unsteady",
+                Rejection::Outscored => "outscored",
+                Rejection::DecimalSeparator => "decimal separator",
+                Rejection::Padding => "padding",
+                Rejection::Alphanumeric => "alphanumeric",
+                Rejection::Punctuation => "punctuation",
+            }
+        )
+    }
+}
+
+/// The layout of a file examined by
+/// [`Sniffer::sniff_layout_path`](../struct.Sniffer.html#method.sniff_layout_path) or
+/// [`Sniffer::sniff_layout_reader`](../struct.Sniffer.html#method.sniff_layout_reader).
+#[derive(Debug, Clone, PartialEq)]
+// the layout is only returned once per sniff, so the size of the metadata doesn't matter
+#[allow(clippy::large_enum_variant)]
+pub enum SniffResult {
+    /// A delimited file, with its [`Metadata`](struct.Metadata.html).
+    Delimited(Metadata),
+    /// A fixed-width (or whitespace-aligned) file, with the byte offset at which each column
+    /// starts.
+    FixedWidth(Vec<usize>),
+}
+
+/// How the values of a column are quoted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColumnQuoting {
+    /// Every (non-empty) value is quoted.
+    Always,
+    /// No value is quoted.
+    Never,
+    /// Some values are quoted, and some aren't.
+    Mixed,
+}
+
+/// How the empty values of a column are written.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EmptyValues {
+    /// The column has no empty values.
+    Absent,
+    /// Empty values are unquoted (`a,,b`), which some loaders read as NULL.
+    Unquoted,
+    /// Empty values are quoted (`a,"",b`), which some loaders read as empty strings.
+    Quoted,
+    /// Some empty values are quoted, and some aren't.
+    Mixed,
+}
+
+/// Whether the values of a datetime column carry a timezone.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Timezone {
+    /// Values are local times, without a UTC offset (e.g. `2020-01-01 10:00:00`).
+    Naive,
+    /// Values have a UTC offset, or are in UTC (e.g. `2020-01-01T10:00:00+02:00`, or
+    /// `2020-01-01T10:00:00Z`).
+    Aware,
+}
+
+/// The conventions of the numbers of a numeric column.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NumberFormat {
+    /// Decimal separator (`.` or `,`).
+    pub decimal_separator: u8,
+    /// Character grouping the digits of the integer part in threes (`,`, `.`, a space or `'`), if
+    /// any value is grouped.
+    pub thousands_separator: Option<u8>,
+}
+
+/// How well a type fits the values of a column.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TypeFit {
+    /// The type.
+    pub ty: Type,
+    /// Fraction of the (non-missing) values of the column which are of this type.
+    pub confidence: f64,
+}
+impl fmt::Display for TypeFit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}({:.2})", self.ty, self.confidence)
+    }
+}
+
+/// The coordinate held by a numeric column.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Coordinate {
+    /// Latitude, in degrees (between -90 and 90).
+    Latitude,
+    /// Longitude, in degrees (between -180 and 180).
+    Longitude,
+}
+
+/// The narrowest integer type holding every value of an integer column.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IntegerWidth {
+    /// 32-bit signed integers.
+    I32,
+    /// 64-bit signed integers.
+    I64,
+    /// 64-bit unsigned integers (values too large for `i64`).
+    U64,
+    /// Integers too large for `i64` and `u64`.
+    Big,
+}
+
+/// The character encoding of a file.
+///
+/// Only the encodings the built-in detection tells apart are listed: files in any other encoding
+/// (e.g. Shift-JIS, GBK or Windows-1251) are reported as one of these, usually windows-1252. More may
+/// be added, so matches on this need a wildcard arm.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub enum Encoding {
+    /// UTF-8 (or ASCII), with or without a byte order mark.
+    Utf8,
+    /// UTF-16, little-endian.
+    Utf16Le,
+    /// UTF-16, big-endian.
+    Utf16Be,
+    /// Windows-1252, the usual encoding of files which aren't valid UTF-8 (and a superset of
+    /// Latin-1).
+    Windows1252,
+    /// ISO-8859-15 (Latin-9), which differs from Latin-1 in eight characters, such as `€` for `¤`.
+    Iso8859_15,
+}
+impl fmt::Display for Encoding {
+    /// The label of the encoding in the [WHATWG Encoding Standard](https://encoding.spec.whatwg.org),
+    /// e.g. `UTF-16LE` or `windows-1252`, as understood by most decoders.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Utf16Le => "UTF-16LE",
+            Encoding::Utf16Be => "UTF-16BE",
+            Encoding::Windows1252 => "windows-1252",
+            Encoding::Iso8859_15 => "ISO-8859-15",
+        })
+    }
+}
+
+/// A candidate encoding of a file, and how likely it is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EncodingCandidate {
+    /// The encoding.
+    pub encoding: Encoding,
+    /// Confidence in the encoding, relative to the other candidates (the confidences add up to 1).
+    pub confidence: f64,
+}
+impl fmt::Display for EncodingCandidate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}({:.2})", self.encoding, self.confidence)
+    }
+}
+
+/// The compression format of a file.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Compression {
+    /// gzip (`*.gz`), which the `gzip` feature decompresses.
+    Gzip,
+    /// Zstandard (`*.zst`).
+    Zstd,
+    /// bzip2 (`*.bz2`).
+    Bzip2,
+    /// xz (`*.xz`).
+    Xz,
+    /// Snappy, in its framing format (`*.sz`), which the `snappy` feature decompresses.
+    Snappy,
+    /// A ZIP archive (`*.zip`), whose CSV files the `zip` feature reads.
+    Zip,
+}
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+            Compression::Bzip2 => "bzip2",
+            Compression::Xz => "xz",
+            Compression::Snappy => "snappy",
+            Compression::Zip => "ZIP",
+        })
+    }
+}
+
+/// The location of an invalid UTF-8 sequence within a file.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Utf8ErrorLocation {
+    /// The line (counting from 1) the sequence is on.
+    pub line: usize,
+    /// The byte offset of the sequence from the start of the file.
+    pub offset: u64,
+}
+
+/// The invalid UTF-8 sequences found in the sample of a file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvalidUtf8 {
+    /// The locations of the first few invalid sequences.
+    pub locations: Vec<Utf8ErrorLocation>,
+    /// The number of invalid sequences in the sample (there may be more further on).
+    pub count: usize,
+}
+impl fmt::Display for InvalidUtf8 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} sequence(s) in the sample", self.count)?;
+        if !self.locations.is_empty() {
+            let locations = self
+                .locations
+                .iter()
+                .map(|location| format!("line {} (byte {})", location.line, location.offset))
+                .collect::<Vec<_>>();
+            write!(f, ", first at {}", locations.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// The smallest and largest values of a column, as written.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValueRange {
+    /// The smallest value.
+    pub min: String,
+    /// The largest value.
+    pub max: String,
+}
+
+/// The radix of the values of an integer column written with a radix prefix.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Radix {
+    /// Hexadecimal values (e.g. `0x1A2B`).
+    Hexadecimal,
+    /// Octal values (e.g. `0o777`).
+    Octal,
+}
+
+/// The day/month order of a date column.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DateOrder {
+    /// The order in which the values were interpreted.
+    pub order: DatePreference,
+    /// Whether every sampled value was ambiguous (e.g. `03/04/2021`), so the order was chosen by
+    /// the sniffer's date preference rather than by the values (e.g. `25/04/2021`).
+    pub from_preference: bool,
+}
 
 /// Dialect-level metadata. This type encapsulates the details to be used to derive a
 /// `ReaderBuilder` object (in the [`csv`](https://docs.rs/csv) crate).
 #[derive(Clone)]
 pub struct Dialect {
-    /// CSV delimiter (field separator).
+    /// CSV delimiter (field separator). If the file uses a multi-byte delimiter, this is the byte
+    /// it gets rewritten to by [`open_rewritten_reader`](#method.open_rewritten_reader).
     pub delimiter: u8,
+    /// Multi-byte delimiter (e.g. `||`), if the file uses one.
+    pub multibyte_delimiter: Option<Vec<u8>>,
+    /// Whether the file only has a single column (i.e. no delimiter was found). The `delimiter`
+    /// of a single-column file is a byte which doesn't occur in the sample, so that the csv
+    /// reader doesn't split the records.
+    pub single_column: bool,
     /// [`Header`](struct.Header.html) subtype (header row boolean and number of preamble rows).
     pub header: Header,
     /// Record quoting details.
     pub quote: Quote,
+    /// How fields are quoted (e.g. every field, or only when necessary).
+    pub quote_style: QuoteStyle,
+    /// Escape character used for quotes within quoted fields (if any).
+    pub escape: Escape,
+    /// Whether two quotes in a row within a quoted field are interpreted as an escaped quote.
+    pub double_quote: bool,
+    /// Comment character; records starting with this character are ignored. `//` comments are
+    /// sniffed as `/`, which ignores any record starting with a `/` (so it's only inferred if no
+    /// other line does).
+    pub comment: Comment,
+    /// Line terminator used to end records.
+    pub terminator: Terminator,
+    /// Whether every record ends with a dangling delimiter (producing an empty trailing field).
+    pub trailing_delimiter: bool,
+    /// Whether unquoted fields encode special characters as C-style escape sequences (e.g. a
+    /// literal `\n` for a newline, or `\t` for a tab), which need to be unescaped after reading.
+    pub embedded_escapes: bool,
+    /// Whitespace trimming of fields (for files padding fields like `value , other`).
+    pub trim: Trim,
+    /// How blank lines within the data should be interpreted.
+    pub blank_lines: BlankLinePolicy,
     /// Whether or not the number of fields in a record is allowed to change.
     pub flexible: bool,
-    /// Whether the file is utf-8 encoded.
+    /// Whether the file is utf-8 encoded, i.e. whether every line examined was valid utf-8. Files
+    /// which aren't (e.g. Latin-1 files) are still sniffed, with invalid sequences replaced.
     pub is_utf8: bool,
 }
 impl PartialEq for Dialect {
     fn eq(&self, other: &Dialect) -> bool {
         self.delimiter == other.delimiter
+            && self.multibyte_delimiter == other.multibyte_delimiter
+            && self.single_column == other.single_column
             && self.header == other.header
             && self.quote == other.quote
+            && self.quote_style == other.quote_style
+            && self.escape == other.escape
+            && self.double_quote == other.double_quote
+            && self.comment == other.comment
+            && self.terminator == other.terminator
+            && self.trailing_delimiter == other.trailing_delimiter
+            && self.embedded_escapes == other.embedded_escapes
+            && self.trim == other.trim
+            && self.blank_lines == other.blank_lines
             && self.flexible == other.flexible
             && self.is_utf8 == other.is_utf8
     }
@@ -91,8 +757,25 @@ impl fmt::Debug for Dialect {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.debug_struct("Dialect")
             .field("delimiter", &char::from(self.delimiter))
+            .field(
+                "multibyte_delimiter",
+                &self
+                    .multibyte_delimiter
+                    .as_ref()
+                    .map(|delim| String::from_utf8_lossy(delim)),
+            )
+            .field("single_column", &self.single_column)
             .field("header", &self.header)
             .field("quote", &self.quote)
+            .field("quote_style", &self.quote_style)
+            .field("escape", &self.escape)
+            .field("double_quote", &self.double_quote)
+            .field("comment", &self.comment)
+            .field("terminator", &self.terminator)
+            .field("trailing_delimiter", &self.trailing_delimiter)
+            .field("embedded_escapes", &self.embedded_escapes)
+            .field("trim", &self.trim)
+            .field("blank_lines", &self.blank_lines)
             .field("flexible", &self.flexible)
             .field("is_utf8", &self.is_utf8)
             .finish()
@@ -101,13 +784,26 @@ impl fmt::Debug for Dialect {
 impl fmt::Display for Dialect {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         writeln!(f, "Dialect:")?;
-        writeln!(f, "\tDelimiter: {}", char::from(self.delimiter))?;
+        match self.multibyte_delimiter {
+            Some(ref delim) => writeln!(f, "\tDelimiter: {}", String::from_utf8_lossy(delim))?,
+            None if self.single_column => writeln!(f, "\tDelimiter: none (single column)")?,
+            // show control characters (like the unit separator) escaped, as they're invisible
+            None if self.delimiter.is_ascii_control() && self.delimiter != b'\t' => {
+                writeln!(f, "\tDelimiter: {}", self.delimiter.escape_ascii())?
+            }
+            None => writeln!(f, "\tDelimiter: {}", char::from(self.delimiter))?,
+        }
         writeln!(f, "\tHas header row?: {}", self.header.has_header_row)?;
         writeln!(
             f,
             "\tNumber of preamble rows: {}",
             self.header.num_preamble_rows
         )?;
+        writeln!(
+            f,
+            "\tNumber of comment-prefixed preamble rows: {}",
+            self.header.num_comment_preamble_rows
+        )?;
         writeln!(
             f,
             "\tQuote character: {}",
@@ -116,21 +812,292 @@ impl fmt::Display for Dialect {
                 Quote::None => "none".into(),
             }
         )?;
+        writeln!(
+            f,
+            "\tQuote style: {}",
+            match self.quote_style {
+                QuoteStyle::Always => "always",
+                QuoteStyle::Minimal => "minimal",
+                QuoteStyle::NonNumeric => "non-numeric",
+                QuoteStyle::Never => "never",
+            }
+        )?;
+        writeln!(
+            f,
+            "\tEscape character: {}",
+            match self.escape {
+                Escape::Enabled(chr) => format!("{}", char::from(chr)),
+                Escape::Disabled => "none".into(),
+            }
+        )?;
+        writeln!(f, "\tDoubled quotes escaped?: {}", self.double_quote)?;
+        writeln!(
+            f,
+            "\tComment character: {}",
+            match self.comment {
+                Comment::Enabled(chr) => format!("{}", char::from(chr)),
+                Comment::Disabled => "none".into(),
+            }
+        )?;
+        writeln!(
+            f,
+            "\tLine terminator: {}",
+            match self.terminator {
+                Terminator::CRLF => "CRLF",
+                Terminator::LF => "LF",
+                Terminator::CR => "CR",
+                Terminator::Mixed => "mixed",
+                Terminator::RS => "RS",
+            }
+        )?;
+        writeln!(f, "\tTrailing delimiter?: {}", self.trailing_delimiter)?;
+        writeln!(f, "\tEmbedded escapes?: {}", self.embedded_escapes)?;
+        writeln!(
+            f,
+            "\tTrim: {}",
+            match self.trim {
+                Trim::None => "none",
+                Trim::Headers => "headers",
+                Trim::Fields => "fields",
+                Trim::All => "all",
+            }
+        )?;
+        writeln!(
+            f,
+            "\tBlank lines: {}",
+            match self.blank_lines {
+                BlankLinePolicy::Skip => "skip",
+                BlankLinePolicy::EmptyRecord => "empty record",
+            }
+        )?;
         writeln!(f, "\tFlexible: {}", self.flexible)?;
         writeln!(f, "\tIs utf-8 encoded?: {}", self.is_utf8)
     }
 }
 impl Dialect {
+    /// A well-known dialect, by name:
+    ///
+    /// * `excel` -- comma-delimited, `"`-quoted when necessary (with doubled quotes as escapes),
+    ///   and CRLF-terminated, as written by Excel
+    /// * `excel-tab` -- the same as `excel`, but tab-delimited
+    /// * `unix` -- the same as `excel`, but with every field quoted and LF-terminated
+    /// * `rfc4180` -- the format described by [RFC 4180](https://www.rfc-editor.org/rfc/rfc4180)
+    ///   (the same as `excel`)
+    ///
+    /// Every preset has a header row. Returns `None` for any other name.
+    pub fn preset(name: &str) -> Option<Dialect> {
+        let (delimiter, quote_style, terminator) = match name {
+            "excel" | "rfc4180" => (b',', QuoteStyle::Minimal, Terminator::CRLF),
+            "excel-tab" => (b'\t', QuoteStyle::Minimal, Terminator::CRLF),
+            "unix" => (b',', QuoteStyle::Always, Terminator::LF),
+            _ => return None,
+        };
+        Some(Dialect {
+            delimiter,
+            multibyte_delimiter: None,
+            single_column: false,
+            header: Header {
+                has_header_row: true,
+                num_preamble_rows: 0,
+                num_comment_preamble_rows: 0,
+            },
+            quote: Quote::Some(b'"'),
+            quote_style,
+            escape: Escape::Disabled,
+            double_quote: true,
+            comment: Comment::Disabled,
+            terminator,
+            trailing_delimiter: false,
+            embedded_escapes: false,
+            trim: Trim::None,
+            blank_lines: BlankLinePolicy::Skip,
+            flexible: false,
+            is_utf8: true,
+        })
+    }
+
+    /// Encode this `Dialect` as a canonical, single-line string (e.g.
+    /// `delim=,;quote=";header=1;preamble=2;...;flexible=0`), which can be cached or passed on a
+    /// command line, and parsed back with [`from_str`](#method.from_str).
+    ///
+    /// Every component is encoded as a `key=value` pair, separated by `;`. Booleans are encoded as
+    /// `0` or `1`, and a missing quote, escape or comment character as an empty value. Tabs are
+    /// encoded as `\t`, and bytes which are `;`, `=`, `\` or not printable as `\xHH`.
+    pub fn to_canonical_string(&self) -> String {
+        let flag = |value: bool| if value { "1" } else { "0" };
+        let optional_byte = |value: Option<u8>| value.map(encode_byte).unwrap_or_default();
+        let mut components = vec![format!("delim={}", encode_byte(self.delimiter))];
+        if let Some(ref delim) = self.multibyte_delimiter {
+            components.push(format!(
+                "multibyte_delim={}",
+                delim
+                    .iter()
+                    .map(|&chr| encode_byte(chr))
+                    .collect::<String>()
+            ));
+        }
+        components.extend([
+            format!("single_column={}", flag(self.single_column)),
+            format!("header={}", flag(self.header.has_header_row)),
+            format!("preamble={}", self.header.num_preamble_rows),
+            format!("comment_preamble={}", self.header.num_comment_preamble_rows),
+            format!(
+                "quote={}",
+                optional_byte(match self.quote {
+                    Quote::Some(chr) => Some(chr),
+                    Quote::None => None,
+                })
+            ),
+            format!(
+                "quote_style={}",
+                match self.quote_style {
+                    QuoteStyle::Always => "always",
+                    QuoteStyle::Minimal => "minimal",
+                    QuoteStyle::NonNumeric => "nonnumeric",
+                    QuoteStyle::Never => "never",
+                }
+            ),
+            format!("escape={}", optional_byte(self.escape.clone().into())),
+            format!("double_quote={}", flag(self.double_quote)),
+            format!("comment={}", optional_byte(self.comment.clone().into())),
+            format!(
+                "terminator={}",
+                match self.terminator {
+                    Terminator::CRLF => "crlf",
+                    Terminator::LF => "lf",
+                    Terminator::CR => "cr",
+                    Terminator::Mixed => "mixed",
+                    Terminator::RS => "rs",
+                }
+            ),
+            format!("trailing_delim={}", flag(self.trailing_delimiter)),
+            format!("embedded_escapes={}", flag(self.embedded_escapes)),
+            format!(
+                "trim={}",
+                match self.trim {
+                    Trim::None => "none",
+                    Trim::Headers => "headers",
+                    Trim::Fields => "fields",
+                    Trim::All => "all",
+                }
+            ),
+            format!(
+                "blank_lines={}",
+                match self.blank_lines {
+                    BlankLinePolicy::Skip => "skip",
+                    BlankLinePolicy::EmptyRecord => "empty",
+                }
+            ),
+            format!("flexible={}", flag(self.flexible)),
+            format!("utf8={}", flag(self.is_utf8)),
+        ]);
+        components.join(";")
+    }
+
     /// Use this `Dialect` to open a file specified by provided path. Returns a `Reader` (from the
     /// [`csv`](https://docs.rs/csv) crate). Fails on file opening or reading errors.
     pub fn open_path<P: AsRef<Path>>(&self, path: P) -> Result<Reader<File>> {
         self.open_reader(File::open(path)?)
     }
 
+    /// Use this `Dialect` to open the gzip-compressed file specified by provided path (with the
+    /// `gzip` feature). Returns a `Reader` (from the [`csv`](https://docs.rs/csv) crate) over the
+    /// decompressed contents. Fails on file opening or reading errors, or if the dialect uses a
+    /// multi-byte delimiter.
+    #[cfg(feature = "gzip")]
+    pub fn open_gzip_path<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> Result<Reader<BufReader<MultiGzDecoder<File>>>> {
+        if let Some(ref delim) = self.multibyte_delimiter {
+            return Err(SnifferError::MultiByteDelimiter(delim.clone()));
+        }
+        let rdr = gzip::open_decompressed(
+            path.as_ref(),
+            self.header.num_preamble_rows,
+            self.terminator.record_end(),
+        )?;
+        let bldr: ReaderBuilder = self.clone().into();
+        Ok(bldr.from_reader(rdr))
+    }
+
+    /// Use this `Dialect` to open the Snappy-framed file specified by provided path (with the
+    /// `snappy` feature). Returns a `Reader` (from the [`csv`](https://docs.rs/csv) crate) over the
+    /// decompressed contents. Fails on file opening or reading errors, or if the dialect uses a
+    /// multi-byte delimiter.
+    #[cfg(feature = "snappy")]
+    pub fn open_snappy_path<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> Result<Reader<BufReader<SnappyDecoder<File>>>> {
+        if let Some(ref delim) = self.multibyte_delimiter {
+            return Err(SnifferError::MultiByteDelimiter(delim.clone()));
+        }
+        let rdr = snappy::open_decompressed(
+            path.as_ref(),
+            self.header.num_preamble_rows,
+            self.terminator.record_end(),
+        )?;
+        let bldr: ReaderBuilder = self.clone().into();
+        Ok(bldr.from_reader(rdr))
+    }
+
+    /// Use this `Dialect` to open the CSV file named `entry` within the ZIP archive specified by
+    /// provided path (with the `zip` feature). Returns a `Reader` (from the
+    /// [`csv`](https://docs.rs/csv) crate) over its contents. Fails on file opening or reading
+    /// errors, if there's no such entry (or it can't be read), or if the dialect uses a multi-byte
+    /// delimiter.
+    #[cfg(feature = "zip")]
+    pub fn open_zip_path<P: AsRef<Path>>(
+        &self,
+        path: P,
+        entry: &str,
+    ) -> Result<Reader<BufReader<ZipEntryReader>>> {
+        if let Some(ref delim) = self.multibyte_delimiter {
+            return Err(SnifferError::MultiByteDelimiter(delim.clone()));
+        }
+        let rdr = zip::open_entry_skipped(
+            path.as_ref(),
+            entry,
+            self.header.num_preamble_rows,
+            self.terminator.record_end(),
+        )?;
+        let bldr: ReaderBuilder = self.clone().into();
+        Ok(bldr.from_reader(rdr))
+    }
+
     /// Use this `Dialect` to create a `Reader` (from the [`csv`](https://docs.rs/csv) crate) using
-    /// the provided reader. Fails if unable to read from the reader.
+    /// the provided reader. Fails if unable to read from the reader, or if the dialect uses a
+    /// multi-byte delimiter (see [`open_rewritten_reader`](#method.open_rewritten_reader)).
     pub fn open_reader<R: Read + Seek>(&self, mut rdr: R) -> Result<Reader<R>> {
-        snip_preamble(&mut rdr, self.header.num_preamble_rows)?;
+        if let Some(ref delim) = self.multibyte_delimiter {
+            return Err(SnifferError::MultiByteDelimiter(delim.clone()));
+        }
+        snip_preamble(
+            &mut rdr,
+            self.header.num_preamble_rows,
+            self.terminator.record_end(),
+        )?;
+        let bldr: ReaderBuilder = self.clone().into();
+        Ok(bldr.from_reader(rdr))
+    }
+
+    /// Use this `Dialect` to create a `Reader` (from the [`csv`](https://docs.rs/csv) crate) using
+    /// the provided reader, rewriting a multi-byte delimiter to `delimiter` on the fly. Dialects
+    /// with a single-byte delimiter are read as-is. Fails if unable to read from the reader.
+    pub fn open_rewritten_reader<R: Read + Seek>(
+        &self,
+        rdr: R,
+    ) -> Result<Reader<DelimiterRewriter<R>>> {
+        let mut rdr = match self.multibyte_delimiter {
+            Some(ref delim) => DelimiterRewriter::new(rdr, delim, self.delimiter),
+            None => DelimiterRewriter::new(rdr, &[self.delimiter], self.delimiter),
+        };
+        snip_preamble(
+            &mut rdr,
+            self.header.num_preamble_rows,
+            self.terminator.record_end(),
+        )?;
         let bldr: ReaderBuilder = self.clone().into();
         Ok(bldr.from_reader(rdr))
     }
@@ -140,6 +1107,11 @@ impl From<Dialect> for ReaderBuilder {
         let mut bldr = ReaderBuilder::new();
         bldr.delimiter(dialect.delimiter)
             .has_headers(dialect.header.has_header_row)
+            .escape(dialect.escape.into())
+            .double_quote(dialect.double_quote)
+            .comment(dialect.comment.into())
+            .terminator(dialect.terminator.into())
+            .trim(dialect.trim.into())
             .flexible(dialect.flexible);
 
         match dialect.quote {
@@ -156,6 +1128,142 @@ impl From<Dialect> for ReaderBuilder {
     }
 }
 
+/// Parses a dialect encoded by [`to_canonical_string`](#method.to_canonical_string). Components
+/// which are left out take their value from the [`excel`](#method.preset) preset, so partial
+/// strings like `delim=\t;header=0` can be used as well.
+///
+/// Fails on unknown keys, and on invalid values.
+impl FromStr for Dialect {
+    type Err = SnifferError;
+
+    fn from_str(s: &str) -> Result<Dialect> {
+        let invalid = |component: &str| {
+            SnifferError::InvalidDialect(format!("invalid dialect component: {component}"))
+        };
+        // safety: the excel preset always exists
+        let mut dialect = Dialect::preset("excel").unwrap();
+        for component in s.split(';').filter(|component| !component.is_empty()) {
+            let (key, value) = component
+                .split_once('=')
+                .ok_or_else(|| invalid(component))?;
+            let flag = || match value {
+                "0" => Ok(false),
+                "1" => Ok(true),
+                _ => Err(invalid(component)),
+            };
+            let byte = || match *decode_bytes(value).ok_or_else(|| invalid(component))? {
+                [chr] => Ok(chr),
+                _ => Err(invalid(component)),
+            };
+            let optional_byte = || {
+                if value.is_empty() {
+                    Ok(None)
+                } else {
+                    byte().map(Some)
+                }
+            };
+            let count = || value.parse::<usize>().map_err(|_| invalid(component));
+            match key {
+                "delim" => dialect.delimiter = byte()?,
+                "multibyte_delim" => {
+                    dialect.multibyte_delimiter =
+                        Some(decode_bytes(value).ok_or_else(|| invalid(component))?);
+                }
+                "single_column" => dialect.single_column = flag()?,
+                "header" => dialect.header.has_header_row = flag()?,
+                "preamble" => dialect.header.num_preamble_rows = count()?,
+                "comment_preamble" => dialect.header.num_comment_preamble_rows = count()?,
+                "quote" => {
+                    dialect.quote = optional_byte()?.map_or(Quote::None, Quote::Some);
+                }
+                "quote_style" => {
+                    dialect.quote_style = match value {
+                        "always" => QuoteStyle::Always,
+                        "minimal" => QuoteStyle::Minimal,
+                        "nonnumeric" => QuoteStyle::NonNumeric,
+                        "never" => QuoteStyle::Never,
+                        _ => return Err(invalid(component)),
+                    };
+                }
+                "escape" => {
+                    dialect.escape = optional_byte()?.map_or(Escape::Disabled, Escape::Enabled);
+                }
+                "double_quote" => dialect.double_quote = flag()?,
+                "comment" => {
+                    dialect.comment = optional_byte()?.map_or(Comment::Disabled, Comment::Enabled);
+                }
+                "terminator" => {
+                    dialect.terminator = match value {
+                        "crlf" => Terminator::CRLF,
+                        "lf" => Terminator::LF,
+                        "cr" => Terminator::CR,
+                        "mixed" => Terminator::Mixed,
+                        "rs" => Terminator::RS,
+                        _ => return Err(invalid(component)),
+                    };
+                }
+                "trailing_delim" => dialect.trailing_delimiter = flag()?,
+                "embedded_escapes" => dialect.embedded_escapes = flag()?,
+                "trim" => {
+                    dialect.trim = match value {
+                        "none" => Trim::None,
+                        "headers" => Trim::Headers,
+                        "fields" => Trim::Fields,
+                        "all" => Trim::All,
+                        _ => return Err(invalid(component)),
+                    };
+                }
+                "blank_lines" => {
+                    dialect.blank_lines = match value {
+                        "skip" => BlankLinePolicy::Skip,
+                        "empty" => BlankLinePolicy::EmptyRecord,
+                        _ => return Err(invalid(component)),
+                    };
+                }
+                "flexible" => dialect.flexible = flag()?,
+                "utf8" => dialect.is_utf8 = flag()?,
+                _ => {
+                    return Err(SnifferError::InvalidDialect(format!(
+                        "unknown dialect component: {key}"
+                    )));
+                }
+            }
+        }
+        Ok(dialect)
+    }
+}
+
+// Encodes a byte for a canonical dialect string.
+fn encode_byte(chr: u8) -> String {
+    match chr {
+        b'\t' => "\\t".to_string(),
+        b';' | b'=' | b'\\' => format!("\\x{chr:02x}"),
+        chr if chr.is_ascii_graphic() => char::from(chr).to_string(),
+        chr => format!("\\x{chr:02x}"),
+    }
+}
+
+// Decodes the bytes of a canonical dialect string value, or returns `None` if an escape is invalid.
+fn decode_bytes(value: &str) -> Option<Vec<u8>> {
+    let mut bytes = vec![];
+    let mut chars = value.bytes();
+    while let Some(chr) = chars.next() {
+        if chr != b'\\' {
+            bytes.push(chr);
+            continue;
+        }
+        match chars.next()? {
+            b't' => bytes.push(b'\t'),
+            b'x' => {
+                let hex = [chars.next()?, chars.next()?];
+                bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+            }
+            _ => return None,
+        }
+    }
+    Some(bytes)
+}
+
 /// Metadata about the header of the CSV file.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Header {
@@ -164,6 +1272,40 @@ pub struct Header {
     /// Number of rows that occur before either the header row (if `has_header_row` is `true), or
     /// the first data row.
     pub num_preamble_rows: usize,
+    /// Number of the preamble rows that are comment-prefixed lines at the start of the file (e.g.
+    /// `# Generated: ...`). These are included in `num_preamble_rows`.
+    pub num_comment_preamble_rows: usize,
+}
+
+/// Known components of a CSV dialect, used as ground truth when sniffing the rest of it (see
+/// [`Sniffer::with_known`](../struct.Sniffer.html#method.with_known)). Components left as `None`
+/// are sniffed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DialectHints {
+    /// The delimiter character.
+    pub delimiter: Option<u8>,
+    /// Whether the CSV file has a header row.
+    pub has_header_row: Option<bool>,
+    /// Number of rows that occur before either the header row or the first data row.
+    pub num_preamble_rows: Option<usize>,
+    /// The quote character (if any).
+    pub quote: Option<Quote>,
+    /// When quotes are used to quote fields.
+    pub quote_style: Option<QuoteStyle>,
+    /// The escape character (if any) used to escape quotes within quoted fields.
+    pub escape: Option<Escape>,
+    /// Whether two quotes in a row within a quoted field are interpreted as an escaped quote.
+    pub double_quote: Option<bool>,
+    /// The comment character (if any).
+    pub comment: Option<Comment>,
+    /// The record terminator.
+    pub terminator: Option<Terminator>,
+    /// The whitespace trimming applied to fields.
+    pub trim: Option<Trim>,
+    /// How blank lines within the data are interpreted.
+    pub blank_lines: Option<BlankLinePolicy>,
+    /// Whether records may have different numbers of fields.
+    pub flexible: Option<bool>,
 }
 
 /// Metadata about the quoting style of the CSV file.
@@ -186,6 +1328,102 @@ impl fmt::Debug for Quote {
     }
 }
 
+/// The quoting style of the CSV file. Can be converted into the [`csv`](https://docs.rs/csv)
+/// crate's `QuoteStyle` to write files quoted the same way.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QuoteStyle {
+    /// Every field is quoted.
+    Always,
+    /// Only fields that need it (e.g. those containing the delimiter or a quote) are quoted.
+    Minimal,
+    /// Every non-numeric field is quoted.
+    NonNumeric,
+    /// Fields are never quoted.
+    Never,
+}
+impl From<QuoteStyle> for csv::QuoteStyle {
+    fn from(quote_style: QuoteStyle) -> csv::QuoteStyle {
+        match quote_style {
+            QuoteStyle::Always => csv::QuoteStyle::Always,
+            QuoteStyle::Minimal => csv::QuoteStyle::Necessary,
+            QuoteStyle::NonNumeric => csv::QuoteStyle::NonNumeric,
+            QuoteStyle::Never => csv::QuoteStyle::Never,
+        }
+    }
+}
+
+// The ASCII record separator, used as a record terminator by some data lake exports.
+pub(crate) const RECORD_SEPARATOR: u8 = 0x1E;
+
+/// The line terminator of the CSV file. Apart from `RS`, any of these are handled by the default
+/// terminator of the `csv` crate's `ReaderBuilder`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Terminator {
+    /// Records are terminated by `\r\n`.
+    CRLF,
+    /// Records are terminated by `\n`.
+    LF,
+    /// Records are terminated by a bare `\r`.
+    CR,
+    /// More than one of the above terminators is used.
+    Mixed,
+    /// Records are terminated by the ASCII record separator (`0x1E`).
+    RS,
+}
+impl Terminator {
+    // The byte that ends each record (for CRLF and mixed terminators, lines are split on `\n`
+    // and any `\r` is trimmed).
+    pub(crate) fn record_end(self) -> u8 {
+        match self {
+            Terminator::CR => b'\r',
+            Terminator::RS => RECORD_SEPARATOR,
+            Terminator::CRLF | Terminator::LF | Terminator::Mixed => b'\n',
+        }
+    }
+}
+impl From<Terminator> for csv::Terminator {
+    fn from(terminator: Terminator) -> csv::Terminator {
+        match terminator {
+            Terminator::RS => csv::Terminator::Any(RECORD_SEPARATOR),
+            _ => csv::Terminator::CRLF,
+        }
+    }
+}
+
+/// Whitespace trimming applied to the fields of the CSV file (mirrors `csv::Trim`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Trim {
+    /// Fields are read as-is.
+    None,
+    /// Whitespace is trimmed from the fields of the header row only.
+    Headers,
+    /// Whitespace is trimmed from the fields of the data rows only.
+    Fields,
+    /// Whitespace is trimmed from all fields.
+    All,
+}
+impl From<Trim> for csv::Trim {
+    fn from(trim: Trim) -> csv::Trim {
+        match trim {
+            Trim::None => csv::Trim::None,
+            Trim::Headers => csv::Trim::Headers,
+            Trim::Fields => csv::Trim::Fields,
+            Trim::All => csv::Trim::All,
+        }
+    }
+}
+
+/// How blank lines within the data of the CSV file should be interpreted. Note that the `csv`
+/// crate's `Reader` always skips blank lines, so `EmptyRecord` is informational only.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlankLinePolicy {
+    /// Blank lines separate groups of records, and should be skipped.
+    Skip,
+    /// Blank lines are records with a single empty field (e.g. missing values in a single-column
+    /// file).
+    EmptyRecord,
+}
+
 /// The escape character (or `Disabled` if escaping is disabled)
 #[derive(Clone, PartialEq)]
 pub enum Escape {
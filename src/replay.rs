@@ -0,0 +1,81 @@
+/*!
+Replaying of the sample read from a stream which can't be rewound.
+*/
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+
+/// A reader over a stream which can't be rewound (such as standard input), of which a sample has
+/// already been read: the buffered sample is replayed before the rest of the stream. Returned by
+/// [`Sniffer::sniff_replayable`](struct.Sniffer.html#method.sniff_replayable), so that
+/// [`Dialect::open_reader`](metadata/struct.Dialect.html#method.open_reader) can skip the preamble
+/// of the stream.
+///
+/// Seeking is only supported within the sample (relative to its start), until the stream past it is
+/// read. The buffered sample can also be taken as is (see [`sample`](#method.sample)), to process
+/// it without reading it again.
+#[derive(Debug)]
+pub struct ReplayReader<R> {
+    sample: Vec<u8>,
+    // position within the sample
+    pos: usize,
+    inner: BufReader<R>,
+    // number of bytes read past the sample
+    n_past: u64,
+}
+
+impl<R: Read> ReplayReader<R> {
+    // Replays `sample`, the bytes already read from `inner`, before the rest of it.
+    pub(crate) fn new(sample: Vec<u8>, inner: BufReader<R>) -> ReplayReader<R> {
+        ReplayReader {
+            sample,
+            pos: 0,
+            inner,
+            n_past: 0,
+        }
+    }
+
+    /// The bytes read from the stream while sniffing (the sample, and possibly the start of the
+    /// line past it), whatever has been replayed since.
+    pub fn sample(&self) -> &[u8] {
+        &self.sample
+    }
+
+    /// Split the reader into the part of the sample which hasn't been replayed yet (all of it, if
+    /// nothing has been read from the reader), and the rest of the stream, which continues right
+    /// after the sample.
+    pub fn into_parts(self) -> (Vec<u8>, BufReader<R>) {
+        let mut sample = self.sample;
+        sample.drain(..self.pos);
+        (sample, self.inner)
+    }
+}
+
+impl<R: Read> Read for ReplayReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos < self.sample.len() {
+            let available = &self.sample[self.pos..];
+            let n = available.len().min(buf.len());
+            buf[..n].copy_from_slice(&available[..n]);
+            self.pos += n;
+            return Ok(n);
+        }
+        let n_read = self.inner.read(buf)?;
+        self.n_past += n_read as u64;
+        Ok(n_read)
+    }
+}
+
+impl<R: Read> Seek for ReplayReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match pos {
+            SeekFrom::Start(offset) if self.n_past == 0 && offset <= self.sample.len() as u64 => {
+                self.pos = offset as usize;
+                Ok(offset)
+            }
+            SeekFrom::Current(0) => Ok(self.pos as u64 + self.n_past),
+            _ => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "ReplayReader only supports seeking within the sample, before reading past it",
+            )),
+        }
+    }
+}
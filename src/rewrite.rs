@@ -0,0 +1,204 @@
+/*!
+Reader adapters for multi-byte delimiters and mixed line terminators.
+*/
+use std::io::{self, Read, Seek, SeekFrom};
+
+/// Byte used in place of a multi-byte delimiter when rewriting (the ASCII unit separator).
+pub(crate) const MULTIBYTE_REPLACEMENT: u8 = 0x1F;
+
+/// A reader that rewrites every occurrence of a multi-byte delimiter to a single byte, since the
+/// [`csv`](https://docs.rs/csv) crate only supports single-byte delimiters.
+///
+/// Seeking is only supported relative to the start of the stream (positions are in terms of the
+/// rewritten bytes), which is enough to skip preamble rows.
+#[derive(Debug)]
+pub struct DelimiterRewriter<R> {
+    inner: R,
+    delimiter: Vec<u8>,
+    replacement: u8,
+    // bytes read from `inner`, but not yet rewritten
+    pending: Vec<u8>,
+    // rewritten bytes, not yet returned (starting at `output_pos`)
+    output: Vec<u8>,
+    output_pos: usize,
+    // position within the rewritten stream
+    pos: u64,
+    eof: bool,
+}
+
+impl<R: Read> DelimiterRewriter<R> {
+    /// Create a new `DelimiterRewriter`, rewriting `delimiter` to `replacement`.
+    pub fn new(inner: R, delimiter: &[u8], replacement: u8) -> DelimiterRewriter<R> {
+        DelimiterRewriter {
+            inner,
+            delimiter: delimiter.to_vec(),
+            replacement,
+            pending: vec![],
+            output: vec![],
+            output_pos: 0,
+            pos: 0,
+            eof: false,
+        }
+    }
+
+    /// Unwrap this `DelimiterRewriter`, returning the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    fn fill_output(&mut self) -> io::Result<()> {
+        self.output.clear();
+        self.output_pos = 0;
+        while self.output.is_empty() && !self.eof {
+            let mut chunk = [0; 1 << 12];
+            let n_read = self.inner.read(&mut chunk)?;
+            if n_read == 0 {
+                self.eof = true;
+            }
+            self.pending.extend_from_slice(&chunk[..n_read]);
+
+            let mut i = 0;
+            while i < self.pending.len() {
+                let rest = &self.pending[i..];
+                if rest.starts_with(&self.delimiter) {
+                    self.output.push(self.replacement);
+                    i += self.delimiter.len();
+                } else if !self.eof && self.delimiter.starts_with(rest) {
+                    // might be the start of a delimiter cut off by the end of the chunk, so
+                    // keep it for the next round
+                    break;
+                } else {
+                    self.output.push(rest[0]);
+                    i += 1;
+                }
+            }
+            self.pending.drain(..i);
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for DelimiterRewriter<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.output_pos == self.output.len() {
+            self.fill_output()?;
+        }
+        let available = &self.output[self.output_pos..];
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.output_pos += n;
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl<R: Read + Seek> Seek for DelimiterRewriter<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match pos {
+            SeekFrom::Start(offset) => {
+                self.inner.seek(SeekFrom::Start(0))?;
+                self.pending.clear();
+                self.output.clear();
+                self.output_pos = 0;
+                self.pos = 0;
+                self.eof = false;
+                io::copy(&mut self.by_ref().take(offset), &mut io::sink())?;
+                Ok(self.pos)
+            }
+            SeekFrom::Current(0) => Ok(self.pos),
+            _ => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "DelimiterRewriter only supports seeking from the start of the stream",
+            )),
+        }
+    }
+}
+
+/// A reader that normalizes every line terminator (`\r\n` or a bare `\r`) to `\n`, so that the
+/// lines of a file mixing terminators can be sampled as if it used `\n` throughout.
+///
+/// Seeking is only supported relative to the start of the stream (positions are in terms of the
+/// normalized bytes), as for [`DelimiterRewriter`](struct.DelimiterRewriter.html).
+#[derive(Debug)]
+pub(crate) struct NewlineNormalizer<R> {
+    inner: R,
+    // normalized bytes, not yet returned (starting at `output_pos`)
+    output: Vec<u8>,
+    output_pos: usize,
+    // whether the last byte read from `inner` was a `\r` (already output as `\n`)
+    after_cr: bool,
+    // position within the normalized stream
+    pos: u64,
+    eof: bool,
+}
+
+impl<R: Read> NewlineNormalizer<R> {
+    pub(crate) fn new(inner: R) -> NewlineNormalizer<R> {
+        NewlineNormalizer {
+            inner,
+            output: vec![],
+            output_pos: 0,
+            after_cr: false,
+            pos: 0,
+            eof: false,
+        }
+    }
+
+    fn fill_output(&mut self) -> io::Result<()> {
+        self.output.clear();
+        self.output_pos = 0;
+        while self.output.is_empty() && !self.eof {
+            let mut chunk = [0; 1 << 12];
+            let n_read = self.inner.read(&mut chunk)?;
+            if n_read == 0 {
+                self.eof = true;
+            }
+            for &byte in &chunk[..n_read] {
+                match byte {
+                    b'\r' => self.output.push(b'\n'),
+                    // the second half of a `\r\n`
+                    b'\n' if self.after_cr => {}
+                    _ => self.output.push(byte),
+                }
+                self.after_cr = byte == b'\r';
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for NewlineNormalizer<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.output_pos == self.output.len() {
+            self.fill_output()?;
+        }
+        let available = &self.output[self.output_pos..];
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.output_pos += n;
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl<R: Read + Seek> Seek for NewlineNormalizer<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match pos {
+            SeekFrom::Start(offset) => {
+                self.inner.seek(SeekFrom::Start(0))?;
+                self.output.clear();
+                self.output_pos = 0;
+                self.after_cr = false;
+                self.pos = 0;
+                self.eof = false;
+                io::copy(&mut self.by_ref().take(offset), &mut io::sink())?;
+                Ok(self.pos)
+            }
+            SeekFrom::Current(0) => Ok(self.pos),
+            _ => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "NewlineNormalizer only supports seeking from the start of the stream",
+            )),
+        }
+    }
+}
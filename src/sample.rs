@@ -1,20 +1,368 @@
-use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::time::Duration;
 
 use crate::error::Result;
-use crate::sniffer::IS_UTF8;
+use crate::sniffer::{next_random, IS_ASCII, IS_UTF8};
 
 /// Argument used when calling `sample_size` on `Sniffer`.
 #[derive(Debug, Clone, Copy)]
 pub enum SampleSize {
-    /// Use a number of records as the size of the sample to sniff.
+    /// Use a number of records as the size of the sample to sniff, however wide they are (the
+    /// types are inferred from parsed records, so a record with multi-line fields counts once).
     Records(usize),
     /// Use a number of bytes as the size of the sample to sniff.
     Bytes(usize),
-    /// Sniff the entire sample.
+    /// Sniff the entire file: the dialect, the types and the encoding are inferred from every
+    /// record, for an answer consistent with all of the data at the cost of reading all of it.
     All,
+    /// Sample as much as can be sniffed within a time budget (e.g. 50 milliseconds, for an
+    /// interactive tool). The number of bytes this allows is estimated from the time sniffing the
+    /// start of the file (with the same options) takes; at most 1 MiB of a stream which can't be
+    /// rewound is buffered. The budget is a target rather than a hard limit; see
+    /// `Metadata::sampled_bytes` for how much was examined.
+    Duration(Duration),
+    /// Sample a percentage of the file, by bytes (e.g. `5.0` for the first 5% of it, up to the end
+    /// of the record which takes the sample past it), so that the sample grows with the file. A
+    /// stream which can't be rewound is read whole, as its length isn't known beforehand.
+    Percent(f32),
+    /// Start with a small sample, and double it until the sniffed dialect and types are confident
+    /// enough: until every confidence in the dialect (see `Metadata::confidence`) and every type
+    /// confidence is at least `min_confidence` (e.g. `0.9`), or the sample reaches `max_bytes`.
+    /// Most files are sniffed from a few KiB, while those which need more get it. The sample sizes
+    /// are tried with the same options; see `Metadata::sampled_bytes` for the size settled on.
+    Adaptive {
+        /// The confidence (from 0.0 to 1.0) every one of the dialect and the types should reach.
+        min_confidence: f64,
+        /// The largest sample (in bytes), which is also what a stream which can't be rewound
+        /// buffers.
+        max_bytes: usize,
+    },
 }
 
-pub fn take_sample_from_start<R>(reader: &mut R, sample_size: SampleSize) -> Result<SampleIter<R>>
+/// The start of a file to skip before sampling (such as a long license or XML header), so that it
+/// doesn't take up the sample.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipStart {
+    /// Skip a number of bytes, along with the rest of the line the last of them is in.
+    Bytes(u64),
+    /// Skip a number of lines.
+    Rows(usize),
+}
+
+/// Consume the skipped start of a stream, which ends with a line. Returns the number of bytes and
+/// of lines consumed.
+pub(crate) fn skip_start<R: BufRead>(reader: &mut R, skip: SkipStart) -> io::Result<(u64, usize)> {
+    let (mut n_bytes, mut n_lines) = (0, 0);
+    let mut line = vec![];
+    match skip {
+        SkipStart::Bytes(n_skipped) => {
+            let mut at_line_start = true;
+            while n_bytes < n_skipped {
+                let buf = reader.fill_buf()?;
+                if buf.is_empty() {
+                    break;
+                }
+                let len = buf
+                    .len()
+                    .min(usize::try_from(n_skipped - n_bytes).unwrap_or(usize::MAX));
+                n_lines += bytecount::count(&buf[..len], b'\n');
+                at_line_start = buf[len - 1] == b'\n';
+                reader.consume(len);
+                n_bytes += len as u64;
+            }
+            if !at_line_start {
+                n_bytes += reader.read_until(b'\n', &mut line)? as u64;
+                n_lines += 1;
+            }
+        }
+        SkipStart::Rows(n_rows) => {
+            for _ in 0..n_rows {
+                line.clear();
+                let n_read = reader.read_until(b'\n', &mut line)?;
+                if n_read == 0 {
+                    break;
+                }
+                n_bytes += n_read as u64;
+                n_lines += 1;
+            }
+        }
+    }
+    Ok((n_bytes, n_lines))
+}
+
+/// A view of a reader which starts at an offset, past its skipped start.
+pub(crate) struct SkippedReader<R> {
+    inner: R,
+    start: u64,
+}
+
+impl<R: Seek> SkippedReader<R> {
+    pub(crate) fn new(mut inner: R, start: u64) -> io::Result<SkippedReader<R>> {
+        inner.seek(SeekFrom::Start(start))?;
+        Ok(SkippedReader { inner, start })
+    }
+}
+
+impl<R: Read> Read for SkippedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+impl<R: Seek> Seek for SkippedReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let pos = match pos {
+            SeekFrom::Start(offset) => self.inner.seek(SeekFrom::Start(self.start + offset))?,
+            pos => self.inner.seek(pos)?,
+        };
+        if pos < self.start {
+            // before the start of the view
+            self.inner.seek(SeekFrom::Start(self.start))?;
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative position",
+            ));
+        }
+        Ok(pos - self.start)
+    }
+}
+
+/// Most bytes buffered from a stream which can't be rewound for a sample of `SampleSize::Duration`
+/// (before the budget is turned into a number of bytes).
+pub(crate) const MAX_TIMED_SAMPLE_BYTES: usize = 1 << 20;
+
+/// Bytes read beyond a sample of `SampleSize::Bytes` from a stream which can't be rewound, so that
+/// the record which takes the sample past its size is whole.
+pub(crate) const SAMPLE_SLACK: u64 = 1 << 16;
+
+/// The start of a stream, pushed in as it arrives until there's enough of it for a sample, so that
+/// data which can't be read without blocking (such as an upload received by an asynchronous web
+/// service) is sniffed without a blocking reader. Created by
+/// [`Sniffer::sample_buffer`](struct.Sniffer.html#method.sample_buffer) for the sample size and the
+/// skipped start of the sniffer, and sniffed by
+/// [`Sniffer::sniff_sample_buffer`](struct.Sniffer.html#method.sniff_sample_buffer). With
+/// `SampleSize::All` (or `SampleSize::Percent`, as the length of the stream isn't known), the
+/// whole stream is buffered.
+#[derive(Debug, Clone)]
+pub struct SampleBuffer {
+    sample_size: SampleSize,
+    skip: Option<SkipStart>,
+    bytes: Vec<u8>,
+    // offset up to which the bytes have been scanned for line breaks, and the number of line
+    // breaks found since the start of the part being scanned (the skipped start, or the sample)
+    scanned: usize,
+    n_lines: usize,
+    // offsets where the sample starts (past the skipped start) and ends, once they're known
+    sample_start: Option<usize>,
+    sample_end: Option<usize>,
+}
+
+impl SampleBuffer {
+    pub(crate) fn new(sample_size: SampleSize, skip: Option<SkipStart>) -> SampleBuffer {
+        let mut buffer = SampleBuffer {
+            sample_size,
+            skip,
+            bytes: vec![],
+            scanned: 0,
+            n_lines: 0,
+            sample_start: None,
+            sample_end: None,
+        };
+        buffer.scan();
+        buffer
+    }
+
+    /// Append the next bytes of the stream, returning how many of them were kept: fewer than were
+    /// provided once the buffer is full, as the rest aren't needed to sniff the stream.
+    pub fn push(&mut self, data: &[u8]) -> usize {
+        if self.is_full() {
+            return 0;
+        }
+        let old_len = self.bytes.len();
+        self.bytes.extend_from_slice(data);
+        self.scan();
+        // one byte past the sample, telling that the stream goes on after it
+        if let Some(end) = self.sample_end {
+            self.bytes.truncate(end + 1);
+        }
+        self.bytes.len() - old_len
+    }
+
+    /// Whether enough of the stream has been pushed to sniff it. A stream which ends before the
+    /// buffer is full is sniffed from what was pushed.
+    pub fn is_full(&self) -> bool {
+        self.sample_end.is_some_and(|end| self.bytes.len() > end)
+    }
+
+    /// The bytes pushed and kept, i.e. the start of the stream (to be read before the rest of it).
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Unwrap this `SampleBuffer`, returning the bytes pushed and kept.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    // Finds where the skipped start and the sample end, in as much of the stream as was pushed
+    // (as `skip_start` and `read_sample` would read them).
+    fn scan(&mut self) {
+        if self.sample_start.is_none() {
+            self.sample_start = match self.skip {
+                None => Some(0),
+                Some(SkipStart::Rows(n_rows)) => self.find_line_end(0, n_rows),
+                Some(SkipStart::Bytes(n_bytes)) => {
+                    let n_bytes = usize::try_from(n_bytes).unwrap_or(usize::MAX);
+                    if n_bytes == 0 {
+                        Some(0)
+                    } else if self.bytes.len() < n_bytes {
+                        None
+                    } else if self.bytes[n_bytes - 1] == b'\n' {
+                        Some(n_bytes)
+                    } else {
+                        // along with the rest of the line
+                        self.find_line_end(n_bytes, 1)
+                    }
+                }
+            };
+        }
+        let Some(start) = self.sample_start else {
+            return;
+        };
+        if self.sample_end.is_none() {
+            self.sample_end = match self.sample_size {
+                SampleSize::All | SampleSize::Percent(_) => None,
+                SampleSize::Bytes(n_bytes)
+                | SampleSize::Adaptive {
+                    max_bytes: n_bytes, ..
+                } => Some(start + n_bytes + SAMPLE_SLACK as usize),
+                SampleSize::Duration(_) => Some(start + MAX_TIMED_SAMPLE_BYTES),
+                SampleSize::Records(n_records) => self.find_line_end(start, 2 * (n_records + 1)),
+            };
+        }
+    }
+
+    // The offset following the `n_lines`-th line break from `from` (counting those found by
+    // earlier calls for the same part of the stream), if that many have been pushed.
+    fn find_line_end(&mut self, from: usize, n_lines: usize) -> Option<usize> {
+        if n_lines == 0 {
+            return Some(from);
+        }
+        let mut pos = self.scanned.max(from);
+        while let Some(i) = memchr::memchr(b'\n', &self.bytes[pos..]) {
+            pos += i + 1;
+            self.n_lines += 1;
+            if self.n_lines == n_lines {
+                // the next part is scanned from here
+                self.scanned = pos;
+                self.n_lines = 0;
+                return Some(pos);
+            }
+        }
+        self.scanned = self.bytes.len();
+        None
+    }
+}
+
+/// Read the start of a stream which can't be rewound, long enough to fill a sample of the provided
+/// size (`SampleSize::All` reads the whole stream). Returns the bytes read, along with the length
+/// of the whole lines among them (i.e. not counting a line cut off, unless the stream has ended).
+pub(crate) fn read_sample<R: BufRead>(
+    reader: &mut R,
+    sample_size: SampleSize,
+) -> io::Result<(Vec<u8>, usize)> {
+    let mut sample = vec![];
+    match sample_size {
+        SampleSize::All | SampleSize::Percent(_) => {
+            reader.read_to_end(&mut sample)?;
+            let len = sample.len();
+            return Ok((sample, len));
+        }
+        SampleSize::Bytes(n_bytes)
+        | SampleSize::Adaptive {
+            max_bytes: n_bytes, ..
+        } => {
+            reader
+                .take(n_bytes as u64 + SAMPLE_SLACK)
+                .read_to_end(&mut sample)?;
+        }
+        SampleSize::Duration(_) => {
+            reader
+                .take(MAX_TIMED_SAMPLE_BYTES as u64)
+                .read_to_end(&mut sample)?;
+        }
+        SampleSize::Records(n_records) => {
+            // twice as many lines as records, to leave room for preamble rows, comments and
+            // multi-line fields
+            for _ in 0..2 * (n_records + 1) {
+                if reader.read_until(b'\n', &mut sample)? == 0 {
+                    break;
+                }
+            }
+        }
+    }
+    let len = if reader.fill_buf()?.is_empty() {
+        sample.len()
+    } else {
+        sample
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map_or(0, |pos| pos + 1)
+    };
+    Ok((sample, len))
+}
+
+/// Read a stream which can't be rewound to its end, keeping its first `n_records` records followed
+/// by `n_records` of the rest drawn uniformly at random (by reservoir sampling), in the order they
+/// occur. Records end at the line breaks following an even number of `quote` characters, so that
+/// a record with a multi-line field is kept whole.
+pub(crate) fn read_reservoir<R: BufRead>(
+    reader: &mut R,
+    n_records: usize,
+    quote: u8,
+) -> io::Result<Vec<u8>> {
+    let mut sample = vec![];
+    // the records drawn so far, along with their indices among the rest of the records
+    let mut reservoir: Vec<(u64, Vec<u8>)> = Vec::with_capacity(n_records);
+    let (mut n_first, mut n_rest) = (0, 0);
+    let mut state = 0x9E37_79B9_7F4A_7C15;
+    let mut record = vec![];
+    loop {
+        record.clear();
+        while reader.read_until(b'\n', &mut record)? > 0 {
+            if bytecount::count(&record, quote) % 2 == 0 {
+                break;
+            }
+        }
+        if record.is_empty() {
+            break;
+        }
+        if n_first < n_records {
+            sample.extend_from_slice(&record);
+            n_first += 1;
+            continue;
+        }
+        if reservoir.len() < n_records {
+            reservoir.push((n_rest, record.clone()));
+        } else {
+            let i = next_random(&mut state) % (n_rest + 1);
+            if let Some(drawn) = reservoir.get_mut(i as usize) {
+                *drawn = (n_rest, record.clone());
+            }
+        }
+        n_rest += 1;
+    }
+    reservoir.sort_by_key(|&(i, _)| i);
+    for (_, record) in reservoir {
+        sample.extend_from_slice(&record);
+    }
+    Ok(sample)
+}
+
+pub fn take_sample_from_start<R>(
+    reader: &mut R,
+    sample_size: SampleSize,
+) -> Result<SampleIter<'_, R>>
 where
     R: Read + Seek,
 {
@@ -28,6 +376,14 @@ pub struct SampleIter<'a, R: 'a + Read> {
     n_bytes: usize,
     n_records: usize,
     is_done: bool,
+    comment: Option<u8>,
+    record_end: u8,
+    n_skip: usize,
+    // position within the reader, and the position to stop at (if any)
+    pos: u64,
+    end: Option<u64>,
+    // position within the reader after the last line returned
+    sampled_len: u64,
 }
 
 impl<'a, R: Read> SampleIter<'a, R> {
@@ -39,8 +395,44 @@ impl<'a, R: Read> SampleIter<'a, R> {
             n_bytes: 0,
             n_records: 0,
             is_done: false,
+            comment: None,
+            record_end: b'\n',
+            n_skip: 0,
+            pos: 0,
+            end: None,
+            sampled_len: 0,
         }
     }
+
+    /// Skip lines starting with the provided comment character (if any).
+    pub fn skip_comments(mut self, comment: Option<u8>) -> SampleIter<'a, R> {
+        self.comment = comment;
+        self
+    }
+
+    /// Skip the provided number of lines at the start of the sample (e.g. a comment-prefixed
+    /// preamble). Skipped lines don't count towards the sample size.
+    pub fn skip_lines(mut self, n_skip: usize) -> SampleIter<'a, R> {
+        self.n_skip = n_skip;
+        self
+    }
+
+    /// Stop the sample at the provided byte position (e.g. the start of a footer), if any.
+    pub fn end_at(mut self, end: Option<u64>) -> SampleIter<'a, R> {
+        self.end = end;
+        self
+    }
+
+    /// The number of bytes of the reader up to the end of the last line returned.
+    pub fn sampled_len(&self) -> u64 {
+        self.sampled_len
+    }
+
+    /// Split records on the provided byte, instead of `\n`.
+    pub fn split_on(mut self, record_end: u8) -> SampleIter<'a, R> {
+        self.record_end = record_end;
+        self
+    }
 }
 
 impl<'a, R: Read> Iterator for SampleIter<'a, R> {
@@ -52,17 +444,38 @@ impl<'a, R: Read> Iterator for SampleIter<'a, R> {
         }
 
         let mut buf = Vec::new();
-        let n_bytes_read = match self.reader.read_until(b'\n', &mut buf) {
-            Ok(n_bytes_read) => n_bytes_read,
-            Err(e) => {
-                return Some(Err(e.into()));
+        let n_bytes_read = loop {
+            if self.end.is_some_and(|end| self.pos >= end) {
+                self.is_done = true;
+                return None;
+            }
+            buf.clear();
+            let n_bytes_read = match self.reader.read_until(self.record_end, &mut buf) {
+                Ok(n_bytes_read) => n_bytes_read,
+                Err(e) => {
+                    return Some(Err(e.into()));
+                }
+            };
+            if n_bytes_read == 0 {
+                self.is_done = true;
+                return None;
+            }
+            self.pos += n_bytes_read as u64;
+            if self.n_skip > 0 {
+                self.n_skip -= 1;
+                continue;
+            }
+            match self.comment {
+                Some(comment) if buf[0] == comment => {}
+                _ => break n_bytes_read,
             }
         };
-        if n_bytes_read == 0 {
-            self.is_done = true;
-            return None;
-        }
 
+        if !buf.is_ascii() {
+            IS_ASCII.with(|flag| {
+                *flag.borrow_mut() = false;
+            });
+        }
         let mut output = simdutf8::basic::from_utf8(&buf).map_or_else(
             |_| {
                 // Its not all utf-8, set IS_UTF8 global to false
@@ -75,14 +488,16 @@ impl<'a, R: Read> Iterator for SampleIter<'a, R> {
         );
 
         let last_byte = (output.as_ref() as &[u8])[output.len() - 1];
-        if last_byte != b'\n' && last_byte != b'\r' {
-            // non CR/LF-ended line
+        if last_byte != b'\n' && last_byte != b'\r' && last_byte != self.record_end {
+            // non-terminated line
             // line was cut off before ending, so we ignore it!
             self.is_done = true;
             return None;
         }
 
-        output = output.trim_matches(|c| c == '\n' || c == '\r').into();
+        output = output
+            .trim_matches(|c| c == '\n' || c == '\r' || c == char::from(self.record_end))
+            .into();
         self.n_bytes += n_bytes_read;
         self.n_records += 1;
         match self.sample_size {
@@ -92,14 +507,21 @@ impl<'a, R: Read> Iterator for SampleIter<'a, R> {
                     return None;
                 }
             }
-            SampleSize::Bytes(max_bytes) => {
+            SampleSize::Bytes(max_bytes) | SampleSize::Adaptive { max_bytes, .. } => {
                 if self.n_bytes > max_bytes {
                     self.is_done = true;
                     return None;
                 }
             }
-            SampleSize::All => {}
+            SampleSize::Duration(_) => {
+                if self.n_bytes > MAX_TIMED_SAMPLE_BYTES {
+                    self.is_done = true;
+                    return None;
+                }
+            }
+            SampleSize::All | SampleSize::Percent(_) => {}
         }
+        self.sampled_len = self.pos;
         Some(Ok(output))
     }
 }
@@ -0,0 +1,208 @@
+/*!
+Reading of files compressed in the Snappy [framing format](https://github.com/google/snappy/blob/main/framing_format.txt)
+(`*.sz`, as qsv writes them).
+*/
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+
+use crate::sample::{read_sample, skip_start, SampleSize, SkipStart};
+
+// The chunk types of the framing format.
+const COMPRESSED_CHUNK: u8 = 0x00;
+const UNCOMPRESSED_CHUNK: u8 = 0x01;
+const STREAM_IDENTIFIER_CHUNK: u8 = 0xFF;
+
+const STREAM_IDENTIFIER: &[u8] = b"sNaPpY";
+
+/// A reader decompressing a Snappy-framed stream (with the `snappy` feature). The checksums of
+/// the chunks aren't verified.
+#[derive(Debug)]
+pub struct SnappyDecoder<R> {
+    inner: R,
+    // decompressed bytes, not yet returned (starting at `output_pos`)
+    output: Vec<u8>,
+    output_pos: usize,
+    // compressed chunk being read
+    chunk: Vec<u8>,
+}
+
+impl<R: Read> SnappyDecoder<R> {
+    /// Create a new `SnappyDecoder`, decompressing the stream `inner` reads.
+    pub fn new(inner: R) -> SnappyDecoder<R> {
+        SnappyDecoder {
+            inner,
+            output: vec![],
+            output_pos: 0,
+            chunk: vec![],
+        }
+    }
+
+    // Decompresses chunks until some data is decompressed, or the stream ends.
+    fn fill_output(&mut self) -> io::Result<()> {
+        self.output.clear();
+        self.output_pos = 0;
+        while self.output.is_empty() {
+            let mut header = Vec::with_capacity(4);
+            self.inner.by_ref().take(4).read_to_end(&mut header)?;
+            match header.len() {
+                0 => return Ok(()),
+                4 => {}
+                _ => return Err(corrupt("stream ends within a chunk header")),
+            }
+            let len =
+                usize::from(header[1]) | usize::from(header[2]) << 8 | usize::from(header[3]) << 16;
+            self.chunk.clear();
+            self.inner
+                .by_ref()
+                .take(len as u64)
+                .read_to_end(&mut self.chunk)?;
+            if self.chunk.len() < len {
+                return Err(corrupt("stream ends within a chunk"));
+            }
+            match header[0] {
+                // both start with the (masked CRC-32C) checksum of the data
+                COMPRESSED_CHUNK if len >= 4 => {
+                    decompress_block(&self.chunk[4..], &mut self.output)?;
+                }
+                UNCOMPRESSED_CHUNK if len >= 4 => self.output.extend_from_slice(&self.chunk[4..]),
+                STREAM_IDENTIFIER_CHUNK if self.chunk == STREAM_IDENTIFIER => {}
+                // padding, and other skippable chunks
+                0x80..=0xFE => {}
+                _ => return Err(corrupt("invalid chunk")),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for SnappyDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.output_pos == self.output.len() {
+            self.fill_output()?;
+        }
+        let available = &self.output[self.output_pos..];
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.output_pos += n;
+        Ok(n)
+    }
+}
+
+fn corrupt(msg: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("corrupt Snappy stream: {msg}"),
+    )
+}
+
+// Decompresses a block in the Snappy format (a varint of its decompressed length, followed by
+// literals, and copies of the preceding output), appending it to `output`.
+fn decompress_block(input: &[u8], output: &mut Vec<u8>) -> io::Result<()> {
+    let (mut len, mut pos) = (0, 0);
+    for shift in (0..32).step_by(7) {
+        let byte = *input.get(pos).ok_or_else(|| corrupt("truncated length"))?;
+        pos += 1;
+        len |= usize::from(byte & 0x7F) << shift;
+        if byte < 0x80 {
+            break;
+        }
+    }
+    let start = output.len();
+    output.reserve(len);
+    let read_le = |bytes: Option<&[u8]>| {
+        bytes
+            .map(|bytes| {
+                bytes
+                    .iter()
+                    .rev()
+                    .fold(0, |acc, &byte| acc << 8 | usize::from(byte))
+            })
+            .ok_or_else(|| corrupt("truncated block"))
+    };
+    while pos < input.len() {
+        let tag = input[pos];
+        pos += 1;
+        let (copy_len, offset) = match tag & 0x03 {
+            // a literal, whose length (less 1) is in the tag, or in up to 4 bytes following it
+            0 => {
+                let mut literal_len = usize::from(tag >> 2);
+                if literal_len >= 60 {
+                    let n_bytes = literal_len - 59;
+                    literal_len = read_le(input.get(pos..pos + n_bytes))?;
+                    pos += n_bytes;
+                }
+                let literal = input
+                    .get(pos..pos + literal_len + 1)
+                    .ok_or_else(|| corrupt("truncated literal"))?;
+                output.extend_from_slice(literal);
+                pos += literal.len();
+                continue;
+            }
+            1 => {
+                let offset = usize::from(tag >> 5) << 8 | read_le(input.get(pos..pos + 1))?;
+                pos += 1;
+                (usize::from((tag >> 2) & 0x07) + 4, offset)
+            }
+            2 => {
+                let offset = read_le(input.get(pos..pos + 2))?;
+                pos += 2;
+                (usize::from(tag >> 2) + 1, offset)
+            }
+            _ => {
+                let offset = read_le(input.get(pos..pos + 4))?;
+                pos += 4;
+                (usize::from(tag >> 2) + 1, offset)
+            }
+        };
+        if offset == 0 || offset > output.len() - start {
+            return Err(corrupt("invalid copy offset"));
+        }
+        // copies may overlap the bytes they produce (e.g. a run of one byte)
+        for _ in 0..copy_len {
+            output.push(output[output.len() - offset]);
+        }
+    }
+    if output.len() - start != len {
+        return Err(corrupt("wrong decompressed length"));
+    }
+    Ok(())
+}
+
+/// The decompressed start of a Snappy-framed file, long enough to fill a sample of the provided
+/// size (`SampleSize::All` decompresses the whole file). Unless the whole file is decompressed,
+/// the bytes after the last line break (a line cut off) are dropped. The sample starts past the
+/// skipped start of the file (if any); returns it along with the numbers of bytes and lines
+/// skipped.
+pub(crate) fn decompress_sample<R: Read>(
+    reader: R,
+    sample_size: SampleSize,
+    skip: Option<SkipStart>,
+) -> io::Result<(Vec<u8>, (u64, usize))> {
+    let mut reader = BufReader::new(SnappyDecoder::new(reader));
+    let skipped = match skip {
+        Some(skip) => skip_start(&mut reader, skip)?,
+        None => (0, 0),
+    };
+    let (mut sample, len) = read_sample(&mut reader, sample_size)?;
+    sample.truncate(len);
+    Ok((sample, skipped))
+}
+
+/// A reader over the decompressed contents of the Snappy-framed file at `path`, with its first
+/// `n_skip` lines skipped.
+pub(crate) fn open_decompressed(
+    path: &Path,
+    n_skip: usize,
+    record_end: u8,
+) -> io::Result<BufReader<SnappyDecoder<File>>> {
+    let mut reader = BufReader::new(SnappyDecoder::new(File::open(path)?));
+    let mut line = vec![];
+    for _ in 0..n_skip {
+        line.clear();
+        if reader.read_until(record_end, &mut line)? == 0 {
+            break;
+        }
+    }
+    Ok(reader)
+}
@@ -1,51 +1,299 @@
 use hashbrown::HashMap;
+use std::borrow::Cow;
 use std::cell::RefCell;
 use std::fs::File;
-use std::io::{Read, Seek, SeekFrom};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
 use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use csv::{Reader, StringRecord};
+use csv::{ByteRecord, Reader, StringRecord};
 use csv_core as csvc;
 use regex::Regex;
 
+#[cfg(feature = "gzip")]
+use crate::gzip;
+#[cfg(feature = "http")]
+use crate::http;
+#[cfg(any(feature = "gzip", feature = "snappy", feature = "zip"))]
+use crate::metadata::Compression;
+#[cfg(feature = "snappy")]
+use crate::snappy;
+#[cfg(feature = "zip")]
+use crate::zip;
 use crate::{
-    chain::{Chain, ViterbiResults, STATE_STEADYFLEX, STATE_STEADYSTRICT, STATE_UNSTEADY},
+    chain::{
+        Chain, VIteration, ViterbiResults, STATE_STEADYFLEX, STATE_STEADYSTRICT, STATE_UNSTEADY,
+    },
+    column::{ColumnOptions, ColumnStats, CurrencyStats, RadixStats},
+    compression::read_compression,
+    encoding::{
+        binary_byte_offsets, decode_text, detect_bom, find_invalid_utf8, rank_encodings, Decoder,
+    },
     error::{Result, SnifferError},
     field_type::{
-        get_best_types, infer_record_types, infer_types, DatePreference, Type, TypeGuesses,
+        get_best_types, has_base64_entropy, infer_record_types, infer_types, is_timezone_aware,
+        parse_decimal, strip_thousands, thousands_separator, DateFormatCandidates, DatePreference,
+        Type, TypeDetector, TypeGuesses, TypeOptions, BOOLEAN_LEXICON, NULL_TOKENS, SEMANTIC_TYPES,
+        THOUSANDS_SEPARATORS,
+    },
+    locale::Locale,
+    metadata::{
+        BlankLinePolicy, ChainState, ColumnQuoting, Comment, Coordinate, DateOrder, Dialect,
+        DialectCandidate, DialectConfidence, DialectHints, EmptyValues, Encoding,
+        EncodingCandidate, Escape, Header, IntegerWidth, InvalidUtf8, Metadata, NumberFormat,
+        Quote, QuoteStyle, Radix, Rejection, SniffDiagnostics, SniffResult, Terminator, Timezone,
+        Trim, TypeFit, ValueRange, RECORD_SEPARATOR,
+    },
+    replay::ReplayReader,
+    rewrite::{DelimiterRewriter, NewlineNormalizer, MULTIBYTE_REPLACEMENT},
+    sample::{
+        read_reservoir, read_sample, skip_start, take_sample_from_start, SampleBuffer, SampleIter,
+        SampleSize, SkipStart, SkippedReader, MAX_TIMED_SAMPLE_BYTES, SAMPLE_SLACK,
     },
-    metadata::{Dialect, Header, Metadata, Quote},
-    sample::{take_sample_from_start, SampleIter, SampleSize},
-    snip::snip_preamble,
+    snip::{comment_preamble_count, snip_preamble},
 };
 
 thread_local! (pub static IS_UTF8: RefCell<bool> = const { RefCell::new(true) });
+thread_local! (pub static IS_ASCII: RefCell<bool> = const { RefCell::new(true) });
+// Line prefixes recognized as comment markers, in order of precedence.
+const COMMENT_PREFIXES: [&str; 3] = ["#", "//", ";"];
+
+// Line prefixes recognized as comment-prefixed preamble rows at the start of a file.
+const COMMENT_PREAMBLE_PREFIXES: [&str; 2] = ["#", "//"];
+
+// Quote characters considered when sniffing, unless other candidates are provided.
+const QUOTE_CANDIDATES: [u8; 3] = [b'\'', b'"', b'`'];
+
+// Curly quotes, which are text rather than quote characters (or delimiters). In a sample which isn't
+// UTF-8, Windows-1252 curly quotes (0x91-0x94) are replaced by U+FFFD.
+const CURLY_QUOTES: [char; 5] = ['\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{FFFD}'];
+
+// Number of bytes sniffed to time how long sniffing takes, for a sample of `SampleSize::Duration`.
+const TIMING_SAMPLE_BYTES: usize = 1 << 12;
+
+// Fraction of the time budget left after timing which the sample size is fitted to, and the
+// smallest sample (in bytes), however short the budget.
+const TIMED_BUDGET_FRACTION: f64 = 0.5;
+const MIN_TIMED_SAMPLE_BYTES: usize = 1 << 10;
+
+// Size (in bytes) of the first sample tried for a sample of `SampleSize::Adaptive`.
+const ADAPTIVE_START_BYTES: usize = 1 << 12;
+
+// Number of bytes read at each of the places records are sampled from, past the start of the file
+// (see `Sniffer::random_samples`).
+const RANDOM_SAMPLE_BYTES: u64 = 1 << 12;
+
+// Number of bytes at the end of the file examined for footer rows.
+const FOOTER_SAMPLE_BYTES: u64 = 1 << 12;
+
+// Maximum number of summary lines following a blank line at the end of the file.
+const MAX_SUMMARY_ROWS: usize = 3;
+
+// Leading words (compared case-insensitively) which mark a footer row.
+const FOOTER_KEYWORDS: [&str; 9] = [
+    "total",
+    "totals",
+    "subtotal",
+    "grand total",
+    "sum",
+    "summary",
+    "end of report",
+    "end of file",
+    "end of data",
+];
+
+// Maximum number of (non-blank) rows in a file whose delimiter is chosen by punctuation class
+// rather than by frequencies alone.
+const MAX_SMALL_FILE_ROWS: usize = 2;
+
+// Minimum number of C-style escape sequences in unquoted fields for them to be considered
+// intentional.
+const MIN_EMBEDDED_ESCAPES: usize = 2;
+
+// Delimiters used to read single-column files, in order of preference (the first one which doesn't
+// occur in the sample is used). The unit separator is used if all of them occur.
+const SINGLE_COLUMN_DELIMITERS: [u8; 5] = [b',', b'\t', b';', b'|', 0x1F];
+
+// Multi-byte delimiters recognized when sniffing, in order of precedence.
+const MULTIBYTE_DELIMITERS: [&str; 4] = ["~|~", "|~|", "^|^", "||"];
+
+// Words in the names of columns which hold durations, which could otherwise be times of day.
+const DURATION_FIELD_NAMES: [&str; 4] = ["duration", "elapsed", "runtime", "length"];
+
+// Most distinct values a categorical column can have, unless specified.
+const DEFAULT_CATEGORY_THRESHOLD: usize = 20;
+
 thread_local! (pub static DATE_PREFERENCE: RefCell<DatePreference> = const { RefCell::new(DatePreference::MdyFormat) });
 
+// Minimum delimiter confidence accepted by strict sniffing.
+const STRICT_MIN_DELIMITER_CONFIDENCE: f64 = 0.5;
+
+/// Argument used when calling `utf8_handling` on `Sniffer`: how to handle invalid UTF-8 in the
+/// sampled values (of a file which isn't UTF-16, which is decoded instead).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Utf8Handling {
+    /// Fail with `SnifferError::InvalidUtf8` (reporting where it is) if the sample isn't valid
+    /// UTF-8.
+    Strict,
+    /// Replace invalid sequences with U+FFFD (the replacement character) before inferring types,
+    /// so that values which only differ in their invalid bytes are the same.
+    Lossy,
+    /// Take each byte of a field which isn't valid UTF-8 as a character of its own (as in
+    /// Latin-1), so that types are inferred from the bytes as written, and distinct values stay
+    /// distinct (e.g. in value ranges or categories).
+    Raw,
+}
+
+/// Argument used when calling `strictness` on `Sniffer`: how to handle contradictory evidence
+/// about the dialect.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Strictness {
+    /// Fail with a descriptive error when the evidence is contradictory: when no delimiter is
+    /// clearly better than the others (e.g. mixed delimiters), or when a quoted field is never
+    /// closed.
+    Strict,
+    /// Resolve contradictory evidence by choosing the most likely dialect, but fail when the
+    /// records can't be read with it (e.g. when a specified delimiter doesn't occur consistently,
+    /// or records of a non-flexible file have different lengths).
+    Balanced,
+    /// Always return a best-effort dialect, reading records of unexpected lengths as flexible.
+    Lenient,
+}
+
 /// A CSV sniffer.
 ///
 /// The sniffer examines a CSV file, passed in either through a file or a reader.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct Sniffer {
     // CSV file dialect guesses
     delimiter: Option<u8>,
+    multibyte_delimiter: Option<Vec<u8>>,
+    single_column: Option<bool>,
     num_preamble_rows: Option<usize>,
+    // comment-prefixed preamble rows, which are skipped before the other preamble rows
+    num_comment_preamble_rows: Option<usize>,
     has_header_row: Option<bool>,
     quote: Option<Quote>,
+    quote_style: Option<QuoteStyle>,
+    escape: Option<Escape>,
+    double_quote: Option<bool>,
+    comment: Option<Comment>,
+    terminator: Option<Terminator>,
+    trailing_delimiter: Option<bool>,
+    embedded_escapes: Option<bool>,
+    trim: Option<Trim>,
+    blank_lines: Option<BlankLinePolicy>,
     flexible: Option<bool>,
     is_utf8: Option<bool>,
+    encoding: Option<Encoding>,
+    encoding_candidates: Vec<EncodingCandidate>,
+    bom: Option<Encoding>,
+    invalid_utf8: Option<InvalidUtf8>,
+    is_ascii: bool,
+    sampled_bytes: u64,
+
+    // candidate delimiters to consider when sniffing (all ASCII characters if not specified)
+    delimiter_candidates: Option<Vec<u8>>,
+    // candidate quote characters to consider when sniffing (', " and ` if not specified)
+    quote_candidates: Option<Vec<u8>>,
+    // weights applied to the chain probabilities of delimiter candidates when ranking them
+    delimiter_priors: Vec<(u8, f64)>,
 
     // Metadata guesses
     delimiter_freq: Option<usize>,
     fields: Vec<String>,
+    fields_raw: Vec<Vec<u8>>,
     types: Vec<Type>,
+    column_quoting: Vec<ColumnQuoting>,
+    empty_values: Vec<EmptyValues>,
+    date_formats: Vec<Option<String>>,
+    timezones: Vec<Option<Timezone>>,
+    date_orders: Vec<Option<DateOrder>>,
+    null_tokens: Vec<Vec<String>>,
+    integer_widths: Vec<Option<IntegerWidth>>,
+    radixes: Vec<Option<Radix>>,
+    value_ranges: Vec<Option<ValueRange>>,
+    number_formats: Vec<Option<NumberFormat>>,
+    looks_like_code: Vec<bool>,
+    categories: Vec<Option<Vec<String>>>,
+    constant_values: Vec<Option<String>>,
+    phone_numbers: Vec<bool>,
+    has_non_finite: Vec<bool>,
+    coordinates: Vec<Option<Coordinate>>,
+    type_confidences: Vec<f64>,
+    type_fallbacks: Vec<Vec<TypeFit>>,
+    type_unions: Vec<Option<Vec<TypeFit>>>,
     avg_record_len: Option<usize>,
+    num_blank_lines: Option<usize>,
+    num_footer_rows: Option<usize>,
+    has_multiline_fields: Option<bool>,
+    // byte position of the first footer row (if any)
+    footer_start: Option<u64>,
+    decimal_separator: Option<u8>,
+    confidence: DialectConfidence,
+    // delimiter candidates considered by the last chain pass
+    candidates: Vec<DialectCandidate>,
 
     // sample size to sniff
     sample_size: Option<SampleSize>,
 
+    // how to handle contradictory evidence
+    strictness: Option<Strictness>,
+    utf8_handling: Option<Utf8Handling>,
+
     // date format preference
     date_preference: Option<DatePreference>,
+
+    // whether to drop the empty field produced by a trailing delimiter
+    drop_trailing_field: bool,
+
+    // whether to infer numeric columns which are always quoted as text
+    quoted_numbers_as_text: bool,
+
+    // whether to strip invisible characters from the field names
+    clean_field_names: bool,
+
+    // whether to infer types from the whole file, rather than from the sample
+    exhaustive_types: bool,
+    // number of places past the sample which records are also sampled from
+    random_samples: usize,
+    // whether records are also sampled from the middle and the end of the file
+    middle_and_tail: bool,
+    // start of the file skipped before sampling
+    skip_start: Option<SkipStart>,
+    // the end of the file being sniffed (and its offset), when it's out of the sample's reach but
+    // was fetched nonetheless (as `sniff_url` does)
+    #[cfg(feature = "http")]
+    tail: Option<(u64, Vec<u8>)>,
+    // number of records drawn at random from a stream which can't be rewound, if it's sampled whole
+    reservoir_size: Option<usize>,
+
+    // whether to infer hexadecimal and octal values as integers
+    prefixed_integers: bool,
+
+    // whether to report the types mixed in each column
+    type_unions_enabled: bool,
+
+    // pairs of (true, false) values recognized as booleans, besides the default ones
+    boolean_values: Vec<(String, String)>,
+    // values recognized as missing values (the default ones if not specified)
+    null_token_candidates: Option<Vec<String>>,
+    // whether non-finite values (NaN, inf) are floats (they are if not specified)
+    non_finite_floats: Option<bool>,
+    // most distinct values a categorical column can have
+    category_threshold: Option<usize>,
+    // thousands separator of numbers (any of the usual ones if not specified)
+    thousands_separator: Option<u8>,
+    // names of the months in dates, unless they're in English
+    month_names: Option<&'static [&'static str; 12]>,
+    // formats of dates, datetimes and times to try before the built-in ones
+    custom_date_formats: Vec<String>,
+    // detectors of custom types, in the order they were registered (shared by clones)
+    type_detectors: Vec<Arc<dyn TypeDetector>>,
+    // number of records parsed for a preview, and the records
+    preview_records: usize,
+    preview: Vec<Vec<String>>,
 }
 impl Sniffer {
     /// Create a new CSV sniffer.
@@ -57,32 +305,483 @@ impl Sniffer {
         self.delimiter = Some(delimiter);
         self
     }
+    /// Restrict delimiter detection to the provided candidate characters (e.g.
+    /// `&[b',', b'\t', b';']`). By default, any ASCII character is considered (including control
+    /// characters like the `0x1F` unit separator), which can lead to
+    /// surprising choices (such as `:` within timestamps) when the sample is short or ambiguous.
+    ///
+    /// Multi-byte delimiters aren't detected when the candidates are restricted.
+    pub fn delimiter_candidates(&mut self, candidates: &[u8]) -> &mut Sniffer {
+        self.delimiter_candidates = Some(candidates.to_vec());
+        self
+    }
+    /// Bias delimiter detection towards (or away from) a candidate delimiter, e.g.
+    /// `delimiter_prior(b'\t', 2.0)` for a file with a `.tsv` extension. The probability of the
+    /// candidate is multiplied by `weight` when ranking candidates, so a weight above `1.0`
+    /// favors the delimiter and a weight below `1.0` disfavors it.
+    ///
+    /// Priors only break ties between candidates which are equally consistent: a delimiter
+    /// which produces steady records is still chosen over a favored delimiter which doesn't.
+    pub fn delimiter_prior(&mut self, delimiter: u8, weight: f64) -> &mut Sniffer {
+        self.delimiter_priors
+            .retain(|&(delim, _)| delim != delimiter);
+        self.delimiter_priors.push((delimiter, weight));
+        self
+    }
+    /// Specify a multi-byte delimiter (e.g. `b"||"`). Since the [`csv`](https://docs.rs/csv) crate
+    /// only supports single-byte delimiters, files with a multi-byte delimiter need to be opened
+    /// with [`Dialect::open_rewritten_reader`](metadata/struct.Dialect.html#method.open_rewritten_reader).
+    pub fn multibyte_delimiter(&mut self, delimiter: &[u8]) -> &mut Sniffer {
+        if let [delimiter] = *delimiter {
+            self.delimiter = Some(delimiter);
+        } else {
+            self.multibyte_delimiter = Some(delimiter.to_vec());
+            self.delimiter = Some(MULTIBYTE_REPLACEMENT);
+        }
+        self
+    }
     /// Specify the header type (whether the CSV file has a header row, and where the data starts).
     pub fn header(&mut self, header: &Header) -> &mut Sniffer {
-        self.num_preamble_rows = Some(header.num_preamble_rows);
+        self.num_comment_preamble_rows = Some(header.num_comment_preamble_rows);
+        self.num_preamble_rows = Some(
+            header
+                .num_preamble_rows
+                .saturating_sub(header.num_comment_preamble_rows),
+        );
         self.has_header_row = Some(header.has_header_row);
         self
     }
+    /// Lock the known components of the dialect (e.g. "the delimiter is a tab"), so that only
+    /// the remaining components are sniffed. Locked components aren't detected, and are used as
+    /// ground truth when sniffing the others.
+    pub fn with_known(&mut self, hints: &DialectHints) -> &mut Sniffer {
+        if let Some(delimiter) = hints.delimiter {
+            self.delimiter(delimiter);
+        }
+        if let Some(has_header_row) = hints.has_header_row {
+            self.has_header_row = Some(has_header_row);
+        }
+        if let Some(num_preamble_rows) = hints.num_preamble_rows {
+            self.num_comment_preamble_rows = Some(0);
+            self.num_preamble_rows = Some(num_preamble_rows);
+        }
+        if let Some(quote) = &hints.quote {
+            self.quote(quote.clone());
+        }
+        if let Some(quote_style) = hints.quote_style {
+            self.quote_style = Some(quote_style);
+        }
+        if let Some(escape) = &hints.escape {
+            self.escape(escape.clone());
+        }
+        if let Some(double_quote) = hints.double_quote {
+            self.double_quote(double_quote);
+        }
+        if let Some(comment) = &hints.comment {
+            self.comment(comment.clone());
+        }
+        if let Some(terminator) = hints.terminator {
+            self.terminator = Some(terminator);
+        }
+        if let Some(trim) = hints.trim {
+            self.trim(trim);
+        }
+        if let Some(blank_lines) = hints.blank_lines {
+            self.blank_lines(blank_lines);
+        }
+        if let Some(flexible) = hints.flexible {
+            self.flexible = Some(flexible);
+        }
+        self
+    }
     /// Specify the quote character (if any), and whether two quotes in a row as to be interepreted
     /// as an escaped quote.
     pub fn quote(&mut self, quote: Quote) -> &mut Sniffer {
         self.quote = Some(quote);
         self
     }
+    /// Specify the candidate quote characters to consider when sniffing, in order of preference
+    /// (e.g. `b"\"'"`). By default, `'`, `"` and `` ` `` are considered.
+    pub fn quote_candidates(&mut self, candidates: &[u8]) -> &mut Sniffer {
+        self.quote_candidates = Some(candidates.to_vec());
+        self
+    }
+    /// Specify the escape character (if any) used to escape quotes within quoted fields.
+    pub fn escape(&mut self, escape: Escape) -> &mut Sniffer {
+        self.escape = Some(escape);
+        self
+    }
+    /// Specify whether two quotes in a row within a quoted field are interpreted as an escaped
+    /// quote.
+    pub fn double_quote(&mut self, double_quote: bool) -> &mut Sniffer {
+        self.double_quote = Some(double_quote);
+        self
+    }
+    /// Specify the comment character (if any). Records starting with this character are ignored.
+    pub fn comment(&mut self, comment: Comment) -> &mut Sniffer {
+        self.comment = Some(comment);
+        self
+    }
+
+    /// Specify the whitespace trimming applied to fields, instead of inferring it.
+    pub fn trim(&mut self, trim: Trim) -> &mut Sniffer {
+        self.trim = Some(trim);
+        self
+    }
+
+    /// Specify whether unquoted fields encode special characters as C-style escape sequences
+    /// (e.g. `\n`), instead of inferring it.
+    pub fn embedded_escapes(&mut self, embedded_escapes: bool) -> &mut Sniffer {
+        self.embedded_escapes = Some(embedded_escapes);
+        self
+    }
+
+    /// Specify how blank lines within the data should be interpreted, instead of inferring it.
+    pub fn blank_lines(&mut self, blank_lines: BlankLinePolicy) -> &mut Sniffer {
+        self.blank_lines = Some(blank_lines);
+        self
+    }
+
+    /// Whether to drop the empty trailing field from the reported fields and types when records
+    /// are terminated by a dangling delimiter (e.g. `a,b,c,`).
+    ///
+    /// Defaults to `false`.
+    pub fn drop_trailing_field(&mut self, drop_trailing_field: bool) -> &mut Sniffer {
+        self.drop_trailing_field = drop_trailing_field;
+        self
+    }
+
+    /// Whether to strip zero-width characters (e.g. U+200B, or a stray U+FEFF) and control
+    /// characters from the field names, and to replace no-break spaces with plain spaces, since
+    /// they invisibly break lookups of columns by name. The names as written are still in
+    /// `Metadata::fields_raw`.
+    ///
+    /// Defaults to `false`.
+    pub fn clean_field_names(&mut self, clean_field_names: bool) -> &mut Sniffer {
+        self.clean_field_names = clean_field_names;
+        self
+    }
+
+    /// Whether numeric columns whose values are always quoted (e.g. `"02134"` zip codes, or IDs)
+    /// are inferred as `Text`, since quoting them suggests they're meant to be text.
+    ///
+    /// Defaults to `false`.
+    pub fn quoted_numbers_as_text(&mut self, quoted_numbers_as_text: bool) -> &mut Sniffer {
+        self.quoted_numbers_as_text = quoted_numbers_as_text;
+        self
+    }
+
+    /// Recognize another pair of (true, false) values as booleans (e.g. `("si", "no")`), besides
+    /// `true`/`false`, `yes`/`no`, `y`/`n`, `t`/`f` and `1`/`0`. Values are compared
+    /// case-insensitively.
+    ///
+    /// A column is only inferred as boolean if all of its values belong to the same pair.
+    pub fn boolean_values(&mut self, true_value: &str, false_value: &str) -> &mut Sniffer {
+        self.boolean_values
+            .push((true_value.to_lowercase(), false_value.to_lowercase()));
+        self
+    }
+
+    /// Specify the values recognized as missing values (e.g. `&["NA", "-"]`), which are compared
+    /// case-insensitively. Missing values don't count towards the type of their column. Empty
+    /// fields are always missing values.
+    ///
+    /// Defaults to `NA`, `N/A`, `NULL`, `-` and `.`.
+    pub fn null_tokens(&mut self, tokens: &[&str]) -> &mut Sniffer {
+        self.null_token_candidates = Some(tokens.iter().map(ToString::to_string).collect());
+        self
+    }
+
+    /// Specify whether non-finite values (`NaN`, `inf`, `-Infinity`, in any case) are floating-point
+    /// numbers. Otherwise columns with such values are inferred as `Text`.
+    ///
+    /// Defaults to `true`.
+    pub fn non_finite_floats(&mut self, non_finite_floats: bool) -> &mut Sniffer {
+        self.non_finite_floats = Some(non_finite_floats);
+        self
+    }
+
+    /// Specify the most distinct values a column can have in the sample to be reported as
+    /// categorical (see [`Metadata::categories`](metadata/struct.Metadata.html#structfield.categories)).
+    ///
+    /// Defaults to 20.
+    pub fn category_threshold(&mut self, threshold: usize) -> &mut Sniffer {
+        self.category_threshold = Some(threshold);
+        self
+    }
+
+    /// Infer columns of hexadecimal (`0x1A2B`) or octal (`0o777`) values as integers, with their
+    /// radix (see [`Metadata::radixes`](metadata/struct.Metadata.html#structfield.radixes)), rather
+    /// than as text. This is off by default since many identifiers (e.g. hashes) look like
+    /// hexadecimal numbers without being meant as numbers.
+    ///
+    /// Defaults to `false`.
+    pub fn prefixed_integers(&mut self, prefixed_integers: bool) -> &mut Sniffer {
+        self.prefixed_integers = prefixed_integers;
+        self
+    }
+
+    /// Report the types mixed in each column, with the fraction of its values of each type (see
+    /// [`Metadata::type_unions`](metadata/struct.Metadata.html#structfield.type_unions)), rather
+    /// than only the type they collapse to.
+    ///
+    /// Defaults to `false`.
+    pub fn type_unions(&mut self, type_unions: bool) -> &mut Sniffer {
+        self.type_unions_enabled = type_unions;
+        self
+    }
+
+    /// Specify additional formats of dates, datetimes and times (e.g. `%d-%b-%y`, or `%Y%j` for
+    /// ordinal dates), in the syntax of
+    /// [`chrono::format::strftime`](https://docs.rs/chrono/latest/chrono/format/strftime/index.html).
+    /// Values in these formats are inferred as dates (or datetimes, or times), and the formats are
+    /// tried in order, before the built-in ones, when inferring the format of each date column
+    /// (see [`Metadata::date_formats`](metadata/struct.Metadata.html#structfield.date_formats)).
+    pub fn date_formats(&mut self, formats: &[&str]) -> &mut Sniffer {
+        self.custom_date_formats = formats.iter().map(ToString::to_string).collect();
+        self
+    }
+
+    /// Register a detector of a domain-specific type (e.g. NHS numbers or FIPS codes). Columns
+    /// whose values all match the detector are inferred as
+    /// [`Type::Custom`](enum.Type.html#variant.Custom), instead of their built-in type.
+    pub fn register_type_detector<D: TypeDetector + 'static>(
+        &mut self,
+        detector: D,
+    ) -> &mut Sniffer {
+        self.type_detectors.push(Arc::new(detector));
+        self
+    }
+
+    /// Specify the decimal separator used by floating-point fields (e.g. `b','` for European
+    /// locales), instead of inferring it.
+    pub fn decimal_separator(&mut self, decimal_separator: u8) -> &mut Sniffer {
+        self.decimal_separator = Some(decimal_separator);
+        self
+    }
+
+    /// Specify the character grouping the digits of numbers in threes (e.g. `b'.'` in
+    /// `1.234.567,89`), instead of recognizing any of `,`, `.`, a space and `'`.
+    pub fn thousands_separator(&mut self, thousands_separator: u8) -> &mut Sniffer {
+        self.thousands_separator = Some(thousands_separator);
+        self
+    }
+
+    /// Specify the locale numbers and dates are written in, which sets their decimal separator,
+    /// thousands separator, day/month order (see
+    /// [`date_preference`](#method.date_preference)) and the language of month names (e.g. `5.
+    /// März 2021` for `Locale::De`).
+    pub fn locale(&mut self, locale: Locale) -> &mut Sniffer {
+        self.decimal_separator(locale.decimal_separator())
+            .thousands_separator(locale.thousands_separator())
+            .date_preference(locale.date_preference());
+        self.month_names = locale.month_names();
+        self
+    }
 
-    /// The size of the sample to examine while sniffing. If using `SampleSize::Records`, the
-    /// sniffer will use the `Terminator::CRLF` as record separator.
+    /// The size of the sample to examine while sniffing. `SampleSize::Records` samples the same
+    /// number of records however wide they are (e.g. the first 1,000), where a sample of
+    /// `SampleSize::Bytes` holds fewer records of a wide file than of a narrow one.
     ///
-    /// The sample size defaults to `SampleSize::Bytes(4096)`.
+    /// The sample size defaults to `SampleSize::Bytes(16384)`.
     pub fn sample_size(&mut self, sample_size: SampleSize) -> &mut Sniffer {
         self.sample_size = Some(sample_size);
         self
     }
 
+    /// Skip the start of the file before sampling (such as a long license or XML header, which
+    /// would otherwise take up the sample), up to the end of a line: a number of bytes, or of
+    /// lines. The skipped lines are counted among the preamble rows of the sniffed dialect, so
+    /// that it reads the file from its start (and the lines of invalid UTF-8 sequences are
+    /// counted from its start). A reader which can't be rewound (see
+    /// [`sniff_reader_unseekable`](#method.sniff_reader_unseekable)) is read past the skipped
+    /// lines instead, which aren't counted (and [`sniff_url`](#method.sniff_url) downloads the
+    /// skipped lines too, rather than asking for the range of the sample).
+    ///
+    /// Defaults to not skipping anything.
+    pub fn skip_start(&mut self, skip: SkipStart) -> &mut Sniffer {
+        self.skip_start = Some(skip);
+        self
+    }
+
     fn get_sample_size(&self) -> SampleSize {
         self.sample_size.unwrap_or(SampleSize::Bytes(1 << 14))
     }
 
+    fn get_type_sample_size(&self) -> SampleSize {
+        if self.exhaustive_types {
+            SampleSize::All
+        } else {
+            self.get_sample_size()
+        }
+    }
+
+    /// Infer the types of the fields (and the other per-field metadata) from every row of the file,
+    /// rather than from the sample, so that the types are valid for every row. The dialect is
+    /// still sniffed from the sample.
+    ///
+    /// Defaults to `false`.
+    pub fn exhaustive_types(&mut self, exhaustive_types: bool) -> &mut Sniffer {
+        self.exhaustive_types = exhaustive_types;
+        self
+    }
+
+    /// Also infer the types of the fields (and the other per-field metadata) from the records at
+    /// `n_places` places spread through the rest of the file, past the sample, as the first rows
+    /// of a file (e.g. one sorted by date) may not be representative of the later ones. The file
+    /// is split into `n_places` parts, and a few kilobytes are read from a pseudo-random offset
+    /// within each, starting at the next line break; records of the wrong length (such as those
+    /// read from within a multi-line field) are skipped. The offsets only depend on the length of
+    /// the file, so the same file is always sniffed the same way. The dialect is still sniffed
+    /// from the sample, and nothing more is read for `SampleSize::All` (or exhaustive types).
+    ///
+    /// Defaults to `0`.
+    pub fn random_samples(&mut self, n_places: usize) -> &mut Sniffer {
+        self.random_samples = n_places;
+        self
+    }
+
+    /// Also infer the types of the fields (and the other per-field metadata) from the records in
+    /// the middle and at the end of the file, past the sample, as many bytes of each as the sample
+    /// takes (e.g. the first, middle and last 16 KiB), to catch changes later in the file cheaply.
+    /// The dialect is still sniffed from the sample, and footer rows are detected either way.
+    /// Records of the wrong length (such as those read from within a multi-line field) are
+    /// skipped, and nothing more is read for `SampleSize::All` (or exhaustive types). This can be
+    /// combined with [random samples](#method.random_samples).
+    ///
+    /// Defaults to `false`.
+    pub fn sample_middle_and_tail(&mut self, middle_and_tail: bool) -> &mut Sniffer {
+        self.middle_and_tail = middle_and_tail;
+        self
+    }
+
+    /// Parse the first `n_records` records of the data with the sniffed dialect, for a preview of
+    /// the file (see [`Metadata::preview`](struct.Metadata.html#structfield.preview)), so that it
+    /// doesn't need to be opened again. Records are decoded from the sniffed encoding.
+    ///
+    /// Defaults to `0`.
+    pub fn preview_records(&mut self, n_records: usize) -> &mut Sniffer {
+        self.preview_records = n_records;
+        self
+    }
+
+    /// Sniff a stream which can't be rewound (see
+    /// [`sniff_reader_unseekable`](#method.sniff_reader_unseekable)) which is sampled whole
+    /// (`SampleSize::All`, or exhaustive types) from a bounded sample rather than from all of it:
+    /// its first `n_records` records (for the dialect, and any preamble and header rows), followed
+    /// by `n_records` of the rest drawn uniformly at random by reservoir sampling, in the order
+    /// they occur. The whole stream is still read, but only the sample is held in memory. Records
+    /// are split on the line breaks which aren't between quotes, and the counts of the metadata
+    /// (such as the number of blank lines) are those of the sample.
+    ///
+    /// Defaults to `None` (buffering the whole stream).
+    pub fn reservoir_sample(&mut self, n_records: usize) -> &mut Sniffer {
+        self.reservoir_size = Some(n_records);
+        self
+    }
+
+    /// How to handle contradictory evidence about the dialect (see
+    /// [`Strictness`](enum.Strictness.html)).
+    ///
+    /// The strictness defaults to `Strictness::Balanced`.
+    pub fn strictness(&mut self, strictness: Strictness) -> &mut Sniffer {
+        self.strictness = Some(strictness);
+        self
+    }
+
+    fn get_strictness(&self) -> Strictness {
+        self.strictness.unwrap_or(Strictness::Balanced)
+    }
+
+    /// How to handle invalid UTF-8 in the sampled values (see
+    /// [`Utf8Handling`](enum.Utf8Handling.html)).
+    ///
+    /// Defaults to `Utf8Handling::Lossy`.
+    pub fn utf8_handling(&mut self, utf8_handling: Utf8Handling) -> &mut Sniffer {
+        self.utf8_handling = Some(utf8_handling);
+        self
+    }
+
+    fn get_utf8_handling(&self) -> Utf8Handling {
+        self.utf8_handling.unwrap_or(Utf8Handling::Lossy)
+    }
+
+    fn get_type_options(&self) -> TypeOptions {
+        TypeOptions {
+            decimal_separator: self.decimal_separator.unwrap_or(b'.'),
+            booleans: BOOLEAN_LEXICON
+                .iter()
+                .map(|&(true_value, false_value)| (true_value.to_string(), false_value.to_string()))
+                .chain(self.boolean_values.iter().cloned())
+                .collect(),
+            null_tokens: self
+                .null_token_candidates
+                .clone()
+                .unwrap_or_else(|| NULL_TOKENS.iter().map(ToString::to_string).collect()),
+            date_formats: self.custom_date_formats.clone(),
+            thousands_separators: self.get_thousands_separators(),
+            month_names: self.month_names,
+            non_finite_floats: self.non_finite_floats.unwrap_or(true),
+        }
+    }
+
+    fn get_thousands_separators(&self) -> Vec<u8> {
+        self.thousands_separator
+            .map_or(THOUSANDS_SEPARATORS.to_vec(), |separator| vec![separator])
+    }
+
+    fn get_comment(&self) -> Option<u8> {
+        self.comment.clone().and_then(Into::into)
+    }
+
+    fn get_quote_candidates(&self) -> &[u8] {
+        self.quote_candidates
+            .as_deref()
+            .unwrap_or(&QUOTE_CANDIDATES)
+    }
+
+    fn is_delimiter_candidate(&self, chr: u8) -> bool {
+        self.delimiter_candidates
+            .as_ref()
+            .map_or(true, |candidates| candidates.contains(&chr))
+    }
+
+    fn get_delimiter_prior(&self, chr: u8) -> f64 {
+        self.delimiter_priors
+            .iter()
+            .find(|&&(delim, _)| delim == chr)
+            .map_or(1.0, |&(_, weight)| weight)
+    }
+
+    fn get_record_end(&self) -> u8 {
+        self.terminator.map_or(b'\n', Terminator::record_end)
+    }
+
+    // Takes a sample from the start of the reader, split into records on the line terminator and
+    // skipping the comment-prefixed preamble, commented lines and footer.
+    fn take_sample<'a, R: Read + Seek>(&self, reader: &'a mut R) -> Result<SampleIter<'a, R>> {
+        Ok(take_sample_from_start(reader, self.get_sample_size())?
+            .split_on(self.get_record_end())
+            .skip_lines(self.num_comment_preamble_rows.unwrap_or(0))
+            .end_at(self.footer_start)
+            .skip_comments(self.get_comment()))
+    }
+
+    /// Whether ambiguous dates (e.g. `03/04/2021`) are read as day/month/year, rather than
+    /// month/day/year. Shorthand for
+    /// [`date_preference`](#method.date_preference) with `DatePreference::DmyFormat`.
+    ///
+    /// The preference only applies to columns in which every value is ambiguous: values such as
+    /// `25/04/2021` determine the order of their column regardless (see
+    /// [`Metadata::date_orders`](metadata/struct.Metadata.html#structfield.date_orders)).
+    pub fn prefer_dmy(&mut self, prefer_dmy: bool) -> &mut Sniffer {
+        self.date_preference(if prefer_dmy {
+            DatePreference::DmyFormat
+        } else {
+            DatePreference::MdyFormat
+        })
+    }
+
     /// The date format preference when sniffing.
     ///
     /// The date format preference defaults to `DatePreference::MDY`.
@@ -95,104 +794,1713 @@ impl Sniffer {
     }
 
     /// Sniff the CSV file located at the provided path, and return a `Reader` (from the
-    /// [`csv`](https://docs.rs/csv) crate) ready to ready the file.
+    /// [`csv`](https://docs.rs/csv) crate) ready to ready the file. As with
+    /// [`open_reader`](#method.open_reader), a UTF-16 file is decoded to UTF-8.
     ///
     /// Fails on file opening or readering errors, or on an error examining the file.
-    pub fn open_path<P: AsRef<Path>>(&mut self, path: P) -> Result<Reader<File>> {
+    pub fn open_path<P: AsRef<Path>>(&mut self, path: P) -> Result<Reader<Decoder<File>>> {
         self.open_reader(File::open(path)?)
     }
     /// Sniff the CSV file provided by the reader, and return a [`csv`](https://docs.rs/csv)
-    /// `Reader` object.
+    /// `Reader` object. A UTF-16 file (such as an Excel "Unicode Text" export) is decoded, so that
+    /// its records come out as UTF-8; files in other encodings are read as-is, save for a UTF-8
+    /// byte order mark (see [`Metadata::open_reader`](metadata/struct.Metadata.html#method.open_reader)
+    /// to decode those as well).
     ///
     /// Fails on file opening or readering errors, or on an error examining the file.
-    pub fn open_reader<R: Read + Seek>(&mut self, mut reader: R) -> Result<Reader<R>> {
+    pub fn open_reader<R: Read + Seek>(&mut self, mut reader: R) -> Result<Reader<Decoder<R>>> {
         let metadata = self.sniff_reader(&mut reader)?;
         reader.seek(SeekFrom::Start(0))?;
-        metadata.dialect.open_reader(reader)
+        let decode = matches!(metadata.encoding, Encoding::Utf16Le | Encoding::Utf16Be);
+        metadata.open_reader(reader, decode)
     }
 
     /// Sniff the CSV file located at the provided path, and return a
     /// [`Metadata`](struct.Metadata.html) object containing information about the CSV file.
     ///
-    /// Fails on file opening or readering errors, or on an error examining the file.
+    /// Compressed files are recognized by their magic bytes. With the `gzip` (or `snappy`)
+    /// feature, gzip-compressed (or Snappy-framed) files are decompressed, though only as far as
+    /// the sample reaches (so footer rows at the end of the file aren't detected unless the sample
+    /// size is `SampleSize::All`). Such files can be opened with
+    /// [`Dialect::open_gzip_path`](metadata/struct.Dialect.html#method.open_gzip_path) (or
+    /// [`Dialect::open_snappy_path`](metadata/struct.Dialect.html#method.open_snappy_path)).
+    ///
+    /// Fails on file opening or readering errors, on an error examining the file, or with
+    /// `SnifferError::UnsupportedCompression` for a file compressed in another format (or
+    /// gzip-compressed or Snappy-framed, without the matching feature). With the `zip` feature, the
+    /// first CSV file in a ZIP archive is sniffed, as with [`sniff_zip_path`](#method.sniff_zip_path).
     pub fn sniff_path<P: AsRef<Path>>(&mut self, path: P) -> Result<Metadata> {
-        let file = File::open(path)?;
+        let mut file = File::open(&path)?;
+        match read_compression(&mut file)? {
+            None => {}
+            #[cfg(feature = "gzip")]
+            Some(Compression::Gzip) => {
+                file.seek(SeekFrom::Start(0))?;
+                let (sample, (n_bytes, n_lines)) =
+                    gzip::decompress_sample(file, self.get_type_sample_size(), self.skip_start)?;
+                let metadata = self.sniff_sized(io::Cursor::new(sample))?;
+                return Ok(with_skipped_lines(metadata, n_lines, n_bytes));
+            }
+            #[cfg(feature = "snappy")]
+            Some(Compression::Snappy) => {
+                file.seek(SeekFrom::Start(0))?;
+                let (sample, (n_bytes, n_lines)) =
+                    snappy::decompress_sample(file, self.get_type_sample_size(), self.skip_start)?;
+                let metadata = self.sniff_sized(io::Cursor::new(sample))?;
+                return Ok(with_skipped_lines(metadata, n_lines, n_bytes));
+            }
+            #[cfg(feature = "zip")]
+            Some(Compression::Zip) => return self.sniff_zip_path(path, None),
+            Some(compression) => return Err(SnifferError::UnsupportedCompression(compression)),
+        }
+        file.seek(SeekFrom::Start(0))?;
         self.sniff_reader(&file)
     }
-    /// Sniff the CSV file provider by the reader, and return a
-    /// [`Metadata`](struct.Metadata.html) object containing information about the CSV file.
+    /// Sniff a CSV file within the ZIP archive located at the provided path (with the `zip`
+    /// feature), and return a [`Metadata`](struct.Metadata.html) object containing information
+    /// about it, including the name of the entry sniffed (`zip_entry`). The entry with the provided
+    /// name is sniffed, or if `entry` is `None`, the first one named like a delimited file (e.g.
+    /// `*.csv` or `*.tsv`). As for compressed files, only as much of the entry as the sample
+    /// reaches is decompressed; it can be opened with
+    /// [`Dialect::open_zip_path`](metadata/struct.Dialect.html#method.open_zip_path).
     ///
-    /// Fails on file opening or readering errors, or on an error examining the file.
-    pub fn sniff_reader<R: Read + Seek>(&mut self, mut reader: R) -> Result<Metadata> {
-        // init IS_UTF8 global var to true
-        IS_UTF8.with(|flag| {
-            *flag.borrow_mut() = true;
-        });
-        // guess quotes & delim
-        self.infer_quotes_delim(&mut reader)?;
-
-        // if we have a delimiter, we just need to search for num_preamble_rows and check for
-        // flexible. Otherwise, we need to guess a delimiter as well.
-        if self.delimiter.is_some() {
-            self.infer_preamble_known_delim(&mut reader)?;
-        } else {
-            self.infer_delim_preamble(&mut reader)?;
-        }
-
-        self.infer_types(&mut reader)?;
-        self.is_utf8 = Some(IS_UTF8.with(|flag| *flag.borrow()));
+    /// Fails on file opening or reading errors, if there's no such entry (or it's encrypted, or
+    /// compressed with a method other than deflate), or on an error examining the file.
+    #[cfg(feature = "zip")]
+    pub fn sniff_zip_path<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        entry: Option<&str>,
+    ) -> Result<Metadata> {
+        let (name, reader) = zip::open_entry(path.as_ref(), entry)?;
+        let mut reader = BufReader::new(reader);
+        let (n_bytes, n_lines) = self.skip_stream_start(&mut reader)?;
+        let (mut sample, len) = read_sample(&mut reader, self.get_type_sample_size())?;
+        sample.truncate(len);
+        let metadata = self.sniff_sized(io::Cursor::new(sample))?;
+        let mut metadata = with_skipped_lines(metadata, n_lines, n_bytes);
+        metadata.zip_entry = Some(name);
+        Ok(metadata)
+    }
 
-        // as this point of the process, we should have all these filled in.
-        // assert!(
-        //     self.delimiter.is_some()
-        //         && self.num_preamble_rows.is_some()
-        //         && self.quote.is_some()
-        //         && self.flexible.is_some()
-        //         && self.is_utf8.is_some()
-        //         && self.delimiter_freq.is_some()
-        //         && self.has_header_row.is_some()
-        //         && self.avg_record_len.is_some()
-        //         && self.delimiter_freq.is_some()
-        // );
-        if !(self.delimiter.is_some()
-            && self.num_preamble_rows.is_some()
-            && self.quote.is_some()
-            && self.flexible.is_some()
-            && self.is_utf8.is_some()
-            && self.delimiter_freq.is_some()
-            && self.has_header_row.is_some()
-            && self.avg_record_len.is_some()
-            && self.delimiter_freq.is_some())
+    /// Sniff the CSV data provided by a reader which can't be rewound (such as standard input),
+    /// and return a [`Metadata`](struct.Metadata.html) object containing information about the
+    /// data. As much of the data as the sample size calls for is read from the reader and buffered
+    /// (all of it, for `SampleSize::All`, unless a [reservoir sample](#method.reservoir_sample) is
+    /// taken); use [`open_reader_unseekable`](#method.open_reader_unseekable) to read the data
+    /// afterwards.
+    ///
+    /// Fails on a reading error, or on an error examining the data.
+    pub fn sniff_reader_unseekable<R: Read>(&mut self, reader: R) -> Result<Metadata> {
+        let mut reader = BufReader::new(reader);
+        self.skip_stream_start(&mut reader)?;
+        if let (SampleSize::All, Some(n_records)) =
+            (self.get_type_sample_size(), self.reservoir_size)
         {
-            return Err(SnifferError::SniffingFailed(format!(
-                "Failed to infer all metadata: {self:?}"
-            )));
+            let quote = match self.quote {
+                Some(Quote::Some(chr)) => chr,
+                _ => b'"',
+            };
+            let sample = read_reservoir(&mut reader, n_records, quote)?;
+            return self.sniff_sized(io::Cursor::new(sample));
         }
-        // safety: we just checked that all these are Some, so it's safe to unwrap
-        Ok(Metadata {
-            dialect: Dialect {
-                delimiter: self.delimiter.unwrap(),
-                header: Header {
-                    num_preamble_rows: self.num_preamble_rows.unwrap(),
-                    has_header_row: self.has_header_row.unwrap(),
-                },
-                quote: self.quote.clone().unwrap(),
-                flexible: self.flexible.unwrap(),
-                is_utf8: self.is_utf8.unwrap(),
-            },
-            avg_record_len: self.avg_record_len.unwrap(),
-            num_fields: self.delimiter_freq.unwrap() + 1,
-            fields: self.fields.clone(),
-            types: self.types.clone(),
+        let (mut sample, len) = read_sample(&mut reader, self.get_type_sample_size())?;
+        sample.truncate(len);
+        self.sniff_sized(io::Cursor::new(sample))
+    }
+
+    // Consumes the skipped start of a stream (if any), returning the numbers of bytes and lines
+    // skipped.
+    fn skip_stream_start<R: BufRead>(&self, reader: &mut R) -> Result<(u64, usize)> {
+        Ok(match self.skip_start {
+            Some(skip) => skip_start(reader, skip)?,
+            None => (0, 0),
         })
     }
 
-    // Infers quotes and delimiter from quoted (or possibly quoted) files. If quotes detected,
+    /// Sniff the CSV data in the provided buffer (such as an upload held in memory), and return a
+    /// [`Metadata`](struct.Metadata.html) object containing information about the data. A record
+    /// cut off at the end of the buffer is ignored.
+    ///
+    /// Fails on an error examining the data.
+    pub fn sniff_bytes(&mut self, bytes: &[u8]) -> Result<Metadata> {
+        self.sniff_reader(io::Cursor::new(bytes))
+    }
+
+    /// Sniff the CSV file at the provided `http://` URL (with the `http` feature), and return a
+    /// [`Metadata`](struct.Metadata.html) object containing information about the file. Only the
+    /// start of the file is downloaded: for a sample of `SampleSize::Bytes`, the request asks for
+    /// just that many bytes (and a little more) with a `Range` header, and otherwise the download
+    /// stops once the sample is read (or the whole file is, for `SampleSize::All`). Unless the
+    /// whole file is sampled, its last few kilobytes are asked for as well (with a `Range` header),
+    /// to detect footer rows: they aren't detected if the server doesn't send them.
+    ///
+    /// Redirects are followed (up to 5 of them), and a request fails if connecting or any read
+    /// takes more than 30 seconds. `https://` URLs aren't supported, for lack of a TLS
+    /// implementation. Fails on a connection or HTTP error, or on an error examining the file.
+    #[cfg(feature = "http")]
+    pub fn sniff_url(&mut self, url: &str) -> Result<Metadata> {
+        let len = match self.get_type_sample_size() {
+            _ if self.skip_start.is_some() => None,
+            // one more byte than is sampled, so that a line cut off by the end of the range is
+            // told apart from the end of the file
+            SampleSize::Bytes(n_bytes)
+            | SampleSize::Adaptive {
+                max_bytes: n_bytes, ..
+            } => Some(n_bytes + SAMPLE_SLACK as usize + 1),
+            SampleSize::Duration(_) => Some(MAX_TIMED_SAMPLE_BYTES + 1),
+            SampleSize::Records(_) | SampleSize::All | SampleSize::Percent(_) => None,
+        };
+        let body = http::get(url, len)?;
+        if matches!(self.get_type_sample_size(), SampleSize::All) {
+            return self.sniff_reader_unseekable(body);
+        }
+        let mut reader = BufReader::new(body);
+        let (n_skipped, _) = self.skip_stream_start(&mut reader)?;
+        let (mut sample, len) = read_sample(&mut reader, self.get_type_sample_size())?;
+        sample.truncate(len);
+        drop(reader);
+        // offsets within the sampled data, which starts after the skipped lines
+        self.tail = http::get_tail(url, FOOTER_SAMPLE_BYTES)?.map(|(tail_start, mut tail)| {
+            tail.drain(..n_skipped.saturating_sub(tail_start).min(tail.len() as u64) as usize);
+            (tail_start.saturating_sub(n_skipped), tail)
+        });
+        let metadata = self.sniff_sized(io::Cursor::new(sample));
+        self.tail = None;
+        metadata
+    }
+
+    /// Sniff the CSV data provided by a reader which can't be rewound (such as standard input), as
+    /// [`sniff_reader_unseekable`](#method.sniff_reader_unseekable) does, and return the
+    /// [`Metadata`](struct.Metadata.html) along with a
+    /// [`ReplayReader`](struct.ReplayReader.html) which replays the buffered sample before the rest
+    /// of the data. It can be passed to
+    /// [`Dialect::open_reader`](metadata/struct.Dialect.html#method.open_reader) (or
+    /// `Metadata::open_reader`), which skip the preamble without the reader being seekable. Any
+    /// reader will do (such as a `File`), so that the start of the data is only read once, for
+    /// sniffing and then for processing it.
+    ///
+    /// Fails on a reading error, or on an error examining the data.
+    pub fn sniff_replayable<R: Read>(&mut self, reader: R) -> Result<(Metadata, ReplayReader<R>)> {
+        let mut reader = BufReader::new(reader);
+        self.skip_stream_start(&mut reader)?;
+        let (sample, len) = read_sample(&mut reader, self.get_type_sample_size())?;
+        let metadata = self.sniff_sized(io::Cursor::new(&sample[..len]))?;
+        Ok((metadata, ReplayReader::new(sample, reader)))
+    }
+
+    /// Sniff the CSV data provided by a reader which can't be rewound (such as standard input),
+    /// and return a `Reader` (from the [`csv`](https://docs.rs/csv) crate) ready to read the data,
+    /// which reads the buffered sample before the rest of the data (see
+    /// [`sniff_replayable`](#method.sniff_replayable)).
+    ///
+    /// Fails on a reading error, on an error examining the data, or if the sniffed dialect uses a
+    /// multi-byte delimiter.
+    pub fn open_reader_unseekable<R: Read>(
+        &mut self,
+        reader: R,
+    ) -> Result<Reader<ReplayReader<R>>> {
+        let (metadata, reader) = self.sniff_replayable(reader)?;
+        metadata.dialect.open_reader(reader)
+    }
+
+    /// Create a [`SampleBuffer`](struct.SampleBuffer.html) for the sample size and the skipped
+    /// start of this sniffer, to push the start of a stream into as it arrives (e.g. the chunks
+    /// read from an asynchronous reader) until it's full, and then sniff with
+    /// [`sniff_sample_buffer`](#method.sniff_sample_buffer). Sniffing needs no more of the stream
+    /// than this sniffer reading it with
+    /// [`sniff_reader_unseekable`](#method.sniff_reader_unseekable) would have.
+    pub fn sample_buffer(&self) -> SampleBuffer {
+        SampleBuffer::new(self.get_type_sample_size(), self.skip_start)
+    }
+
+    /// Sniff the start of the stream pushed into `buffer` (created by
+    /// [`sample_buffer`](#method.sample_buffer), with the same options), as
+    /// [`sniff_reader_unseekable`](#method.sniff_reader_unseekable) sniffs a stream, and return a
+    /// [`Metadata`](struct.Metadata.html) object containing information about the data. The
+    /// stream is taken to have ended if the buffer isn't full.
+    ///
+    /// Fails on an error examining the data.
+    pub fn sniff_sample_buffer(&mut self, buffer: &SampleBuffer) -> Result<Metadata> {
+        self.sniff_reader_unseekable(buffer.as_bytes())
+    }
+
+    /// Sniff the CSV file provided by the reader, and return a
+    /// [`Metadata`](struct.Metadata.html) object along with
+    /// [`SniffDiagnostics`](metadata/struct.SniffDiagnostics.html) describing the candidate
+    /// delimiters that were scored, and why each one was rejected. Useful for figuring out why a
+    /// file was sniffed incorrectly.
+    ///
+    /// Fails on file opening or readering errors, or on an error examining the file.
+    pub fn sniff_with_diagnostics<R: Read + Seek>(
+        &mut self,
+        reader: R,
+    ) -> Result<(Metadata, SniffDiagnostics)> {
+        let metadata = self.sniff_reader(reader)?;
+        let diagnostics = SniffDiagnostics {
+            candidates: self.candidates.clone(),
+        };
+        Ok((metadata, diagnostics))
+    }
+
+    /// Re-sniff a CSV file which has grown since it was sniffed (such as a log that's appended to
+    /// continuously), examining only the data after `offset`, where the data `previous` was
+    /// sniffed from ends (e.g. the length of the file when it was sniffed with `SampleSize::All`).
+    /// Returns the metadata of the whole file, merging `previous` with that of the appended data,
+    /// along with the offset where the examined data ends, to pass to the next re-sniff.
+    ///
+    /// The appended data is sniffed with the dialect of `previous` (without preamble or header
+    /// rows), up to the sample size. Column types are widened to fit both (e.g. `Unsigned` and
+    /// `Float` to `Float`, or `Date` and `Text` to `Text`), counts are added up, and the details of
+    /// columns which differ (e.g. date formats, or constant values) are dropped; confidences are
+    /// weighted by the (estimated) number of records on each side. Columns only the appended data
+    /// has are added, with empty names (if `previous` has field names). `offset` must be at the
+    /// start of a record. If no whole record has been appended, `previous` is returned as is.
+    ///
+    /// Fails on a reading error, or on an error examining the appended data.
+    pub fn resniff_appended<R: Read + Seek>(
+        &mut self,
+        mut reader: R,
+        previous: &Metadata,
+        offset: u64,
+    ) -> Result<(Metadata, u64)> {
+        reader.seek(SeekFrom::Start(offset))?;
+        let (sample, len) = read_sample(
+            &mut BufReader::new(&mut reader),
+            self.get_type_sample_size(),
+        )?;
+        if len == 0 {
+            return Ok((previous.clone(), offset));
+        }
+        // the appended data is sniffed with the dialect of the previous metadata, which this
+        // sniffer keeps no trace of
+        let mut sniffer = self.clone();
+        let dialect = &previous.dialect;
+        if let Some(ref delimiter) = dialect.multibyte_delimiter {
+            sniffer.multibyte_delimiter(delimiter);
+        }
+        sniffer.with_known(&DialectHints {
+            delimiter: dialect
+                .multibyte_delimiter
+                .is_none()
+                .then_some(dialect.delimiter),
+            has_header_row: Some(false),
+            num_preamble_rows: Some(0),
+            quote: Some(dialect.quote.clone()),
+            quote_style: Some(dialect.quote_style),
+            escape: Some(dialect.escape.clone()),
+            double_quote: Some(dialect.double_quote),
+            comment: Some(dialect.comment.clone()),
+            terminator: Some(dialect.terminator),
+            trim: Some(dialect.trim),
+            blank_lines: Some(dialect.blank_lines),
+            flexible: None,
+        });
+        let appended = sniffer.sniff_sized(io::Cursor::new(&sample[..len]))?;
+        // the lines of invalid UTF-8 sequences are counted from the start of the file
+        let n_lines_before = if previous.invalid_utf8.is_none() && appended.invalid_utf8.is_some() {
+            reader.seek(SeekFrom::Start(0))?;
+            let mut n_lines = 0;
+            let mut reader = BufReader::new(reader.take(offset));
+            loop {
+                let buf = reader.fill_buf()?;
+                if buf.is_empty() {
+                    break;
+                }
+                n_lines += bytecount::count(buf, b'\n');
+                let n_read = buf.len();
+                reader.consume(n_read);
+            }
+            n_lines
+        } else {
+            0
+        };
+        let metadata = sniffer.merge_appended(previous, appended, offset, len, n_lines_before);
+        Ok((metadata, offset + len as u64))
+    }
+
+    // Merges the metadata of data appended to a file (`len` bytes from `offset`) into that of the
+    // file before it. The columns only one side has are taken as they are, and the details of a
+    // column without values on one side (typed `NULL`) come from the other.
+    fn merge_appended(
+        &mut self,
+        previous: &Metadata,
+        appended: Metadata,
+        offset: u64,
+        len: usize,
+        n_lines_before: usize,
+    ) -> Metadata {
+        // estimates of the numbers of records each side was sniffed from
+        let n_previous = offset as f64 / previous.avg_record_len.max(1) as f64;
+        let n_appended = len as f64 / appended.avg_record_len.max(1) as f64;
+        let weigh = |a: f64, b: f64| (a * n_previous + b * n_appended) / (n_previous + n_appended);
+        let threshold = self
+            .category_threshold
+            .unwrap_or(DEFAULT_CATEGORY_THRESHOLD);
+
+        let mut metadata = previous.clone();
+        let n_common = previous.types.len().min(appended.types.len());
+        // the columns only the appended data has (which has no header row to name them)
+        macro_rules! extend_columns {
+            ($($column:ident),*) => {
+                $(metadata
+                    .$column
+                    .extend_from_slice(appended.$column.get(n_common..).unwrap_or_default());)*
+            };
+        }
+        if appended.types.len() > n_common {
+            if !metadata.fields.is_empty() {
+                metadata.fields.resize(appended.types.len(), String::new());
+                metadata.fields_raw.resize(appended.types.len(), vec![]);
+            }
+            extend_columns!(
+                types,
+                date_formats,
+                timezones,
+                date_orders,
+                nullable,
+                null_tokens,
+                integer_widths,
+                radixes,
+                value_ranges,
+                number_formats,
+                has_non_finite,
+                looks_like_code,
+                categories,
+                constant_values,
+                phone_numbers,
+                coordinates,
+                type_confidences,
+                type_fallbacks,
+                type_unions,
+                column_quoting,
+                empty_values
+            );
+        }
+
+        for i in 0..n_common {
+            let (ty_a, ty_b) = (previous.types[i], appended.types[i]);
+            let ty = merge_type(ty_a, ty_b);
+            metadata.types[i] = ty;
+            metadata.nullable[i] |= appended.nullable[i];
+            for token in &appended.null_tokens[i] {
+                if !metadata.null_tokens[i].contains(token) {
+                    metadata.null_tokens[i].push(token.clone());
+                }
+            }
+            metadata.has_non_finite[i] |= appended.has_non_finite[i];
+            metadata.looks_like_code[i] |= appended.looks_like_code[i];
+            metadata.type_confidences[i] =
+                weigh(previous.type_confidences[i], appended.type_confidences[i]);
+            metadata.empty_values[i] = match (previous.empty_values[i], appended.empty_values[i]) {
+                (a, b) if a == b => a,
+                (EmptyValues::Absent, other) | (other, EmptyValues::Absent) => other,
+                _ => EmptyValues::Mixed,
+            };
+            if ty_a == Type::NULL {
+                // the previous sample had no values
+                metadata.date_formats[i] = appended.date_formats[i].clone();
+                metadata.timezones[i] = appended.timezones[i];
+                metadata.date_orders[i] = appended.date_orders[i];
+                metadata.integer_widths[i] = appended.integer_widths[i];
+                metadata.radixes[i] = appended.radixes[i];
+                metadata.number_formats[i] = appended.number_formats[i];
+                metadata.categories[i] = appended.categories[i].clone();
+                metadata.constant_values[i] = appended.constant_values[i].clone();
+                metadata.phone_numbers[i] = appended.phone_numbers[i];
+                metadata.coordinates[i] = appended.coordinates[i];
+                metadata.type_fallbacks[i] = appended.type_fallbacks[i].clone();
+                metadata.type_unions[i] = appended.type_unions[i].clone();
+                metadata.column_quoting[i] = appended.column_quoting[i];
+                continue;
+            }
+            if ty_b == Type::NULL {
+                continue;
+            }
+            let agreed =
+                |a: &Option<String>, b: &Option<String>| (a == b).then(|| a.clone()).flatten();
+            let is_date = matches!(ty, Type::Date | Type::DateTime | Type::Time);
+            let is_integer = matches!(ty, Type::Unsigned | Type::Signed);
+            metadata.date_formats[i] =
+                agreed(&previous.date_formats[i], &appended.date_formats[i]).filter(|_| is_date);
+            if metadata.date_formats[i].is_none() {
+                metadata.timezones[i] = None;
+                metadata.date_orders[i] = None;
+            } else if metadata.date_orders[i] != appended.date_orders[i] {
+                metadata.date_orders[i] = None;
+            }
+            metadata.integer_widths[i] =
+                match (previous.integer_widths[i], appended.integer_widths[i]) {
+                    (Some(a), Some(b)) if is_integer => Some(wider_integer(a, b)),
+                    _ => None,
+                };
+            if !is_integer || metadata.radixes[i] != appended.radixes[i] {
+                metadata.radixes[i] = None;
+            }
+            if !matches!(ty, Type::Unsigned | Type::Signed | Type::Float)
+                || metadata.number_formats[i] != appended.number_formats[i]
+            {
+                metadata.number_formats[i] = None;
+            }
+            metadata.categories[i] = match (&previous.categories[i], &appended.categories[i]) {
+                (Some(a), Some(b)) => {
+                    let mut categories = a.clone();
+                    for category in b {
+                        if !categories.contains(category) {
+                            categories.push(category.clone());
+                        }
+                    }
+                    (categories.len() <= threshold).then_some(categories)
+                }
+                _ => None,
+            };
+            metadata.constant_values[i] =
+                agreed(&previous.constant_values[i], &appended.constant_values[i]);
+            metadata.phone_numbers[i] &= appended.phone_numbers[i];
+            if metadata.coordinates[i] != appended.coordinates[i] {
+                metadata.coordinates[i] = None;
+            }
+            // the types fitting both sides, with their weighted fractions
+            metadata.type_fallbacks[i] = previous.type_fallbacks[i]
+                .iter()
+                .filter_map(|a| {
+                    let b = appended.type_fallbacks[i].iter().find(|b| b.ty == a.ty)?;
+                    Some(TypeFit {
+                        ty: a.ty,
+                        confidence: weigh(a.confidence, b.confidence),
+                    })
+                })
+                .collect();
+            if self.type_unions_enabled {
+                let single = |ty| {
+                    vec![TypeFit {
+                        ty,
+                        confidence: 1.0,
+                    }]
+                };
+                let a = previous.type_unions[i]
+                    .clone()
+                    .unwrap_or_else(|| single(ty_a));
+                let b = appended.type_unions[i]
+                    .clone()
+                    .unwrap_or_else(|| single(ty_b));
+                let mut union = a
+                    .iter()
+                    .map(|a| {
+                        let b = b
+                            .iter()
+                            .find(|b| b.ty == a.ty)
+                            .map_or(0.0, |b| b.confidence);
+                        TypeFit {
+                            ty: a.ty,
+                            confidence: weigh(a.confidence, b),
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                for b in &b {
+                    if !a.iter().any(|a| a.ty == b.ty) {
+                        union.push(TypeFit {
+                            ty: b.ty,
+                            confidence: weigh(0.0, b.confidence),
+                        });
+                    }
+                }
+                union.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+                metadata.type_unions[i] = (union.len() > 1).then_some(union);
+            }
+            if metadata.column_quoting[i] != appended.column_quoting[i] {
+                metadata.column_quoting[i] = ColumnQuoting::Mixed;
+            }
+        }
+
+        // the value ranges of both sides are ranked together, as the values of the merged types
+        let options = ColumnOptions {
+            prefixed_integers: true,
+            ..self.get_column_options()
+        };
+        let range_stats = metadata
+            .value_ranges
+            .iter()
+            .zip(&appended.value_ranges)
+            .enumerate()
+            .map(|(i, (a, b))| {
+                // prefixed integers are only summarized as such while they're text
+                let ty = match metadata.radixes.get(i) {
+                    Some(Some(_)) => Type::Text,
+                    _ => metadata.types[i],
+                };
+                let mut stats = ColumnStats::new(ty, &options);
+                if let (Some(candidates), Some(format)) =
+                    (&mut stats.date_formats, &metadata.date_formats[i])
+                {
+                    *candidates = DateFormatCandidates::only(format);
+                }
+                for value in a.iter().chain(b).flat_map(|range| [&range.min, &range.max]) {
+                    stats.add(value, &options);
+                }
+                stats
+            })
+            .collect::<Vec<_>>();
+        self.types.clone_from(&metadata.types);
+        self.radixes.clone_from(&metadata.radixes);
+        self.date_formats.clone_from(&metadata.date_formats);
+        self.infer_value_ranges(&range_stats);
+        for (i, range) in self.value_ranges.drain(..).enumerate().take(n_common) {
+            metadata.value_ranges[i] = range;
+        }
+
+        metadata.num_fields = previous.num_fields.max(appended.num_fields);
+        metadata.dialect.flexible = previous.dialect.flexible
+            || appended.dialect.flexible
+            || previous.num_fields != appended.num_fields;
+        metadata.dialect.is_utf8 &= appended.dialect.is_utf8;
+        metadata.is_ascii &= appended.is_ascii;
+        metadata.invalid_utf8 = match (&previous.invalid_utf8, appended.invalid_utf8) {
+            (Some(a), Some(b)) => Some(InvalidUtf8 {
+                locations: a.locations.clone(),
+                count: a.count + b.count,
+            }),
+            (None, Some(mut b)) => {
+                for location in &mut b.locations {
+                    location.line += n_lines_before;
+                    location.offset += offset;
+                }
+                Some(b)
+            }
+            (a, None) => a.clone(),
+        };
+        metadata.avg_record_len =
+            ((offset as f64 + len as f64) / (n_previous + n_appended)).round() as usize;
+        metadata.num_blank_lines += appended.num_blank_lines;
+        metadata.num_footer_rows = appended.num_footer_rows;
+        metadata.has_multiline_fields |= appended.has_multiline_fields;
+        metadata
+    }
+
+    // Infers the encoding from the start of the file (or all of it, when the whole file is
+    // sampled, so a non-UTF-8 byte near its end isn't missed).
+    fn infer_encoding<R: Read + Seek>(&mut self, reader: &mut R) -> Result<()> {
+        reader.seek(SeekFrom::Start(0))?;
+        let mut sample = vec![];
+        match self.get_type_sample_size() {
+            SampleSize::All => reader.read_to_end(&mut sample)?,
+            _ => reader.by_ref().take(1 << 14).read_to_end(&mut sample)?,
+        };
+        reader.seek(SeekFrom::Start(0))?;
+        let encoding_candidates = rank_encodings(&sample);
+        let encoding = encoding_candidates[0].encoding;
+        if !matches!(encoding, Encoding::Utf16Le | Encoding::Utf16Be) {
+            if let Some(offsets) = binary_byte_offsets(&sample) {
+                return Err(SnifferError::NotTextual(offsets));
+            }
+        }
+        self.encoding = Some(encoding);
+        self.encoding_candidates = encoding_candidates;
+        self.bom = detect_bom(&sample);
+        Ok(())
+    }
+
+    // Sniffs the dialect and types of a (UTF-8, or at least ASCII-compatible) file.
+    fn sniff_decoded<R: Read + Seek>(&mut self, reader: &mut R) -> Result<()> {
+        // guess line terminator; a file mixing terminators is examined with all of them
+        // normalized to `\n`, so that bare `\r`s don't hide the ends of records (the dialect still
+        // reports the terminator as mixed)
+        self.infer_terminator(reader)?;
+        if self.terminator == Some(Terminator::Mixed) {
+            self.sniff_lines(&mut NewlineNormalizer::new(reader))
+        } else {
+            self.sniff_lines(reader)
+        }
+    }
+
+    // Sniffs the dialect and types of a file, once its line terminator is known.
+    fn sniff_lines<R: Read + Seek>(&mut self, reader: &mut R) -> Result<()> {
+        // count comment-prefixed preamble rows, which are skipped in the remaining passes
+        self.infer_comment_preamble(reader)?;
+        // guess comment character, so commented lines can be ignored in the remaining passes
+        self.infer_comment(reader)?;
+        // guess multi-byte delimiter; if there is one, the remaining passes examine a reader that
+        // rewrites it to a single byte
+        self.infer_multibyte_delim(reader)?;
+        match self.multibyte_delimiter.clone() {
+            Some(delim) => self.infer_dialect_types(&mut DelimiterRewriter::new(
+                reader,
+                &delim,
+                MULTIBYTE_REPLACEMENT,
+            ))?,
+            None => self.infer_dialect_types(reader)?,
+        }
+        Ok(())
+    }
+
+    /// Sniff the CSV file provider by the reader, and return a
+    /// [`Metadata`](struct.Metadata.html) object containing information about the CSV file.
+    ///
+    /// Fails on file opening or readering errors, or on an error examining the file.
+    pub fn sniff_reader<R: Read + Seek>(&mut self, mut reader: R) -> Result<Metadata> {
+        let Some(skip) = self.skip_start else {
+            return self.sniff_sized(reader);
+        };
+        reader.seek(SeekFrom::Start(0))?;
+        let (start, n_skipped) = skip_start(&mut BufReader::new(&mut reader), skip)?;
+        let metadata = self.sniff_sized(SkippedReader::new(&mut reader, start)?)?;
+        Ok(with_skipped_lines(metadata, n_skipped, start))
+    }
+
+    // Sniffs the reader, with a sample of a size in records or bytes (or the whole file), turning
+    // other sample sizes into one of those.
+    fn sniff_sized<R: Read + Seek>(&mut self, mut reader: R) -> Result<Metadata> {
+        // a time budget (or a percentage, or a confidence to reach) is turned into a number of bytes
+        // for the duration of the sniff
+        let sample_size = self.sample_size;
+        match sample_size {
+            Some(SampleSize::Duration(budget)) => {
+                self.sample_size = Some(timed_sample_size(self, &mut reader, budget)?);
+            }
+            Some(SampleSize::Percent(percent)) => {
+                let len = reader.seek(SeekFrom::End(0))?;
+                reader.seek(SeekFrom::Start(0))?;
+                self.sample_size = Some(if percent >= 100.0 {
+                    SampleSize::All
+                } else {
+                    SampleSize::Bytes((len as f64 * f64::from(percent.max(0.0)) / 100.0) as usize)
+                });
+            }
+            Some(SampleSize::Adaptive {
+                min_confidence,
+                max_bytes,
+            }) => {
+                self.sample_size = Some(adaptive_sample_size(
+                    self,
+                    &mut reader,
+                    min_confidence,
+                    max_bytes,
+                )?);
+            }
+            _ => {}
+        }
+        let metadata = self.sniff_sample(&mut reader);
+        self.sample_size = sample_size;
+        metadata
+    }
+
+    // Sniffs the sample of the reader (of a size in records or bytes, or the whole file).
+    fn sniff_sample<R: Read + Seek>(&mut self, mut reader: R) -> Result<Metadata> {
+        // init IS_UTF8 and IS_ASCII global vars to true
+        IS_UTF8.with(|flag| {
+            *flag.borrow_mut() = true;
+        });
+        IS_ASCII.with(|flag| {
+            *flag.borrow_mut() = true;
+        });
+        // guess the encoding; UTF-16 files (and files with a byte order mark) are sniffed once
+        // decoded, while the delimiters and quotes of the others are ASCII anyway
+        self.infer_encoding(&mut reader)?;
+        let encoding = self.encoding.unwrap_or(Encoding::Utf8);
+        if matches!(encoding, Encoding::Utf16Le | Encoding::Utf16Be) || self.bom.is_some() {
+            self.sniff_decoded(&mut Decoder::new(&mut reader, encoding))?;
+        } else {
+            self.sniff_decoded(&mut reader)?;
+        }
+        self.is_utf8 =
+            Some(IS_UTF8.with(|flag| *flag.borrow()) && self.encoding == Some(Encoding::Utf8));
+        // the bytes as written are ASCII (so not those of a UTF-16 file, or a byte order mark)
+        self.is_ascii = IS_ASCII.with(|flag| *flag.borrow())
+            && self.encoding == Some(Encoding::Utf8)
+            && self.bom.is_none();
+        // locate the invalid UTF-8 (the sequences of UTF-16 files are decoded instead)
+        self.invalid_utf8 = None;
+        if self.is_utf8 == Some(false) && !matches!(encoding, Encoding::Utf16Le | Encoding::Utf16Be)
+        {
+            reader.seek(SeekFrom::Start(0))?;
+            let (sample, len) = read_sample(
+                &mut BufReader::new(&mut reader),
+                self.get_type_sample_size(),
+            )?;
+            let invalid_utf8 = find_invalid_utf8(&sample[..len]);
+            if self.get_utf8_handling() == Utf8Handling::Strict {
+                return Err(SnifferError::InvalidUtf8(invalid_utf8));
+            }
+            self.invalid_utf8 = Some(invalid_utf8);
+        }
+        let mut sample_iter = take_sample_from_start(&mut reader, self.get_type_sample_size())?
+            .split_on(self.get_record_end());
+        for line in sample_iter.by_ref() {
+            line?;
+        }
+        self.sampled_bytes = sample_iter.sampled_len();
+
+        // as this point of the process, we should have all these filled in.
+        // assert!(
+        //     self.delimiter.is_some()
+        //         && self.num_preamble_rows.is_some()
+        //         && self.quote.is_some()
+        //         && self.flexible.is_some()
+        //         && self.is_utf8.is_some()
+        //         && self.delimiter_freq.is_some()
+        //         && self.has_header_row.is_some()
+        //         && self.avg_record_len.is_some()
+        //         && self.delimiter_freq.is_some()
+        // );
+        if !(self.delimiter.is_some()
+            && self.single_column.is_some()
+            && self.num_preamble_rows.is_some()
+            && self.num_comment_preamble_rows.is_some()
+            && self.quote.is_some()
+            && self.quote_style.is_some()
+            && self.escape.is_some()
+            && self.double_quote.is_some()
+            && self.comment.is_some()
+            && self.terminator.is_some()
+            && self.trailing_delimiter.is_some()
+            && self.embedded_escapes.is_some()
+            && self.trim.is_some()
+            && self.blank_lines.is_some()
+            && self.num_blank_lines.is_some()
+            && self.flexible.is_some()
+            && self.is_utf8.is_some()
+            && self.delimiter_freq.is_some()
+            && self.has_header_row.is_some()
+            && self.avg_record_len.is_some()
+            && self.decimal_separator.is_some()
+            && self.num_footer_rows.is_some()
+            && self.has_multiline_fields.is_some()
+            && self.delimiter_freq.is_some())
+        {
+            return Err(SnifferError::SniffingFailed(format!(
+                "Failed to infer all metadata: {self:?}"
+            )));
+        }
+        // safety: we just checked that all these are Some, so it's safe to unwrap
+        Ok(Metadata {
+            encoding: self.encoding.unwrap_or(Encoding::Utf8),
+            encoding_candidates: self.encoding_candidates.clone(),
+            bom: self.bom,
+            invalid_utf8: self.invalid_utf8.clone(),
+            zip_entry: None,
+            is_ascii: self.is_ascii,
+            sampled_bytes: self.sampled_bytes,
+            dialect: Dialect {
+                delimiter: self.delimiter.unwrap(),
+                multibyte_delimiter: self.multibyte_delimiter.clone(),
+                single_column: self.single_column.unwrap(),
+                header: Header {
+                    num_preamble_rows: self.num_comment_preamble_rows.unwrap()
+                        + self.num_preamble_rows.unwrap(),
+                    num_comment_preamble_rows: self.num_comment_preamble_rows.unwrap(),
+                    has_header_row: self.has_header_row.unwrap(),
+                },
+                quote: self.quote.clone().unwrap(),
+                quote_style: self.quote_style.unwrap(),
+                escape: self.escape.clone().unwrap(),
+                double_quote: self.double_quote.unwrap(),
+                comment: self.comment.clone().unwrap(),
+                terminator: self.terminator.unwrap(),
+                trailing_delimiter: self.trailing_delimiter.unwrap(),
+                embedded_escapes: self.embedded_escapes.unwrap(),
+                trim: self.trim.unwrap(),
+                blank_lines: self.blank_lines.unwrap(),
+                flexible: self.flexible.unwrap(),
+                is_utf8: self.is_utf8.unwrap(),
+            },
+            avg_record_len: self.avg_record_len.unwrap(),
+            num_fields: self.delimiter_freq.unwrap() + 1
+                - usize::from(self.drop_trailing_field && self.trailing_delimiter.unwrap()),
+            fields: self.fields.clone(),
+            fields_raw: self.fields_raw.clone(),
+            types: self.types.clone(),
+            date_formats: self.date_formats.clone(),
+            timezones: self.timezones.clone(),
+            date_orders: self.date_orders.clone(),
+            nullable: self
+                .null_tokens
+                .iter()
+                .map(|tokens| !tokens.is_empty())
+                .collect(),
+            null_tokens: self.null_tokens.clone(),
+            integer_widths: self.integer_widths.clone(),
+            radixes: self.radixes.clone(),
+            value_ranges: self.value_ranges.clone(),
+            number_formats: self.number_formats.clone(),
+            looks_like_code: self.looks_like_code.clone(),
+            categories: self.categories.clone(),
+            constant_values: self.constant_values.clone(),
+            phone_numbers: self.phone_numbers.clone(),
+            has_non_finite: self.has_non_finite.clone(),
+            coordinates: self.coordinates.clone(),
+            type_confidences: self.type_confidences.clone(),
+            type_fallbacks: self.type_fallbacks.clone(),
+            type_unions: self.type_unions.clone(),
+            column_quoting: self.column_quoting.clone(),
+            empty_values: self.empty_values.clone(),
+            decimal_separator: self.decimal_separator.unwrap(),
+            num_blank_lines: self.num_blank_lines.unwrap(),
+            num_footer_rows: self.num_footer_rows.unwrap(),
+            has_multiline_fields: self.has_multiline_fields.unwrap(),
+            preview: self.preview.clone(),
+            confidence: self.confidence,
+        })
+    }
+
+    /// Sniff the file located at the provided path, and return a
+    /// [`SniffResult`](metadata/enum.SniffResult.html) describing whether it's a delimited file
+    /// (along with its metadata) or a fixed-width file (along with its column boundaries).
+    ///
+    /// Fails on file opening or readering errors, or on an error examining the file.
+    pub fn sniff_layout_path<P: AsRef<Path>>(&mut self, path: P) -> Result<SniffResult> {
+        let file = File::open(path)?;
+        self.sniff_layout_reader(&file)
+    }
+    /// Sniff the file provided by the reader, and return a
+    /// [`SniffResult`](metadata/enum.SniffResult.html) describing whether it's a delimited file
+    /// (along with its metadata) or a fixed-width file (along with its column boundaries).
+    ///
+    /// A file is only considered fixed-width if no plausible delimiter is found (i.e. it would
+    /// otherwise be sniffed as a single-column file), and its sampled lines share columns of blank
+    /// space.
+    ///
+    /// Fails on file opening or readering errors, or on an error examining the file.
+    pub fn sniff_layout_reader<R: Read + Seek>(&mut self, mut reader: R) -> Result<SniffResult> {
+        match self.sniff_reader(&mut reader) {
+            Ok(metadata) if !metadata.dialect.single_column => Ok(SniffResult::Delimited(metadata)),
+            result => match self.infer_fixed_width(&mut reader)? {
+                Some(column_starts) => Ok(SniffResult::FixedWidth(column_starts)),
+                None => result.map(SniffResult::Delimited),
+            },
+        }
+    }
+
+    // Infers the column boundaries of a fixed-width file. Columns are separated by runs of byte
+    // positions that are blank (a space, or past the end of the line) in every sampled line; a
+    // column starts wherever such a run ends. Returns `None` if fewer than two columns are found.
+    fn infer_fixed_width<R: Read + Seek>(&self, reader: &mut R) -> Result<Option<Vec<usize>>> {
+        let lines = self
+            .take_sample(reader)?
+            .filter(|line| !matches!(line, Ok(line) if line.trim().is_empty()))
+            .collect::<Result<Vec<String>>>()?;
+        if lines.len() < 2 {
+            return Ok(None);
+        }
+        let max_len = lines.iter().map(String::len).max().unwrap_or(0);
+        let is_blank = (0..max_len)
+            .map(|i| {
+                lines
+                    .iter()
+                    .all(|line| line.as_bytes().get(i).map_or(true, |&chr| chr == b' '))
+            })
+            .collect::<Vec<bool>>();
+        let mut column_starts = vec![0];
+        for i in 1..max_len {
+            if is_blank[i - 1] && !is_blank[i] {
+                column_starts.push(i);
+            }
+        }
+        Ok((column_starts.len() > 1).then_some(column_starts))
+    }
+
+    // Infers the quotes, escape character, delimiter (if not already known), preamble and types.
+    fn infer_dialect_types<R: Read + Seek>(&mut self, reader: &mut R) -> Result<()> {
+        // guess footer rows, so they can be excluded from the remaining passes
+        self.infer_footer(reader)?;
+        // quote characters whose quoted text is ignored when counting delimiters (even if the file
+        // doesn't turn out to be consistently quoted), unless the file is known to be unquoted
+        let masked_quotes = match self.quote {
+            Some(Quote::Some(chr)) => vec![chr],
+            Some(Quote::None) => vec![],
+            None => self.get_quote_candidates().to_vec(),
+        };
+        // guess quotes & delim
+        self.infer_quotes_delim(reader)?;
+        // guess escape character (only meaningful for quoted files)
+        self.infer_escape(reader)?;
+
+        // if we have a delimiter, we just need to search for num_preamble_rows and check for
+        // flexible. Otherwise, we need to guess a delimiter as well.
+        if self.delimiter.is_some() {
+            self.infer_preamble_known_delim(reader, &masked_quotes)?;
+        } else {
+            self.infer_delim_preamble(reader, &masked_quotes)?;
+        }
+
+        // guess whitespace trimming and decimal separator, which are needed to infer numeric types
+        self.infer_trim(reader)?;
+        self.infer_decimal_separator(reader)?;
+        self.infer_types(reader)?;
+        if self.clean_field_names {
+            self.fields = self
+                .fields
+                .iter()
+                .map(|field| clean_field_name(field))
+                .collect();
+        }
+        self.null_tokens.resize(self.types.len(), vec![]);
+        // guess the formats of date columns and the widths of integer columns (needs to know
+        // whether there's a header row to skip)
+        self.infer_column_details(reader)?;
+        // guess blank line policy (needs to know the number of fields)
+        self.infer_blank_lines(reader)?;
+        self.infer_embedded_escapes(reader)?;
+        // guess quote style (needs to know whether there's a header row to skip)
+        self.infer_quote_style(reader)?;
+        self.infer_empty_values(reader)?;
+        if self.get_strictness() == Strictness::Strict {
+            self.check_consistency(reader)?;
+        }
+        self.take_preview(reader)?;
+        Ok(())
+    }
+
+    // Takes the first records of the data for a preview, as many as asked for. The preview stops at
+    // a record the csv reader fails on (e.g. a record of the wrong length).
+    fn take_preview<R: Read + Seek>(&mut self, reader: &mut R) -> Result<()> {
+        self.preview.clear();
+        if self.preview_records == 0 {
+            return Ok(());
+        }
+        let encoding = self.encoding.unwrap_or(Encoding::Utf8);
+        let drop_trailing_field = self.drop_trailing_field && self.trailing_delimiter == Some(true);
+        let mut csv_reader = self.create_csv_reader(reader)?;
+        for record in csv_reader.byte_records().take(self.preview_records) {
+            let Ok(record) = record else {
+                break;
+            };
+            let mut fields = record
+                .iter()
+                .map(|field| decode_text(field, encoding))
+                .collect::<Vec<_>>();
+            if drop_trailing_field {
+                fields.pop();
+            }
+            self.preview.push(fields);
+        }
+        Ok(())
+    }
+
+    // Infers the details of each column which depend on its sampled values as a whole, rather than
+    // on each value (as types do).
+    fn infer_column_details<R: Read + Seek>(&mut self, reader: &mut R) -> Result<()> {
+        let options = self.get_column_options();
+        let stats = self.sample_columns(reader, &options)?;
+        self.infer_custom_types(&stats, &options.detectors);
+        self.infer_currencies(&stats);
+        self.infer_semantic_types(&stats);
+        self.infer_clock_durations(&stats);
+        self.infer_binary(&stats);
+        self.infer_codes(&stats);
+        self.infer_date_formats(&stats);
+        self.infer_number_formats(&stats);
+        self.infer_non_finite(&stats);
+        self.infer_radixes(&stats);
+        self.infer_integer_widths(&stats);
+        self.infer_coordinates(&stats);
+        self.infer_phone_numbers(&stats);
+        self.infer_type_fallbacks(&stats);
+        self.infer_type_unions(&stats);
+        self.infer_value_ranges(&stats);
+        self.infer_categories(&stats);
+        self.infer_constant_values(&stats);
+        Ok(())
+    }
+
+    fn get_column_options(&self) -> ColumnOptions {
+        let mut detectors = self.type_detectors.clone();
+        // stable, so that ties are broken by the order of registration
+        detectors.sort_by_key(|detector| std::cmp::Reverse(detector.priority()));
+        ColumnOptions {
+            type_options: self.get_type_options(),
+            detectors,
+            prefixed_integers: self.prefixed_integers,
+            category_threshold: self
+                .category_threshold
+                .unwrap_or(DEFAULT_CATEGORY_THRESHOLD),
+        }
+    }
+
+    // Summarizes the (non-missing) values of each column in the sample.
+    fn sample_columns<R: Read + Seek>(
+        &self,
+        reader: &mut R,
+        options: &ColumnOptions,
+    ) -> Result<Vec<ColumnStats>> {
+        let mut stats = self
+            .types
+            .iter()
+            .map(|&ty| ColumnStats::new(ty, options))
+            .collect::<Vec<_>>();
+        if self.types.is_empty() {
+            return Ok(stats);
+        }
+        let (mut n_records, mut n_bytes) = (0, 0);
+        let sample_size = self.get_type_sample_size();
+        let type_options = &options.type_options;
+        let mut csv_reader = self.create_csv_reader(reader)?;
+        let mut records_iter = csv_reader.byte_records();
+        // the records sampled past the sample (if any), once it's read
+        let mut random_records: Option<std::vec::IntoIter<ByteRecord>> = None;
+        loop {
+            let record = match random_records {
+                None => match records_iter.next() {
+                    Some(record) => record?,
+                    None => break,
+                },
+                Some(ref mut records) => match records.next() {
+                    Some(record) => record,
+                    None => break,
+                },
+            };
+            let record = decode_record(record, self.get_utf8_handling());
+            for (column, field) in stats.iter_mut().zip(&record) {
+                if !type_options.is_null(field) {
+                    column.add(field, options);
+                }
+            }
+            n_records += 1;
+            n_bytes += record.as_slice().len();
+            // stop (or sample the rest of the file) if we pass sample size limits
+            let is_done = match sample_size {
+                SampleSize::Records(recs) => n_records > recs,
+                SampleSize::Bytes(bytes)
+                | SampleSize::Adaptive {
+                    max_bytes: bytes, ..
+                } => n_bytes > bytes,
+                SampleSize::Duration(_) => n_bytes > MAX_TIMED_SAMPLE_BYTES,
+                SampleSize::All | SampleSize::Percent(_) => false,
+            };
+            if is_done && random_records.is_none() {
+                if self.random_samples == 0 && !self.middle_and_tail {
+                    break;
+                }
+                let sample_len = records_iter.reader().position().byte();
+                let reader = records_iter.reader_mut().get_mut().get_mut();
+                let field_count = self.delimiter_freq.map_or(0, |freq| freq + 1);
+                random_records = Some(
+                    self.sample_later_records(reader, sample_len, field_count)?
+                        .into_iter(),
+                );
+            }
+        }
+        Ok(stats)
+    }
+
+    // Infers the format of each date, datetime and time column, its day/month order, and whether
+    // datetime columns are timezone-aware.
+    fn infer_date_formats(&mut self, stats: &[ColumnStats]) {
+        self.date_formats = vec![None; self.types.len()];
+        self.timezones = vec![None; self.types.len()];
+        self.date_orders = vec![None; self.types.len()];
+        for (i, stats) in stats.iter().enumerate() {
+            if !matches!(self.types[i], Type::Date | Type::DateTime | Type::Time) {
+                continue;
+            }
+            let Some((format, order)) = stats
+                .date_formats
+                .as_ref()
+                .and_then(DateFormatCandidates::format)
+            else {
+                continue;
+            };
+            self.date_orders[i] = order.map(|(order, from_preference)| DateOrder {
+                order,
+                from_preference,
+            });
+            if self.types[i] == Type::DateTime {
+                self.timezones[i] = Some(if is_timezone_aware(&format) {
+                    Timezone::Aware
+                } else {
+                    Timezone::Naive
+                });
+            }
+            self.date_formats[i] = Some(format);
+        }
+    }
+
+    // Infers columns whose values all match a custom type detector as that type, trying the
+    // detectors with the highest priority first.
+    fn infer_custom_types(&mut self, stats: &[ColumnStats], detectors: &[Arc<dyn TypeDetector>]) {
+        for (i, stats) in stats.iter().enumerate() {
+            if stats.n_values == 0 {
+                continue;
+            }
+            if let Some((_, detector)) = stats
+                .custom
+                .iter()
+                .zip(detectors)
+                .find(|&(&matches, _)| matches)
+            {
+                self.types[i] = Type::Custom(detector.name());
+            }
+        }
+    }
+
+    // Infers text columns whose values are all currency amounts with the same symbol (and which
+    // write negative amounts the same way) as currency columns.
+    fn infer_currencies(&mut self, stats: &[ColumnStats]) {
+        for (i, stats) in stats.iter().enumerate() {
+            if self.types[i] != Type::Text || stats.n_values == 0 {
+                continue;
+            }
+            if let Some(CurrencyStats {
+                symbol: Some(symbol),
+                negative,
+                agree: true,
+            }) = stats.currency
+            {
+                self.types[i] = Type::Currency { symbol, negative };
+            }
+        }
+    }
+
+    // Infers text columns whose values all have the same recognizable structure (e.g. percentages
+    // or UUIDs) as being of the corresponding type.
+    fn infer_semantic_types(&mut self, stats: &[ColumnStats]) {
+        for (i, stats) in stats.iter().enumerate() {
+            if self.types[i] != Type::Text || stats.n_values == 0 {
+                continue;
+            }
+            if let Some((_, &(_, ty))) = stats
+                .semantic
+                .iter()
+                .zip(&SEMANTIC_TYPES)
+                .find(|&(&matches, _)| matches)
+            {
+                self.types[i] = ty;
+            }
+        }
+    }
+
+    // Infers columns of clock-like values (e.g. `01:23:45`) as durations rather than times of day if
+    // some of them are over 24 hours, or if the column is named like a duration.
+    fn infer_clock_durations(&mut self, stats: &[ColumnStats]) {
+        for (i, stats) in stats.iter().enumerate() {
+            if !matches!(self.types[i], Type::Time | Type::Text) || stats.n_values == 0 {
+                continue;
+            }
+            let Some(max_hours) = stats.clock_hours else {
+                continue;
+            };
+            let name = self.fields.get(i).map(|field| field.to_lowercase());
+            let is_named_duration = name.is_some_and(|name| {
+                DURATION_FIELD_NAMES
+                    .iter()
+                    .any(|duration_name| name.contains(duration_name))
+            });
+            if is_named_duration || max_hours >= 24 {
+                self.types[i] = Type::Duration;
+            }
+        }
+    }
+
+    // Infers text columns of long, base64-encoded strings which look random as binary data.
+    fn infer_binary(&mut self, stats: &[ColumnStats]) {
+        for (i, stats) in stats.iter().enumerate() {
+            if self.types[i] == Type::Text
+                && stats.n_values > 0
+                && stats
+                    .base64_counts
+                    .as_deref()
+                    .is_some_and(has_base64_entropy)
+            {
+                self.types[i] = Type::Binary;
+            }
+        }
+    }
+
+    // Infers unsigned columns of zero-padded values (e.g. ZIP codes like `00420`) as codes, which
+    // are typed as text so that the leading zeros aren't lost: either every value is zero-padded,
+    // or some are and all of them are as wide. A single zero-padded number doesn't make a code.
+    fn infer_codes(&mut self, stats: &[ColumnStats]) {
+        self.looks_like_code = vec![false; self.types.len()];
+        for (i, stats) in stats.iter().enumerate() {
+            let is_code = self.types[i] == Type::Unsigned
+                && stats
+                    .code
+                    .as_ref()
+                    .is_some_and(|code| code.any_padded && (code.all_padded || code.same_len));
+            if is_code {
+                self.types[i] = Type::Text;
+                self.looks_like_code[i] = true;
+            }
+        }
+    }
+
+    // Infers the decimal and thousands separators of each numeric column. Numeric columns whose
+    // values are grouped with different thousands separators are inferred as text instead.
+    fn infer_number_formats(&mut self, stats: &[ColumnStats]) {
+        let decimal_separator = self.decimal_separator.unwrap_or(b'.');
+        self.number_formats = vec![None; self.types.len()];
+        for (i, stats) in stats.iter().enumerate() {
+            if !matches!(self.types[i], Type::Unsigned | Type::Signed | Type::Float) {
+                continue;
+            }
+            let separators = stats.thousands_separators.as_deref().unwrap_or_default();
+            if separators.len() > 1 {
+                self.types[i] = Type::Text;
+                continue;
+            }
+            self.number_formats[i] = Some(NumberFormat {
+                decimal_separator,
+                thousands_separator: separators.first().copied(),
+            });
+        }
+    }
+
+    // Infers the text columns of hexadecimal or octal values (all with the same radix) as integers,
+    // if enabled.
+    fn infer_radixes(&mut self, stats: &[ColumnStats]) {
+        self.radixes = vec![None; self.types.len()];
+        if !self.prefixed_integers {
+            return;
+        }
+        for (i, stats) in stats.iter().enumerate() {
+            if self.types[i] != Type::Text || stats.n_values == 0 {
+                continue;
+            }
+            let Some(RadixStats {
+                radix: Some(radix),
+                same_radix: true,
+                min,
+                max,
+                ..
+            }) = stats.radix
+            else {
+                continue;
+            };
+            self.types[i] = if min >= 0 && max <= u64::MAX.into() {
+                Type::Unsigned
+            } else if min >= i64::MIN.into() && max <= i64::MAX.into() {
+                Type::Signed
+            } else {
+                continue;
+            };
+            self.radixes[i] = Some(if radix == 16 {
+                Radix::Hexadecimal
+            } else {
+                Radix::Octal
+            });
+        }
+    }
+
+    // Infers the narrowest integer type holding every value of each integer column. Text columns
+    // of integers too large for 64 bits are reported as big integers.
+    fn infer_integer_widths(&mut self, stats: &[ColumnStats]) {
+        self.integer_widths = stats
+            .iter()
+            .zip(&self.types)
+            .zip(&self.looks_like_code)
+            .zip(&self.radixes)
+            .map(|(((stats, ty), &is_code), radix)| match ty {
+                Type::Unsigned | Type::Signed => {
+                    let (min, max) = match radix {
+                        Some(_) => stats
+                            .radix
+                            .as_ref()
+                            .map_or((0, 0), |radix| (radix.min, radix.max)),
+                        None => stats.integer_range.unwrap_or_default(),
+                    };
+                    Some(if min >= i32::MIN.into() && max <= i32::MAX.into() {
+                        IntegerWidth::I32
+                    } else if max <= i64::MAX.into() {
+                        IntegerWidth::I64
+                    } else {
+                        IntegerWidth::U64
+                    })
+                }
+                Type::Text if !is_code && stats.n_values > 0 && stats.all_integer_literals => {
+                    Some(IntegerWidth::Big)
+                }
+                _ => None,
+            })
+            .collect();
+    }
+
+    // Infers the numeric columns holding latitudes and longitudes: the ones named after their
+    // coordinate and in its range, or else pairs of adjacent (latitude, longitude) float columns.
+    fn infer_coordinates(&mut self, stats: &[ColumnStats]) {
+        let ranges = stats
+            .iter()
+            .zip(&self.types)
+            .map(|(stats, ty)| {
+                if !matches!(ty, Type::Unsigned | Type::Signed | Type::Float) || stats.n_values == 0
+                {
+                    return None;
+                }
+                stats.decimal_range
+            })
+            .collect::<Vec<_>>();
+        let in_range = |i: usize, limit: f64| {
+            ranges[i].is_some_and(|(min, max)| min >= -limit && max <= limit)
+        };
+        self.coordinates = vec![None; self.types.len()];
+        for (i, field) in self.fields.iter().enumerate().take(ranges.len()) {
+            let name = field.trim().to_lowercase();
+            if (name == "lat" || name.ends_with("_lat") || name.ends_with("latitude"))
+                && in_range(i, 90.0)
+            {
+                self.coordinates[i] = Some(Coordinate::Latitude);
+            } else if (["lon", "lng", "long"].contains(&name.as_str())
+                || ["_lon", "_lng", "_long", "longitude"]
+                    .iter()
+                    .any(|suffix| name.ends_with(suffix)))
+                && in_range(i, 180.0)
+            {
+                self.coordinates[i] = Some(Coordinate::Longitude);
+            }
+        }
+        if self.coordinates.iter().any(Option::is_some) {
+            return;
+        }
+        let mut i = 0;
+        while i + 1 < ranges.len() {
+            if self.types[i] == Type::Float
+                && self.types[i + 1] == Type::Float
+                && in_range(i, 90.0)
+                && in_range(i + 1, 180.0)
+            {
+                self.coordinates[i] = Some(Coordinate::Latitude);
+                self.coordinates[i + 1] = Some(Coordinate::Longitude);
+                i += 1;
+            }
+            i += 1;
+        }
+    }
+
+    // Infers the columns holding phone numbers (if the `phone` feature is enabled). Those in E.164
+    // format (e.g. `+14155552671`) are also inferred as integers.
+    #[cfg_attr(not(feature = "phone"), allow(unused_variables))]
+    fn infer_phone_numbers(&mut self, stats: &[ColumnStats]) {
+        self.phone_numbers = vec![false; self.types.len()];
+        #[cfg(feature = "phone")]
+        for (i, stats) in stats.iter().enumerate() {
+            self.phone_numbers[i] =
+                matches!(self.types[i], Type::Text | Type::Unsigned | Type::Signed)
+                    && stats.n_values > 0
+                    && stats.all_phone_numbers;
+        }
+    }
+
+    // Infers the fallback chain of each column, and how many of its values fit its type. Types
+    // besides the general ones (e.g. currencies) are only inferred if all of the values fit.
+    fn infer_type_fallbacks(&mut self, stats: &[ColumnStats]) {
+        self.type_fallbacks = stats
+            .iter()
+            .map(|stats| {
+                stats
+                    .type_tally
+                    .fits()
+                    .into_iter()
+                    .map(|(ty, confidence)| TypeFit { ty, confidence })
+                    .collect()
+            })
+            .collect();
+        self.type_confidences = self
+            .types
+            .iter()
+            .zip(&self.type_fallbacks)
+            .map(|(ty, fallbacks)| {
+                fallbacks
+                    .iter()
+                    .find(|fit| fit.ty == *ty)
+                    .map_or(1.0, |fit| fit.confidence)
+            })
+            .collect();
+    }
+
+    // Infers the types mixed in each column (if enabled).
+    fn infer_type_unions(&mut self, stats: &[ColumnStats]) {
+        self.type_unions = stats
+            .iter()
+            .map(|stats| {
+                if !self.type_unions_enabled {
+                    return None;
+                }
+                let union = stats.type_tally.union();
+                (union.len() > 1).then(|| {
+                    union
+                        .into_iter()
+                        .map(|(ty, confidence)| TypeFit { ty, confidence })
+                        .collect()
+                })
+            })
+            .collect();
+    }
+
+    // Infers the smallest and largest values of each numeric, date or text column.
+    fn infer_value_ranges(&mut self, stats: &[ColumnStats]) {
+        self.value_ranges = stats
+            .iter()
+            .enumerate()
+            .map(|(i, stats)| match self.types[i] {
+                Type::Unsigned | Type::Signed => match self.radixes[i] {
+                    Some(_) => stats.radix.as_ref()?.bounds.range(),
+                    None => stats.integer_bounds.as_ref()?.range(),
+                },
+                Type::Float => stats.float_bounds.as_ref()?.range(),
+                Type::Date | Type::DateTime | Type::Time => {
+                    self.date_formats[i].as_ref()?;
+                    stats.date_formats.as_ref()?.range()
+                }
+                Type::Text => stats.text_bounds.as_ref()?.range(),
+                _ => None,
+            })
+            .collect();
+    }
+
+    // Infers the categories of columns with few distinct values, in the order they first occur.
+    // Columns without repeated values aren't categorical, however few values they have.
+    fn infer_categories(&mut self, stats: &[ColumnStats]) {
+        self.categories = stats
+            .iter()
+            .map(|stats| {
+                let categories = stats.categories.as_ref()?;
+                (categories.len() < stats.n_values).then(|| categories.clone())
+            })
+            .collect();
+    }
+
+    // Infers which floating-point columns have non-finite values.
+    fn infer_non_finite(&mut self, stats: &[ColumnStats]) {
+        self.has_non_finite = self
+            .types
+            .iter()
+            .zip(stats)
+            .map(|(ty, stats)| *ty == Type::Float && stats.has_non_finite)
+            .collect();
+    }
+
+    // Infers the value of each column whose (non-missing) values are all the same.
+    fn infer_constant_values(&mut self, stats: &[ColumnStats]) {
+        self.constant_values = stats
+            .iter()
+            .map(|stats| stats.constant.clone().filter(|_| stats.is_constant))
+            .collect();
+    }
+
+    // Fails if the sniffed dialect is contradicted by the sample: if a quoted field is never
+    // closed, or if the delimiter isn't clearly better than the runner-up (or no delimiter is,
+    // for single-column files).
+    fn check_consistency<R: Read + Seek>(&mut self, reader: &mut R) -> Result<()> {
+        if let Some(Quote::Some(quote_chr)) = self.quote {
+            let escape: Option<u8> = self.escape.clone().and_then(Into::into);
+            // line on which the currently open quoted field started, if any
+            let mut open_since = None;
+            for (i, line) in self.take_sample(reader)?.enumerate() {
+                let line = line?;
+                let mut chars = line.bytes();
+                while let Some(chr) = chars.next() {
+                    if Some(chr) == escape && open_since.is_some() {
+                        chars.next();
+                    } else if chr == quote_chr {
+                        open_since = match open_since {
+                            Some(_) => None,
+                            None => Some(i),
+                        };
+                    }
+                }
+            }
+            if let Some(line) = open_since {
+                return Err(SnifferError::SniffingFailed(format!(
+                    "unbalanced quotes: the sample ends within a quoted field (opened on line {})",
+                    line + 1
+                )));
+            }
+        }
+        if self.confidence.delimiter < STRICT_MIN_DELIMITER_CONFIDENCE {
+            let describe = |delim: u8| match delim {
+                b' ' => "space".to_string(),
+                delim => format!("'{}'", delim.escape_ascii()),
+            };
+            // the runner-up among the candidates which weren't ruled out by other heuristics
+            let runner_up = self.candidates.iter().find(|candidate| {
+                matches!(
+                    candidate.rejection,
+                    Some(Rejection::Outscored | Rejection::Unsteady)
+                ) && (self.single_column == Some(true)
+                    || Some(candidate.delimiter) != self.delimiter)
+            });
+            let chosen = if self.single_column == Some(true) {
+                "a single column".to_string()
+            } else {
+                // safety: the delimiter is always known after inferring it
+                describe(self.delimiter.unwrap())
+            };
+            return Err(SnifferError::SniffingFailed(match runner_up {
+                Some(runner_up) => format!(
+                    "contradictory delimiters: {chosen} (confidence {:.2}) is not clearly better \
+                     than {}",
+                    self.confidence.delimiter,
+                    describe(runner_up.delimiter)
+                ),
+                None => format!(
+                    "inconsistent delimiter: {chosen} has a confidence of {:.2}",
+                    self.confidence.delimiter
+                ),
+            }));
+        }
+        Ok(())
+    }
+
+    // Infers the line terminator by counting the terminators in the raw sample bytes (the sample
+    // iterator strips terminators, so it can't be used here). Files using record separators more
+    // often than line breaks are assumed to be RS-terminated, and files without any terminators
+    // are assumed to use LF.
+    fn infer_terminator<R: Read + Seek>(&mut self, reader: &mut R) -> Result<()> {
+        if self.terminator.is_some() {
+            // nothing left to infer!
+            return Ok(());
+        }
+        reader.seek(SeekFrom::Start(0))?;
+        let sample_size = self.get_sample_size();
+        let mut buf_reader = BufReader::new(reader);
+        let (mut n_crlf, mut n_lf, mut n_cr, mut n_rs) = (0, 0, 0, 0);
+        let mut n_bytes = 0;
+        let mut prev_cr = false;
+        loop {
+            let buf = buf_reader.fill_buf()?;
+            if buf.is_empty() {
+                break;
+            }
+            for &byte in buf {
+                match byte {
+                    b'\n' if prev_cr => n_crlf += 1,
+                    b'\n' => n_lf += 1,
+                    _ if prev_cr => n_cr += 1,
+                    _ => {}
+                }
+                if byte == RECORD_SEPARATOR {
+                    n_rs += 1;
+                }
+                prev_cr = byte == b'\r';
+            }
+            let n_read = buf.len();
+            buf_reader.consume(n_read);
+            n_bytes += n_read;
+            let is_done = match sample_size {
+                SampleSize::Records(max_records) => n_crlf + n_lf + n_cr + n_rs > max_records,
+                SampleSize::Bytes(max_bytes) | SampleSize::Adaptive { max_bytes, .. } => {
+                    n_bytes > max_bytes
+                }
+                SampleSize::Duration(_) => n_bytes > MAX_TIMED_SAMPLE_BYTES,
+                SampleSize::All | SampleSize::Percent(_) => false,
+            };
+            if is_done {
+                break;
+            }
+        }
+        if prev_cr {
+            n_cr += 1;
+        }
+        if n_rs > n_crlf + n_lf + n_cr {
+            self.terminator = Some(Terminator::RS);
+            return Ok(());
+        }
+        self.terminator = Some(match (n_crlf > 0, n_lf > 0, n_cr > 0) {
+            (true, false, false) => Terminator::CRLF,
+            (false, false, true) => Terminator::CR,
+            (false, _, false) => Terminator::LF,
+            _ => Terminator::Mixed,
+        });
+        Ok(())
+    }
+
+    // Counts the lines at the start of the file with a comment prefix (# or //). These are treated
+    // as preamble rows even if they contain the delimiter, while comments further down the file
+    // still determine the comment character.
+    fn infer_comment_preamble<R: Read + Seek>(&mut self, reader: &mut R) -> Result<()> {
+        if self.num_comment_preamble_rows.is_some() {
+            // nothing left to infer!
+            return Ok(());
+        }
+        reader.seek(SeekFrom::Start(0))?;
+        self.num_comment_preamble_rows = Some(comment_preamble_count(
+            &mut *reader,
+            &COMMENT_PREAMBLE_PREFIXES,
+            self.get_record_end(),
+        )?);
+        Ok(())
+    }
+
+    // Infers the comment character from lines starting with one of the common comment prefixes
+    // (#, // or ;). A prefix is only considered a comment marker if it starts some, but not most,
+    // of the sampled lines. A semicolon is further excluded if it appears in any other line, since
+    // it's then more likely to be the delimiter. The comment character of `//` is `/`, which
+    // comments out lines starting with a single `/` as well (e.g. paths), so `//` is excluded if
+    // any other line starts with `/`.
+    fn infer_comment<R: Read + Seek>(&mut self, reader: &mut R) -> Result<()> {
+        if self.comment.is_some() {
+            // nothing left to infer!
+            return Ok(());
+        }
+        let sample_iter = self.take_sample(reader)?;
+        let mut n_lines = 0;
+        let mut prefix_counts = [0; COMMENT_PREFIXES.len()];
+        let mut semicolon_elsewhere = self.delimiter == Some(b';');
+        let mut slash_elsewhere = false;
+        for line in sample_iter {
+            let line = line?;
+            n_lines += 1;
+            slash_elsewhere |= line.starts_with('/') && !line.starts_with("//");
+            match COMMENT_PREFIXES
+                .iter()
+                .position(|prefix| line.starts_with(prefix))
+            {
+                Some(idx) => prefix_counts[idx] += 1,
+                None => semicolon_elsewhere |= line.contains(';'),
+            }
+        }
+        let best_prefix = prefix_counts
+            .iter()
+            .enumerate()
+            .filter(|&(idx, &cnt)| {
+                cnt > 0
+                    && cnt * 2 < n_lines
+                    && !(COMMENT_PREFIXES[idx] == ";" && semicolon_elsewhere)
+                    && !(COMMENT_PREFIXES[idx] == "//" && slash_elsewhere)
+            })
+            // on ties, prefer the prefix listed first
+            .max_by(|(idx_a, cnt_a), (idx_b, cnt_b)| cnt_a.cmp(cnt_b).then(idx_b.cmp(idx_a)));
+        self.comment = Some(match best_prefix {
+            Some((idx, _)) => Comment::Enabled(COMMENT_PREFIXES[idx].as_bytes()[0]),
+            None => Comment::Disabled,
+        });
+        Ok(())
+    }
+
+    // Infers a multi-byte delimiter. A candidate is chosen if it occurs in the sample and its bytes
+    // never occur outside of it (otherwise one of its bytes is more likely the actual delimiter).
+    // Only runs if the delimiter isn't already known.
+    fn infer_multibyte_delim<R: Read + Seek>(&mut self, reader: &mut R) -> Result<()> {
+        if self.delimiter.is_some() || self.delimiter_candidates.is_some() {
+            // nothing left to infer!
+            return Ok(());
+        }
+        let lines = self.take_sample(reader)?.collect::<Result<Vec<String>>>()?;
+        for candidate in MULTIBYTE_DELIMITERS {
+            let delim = candidate.as_bytes();
+            let mut n_occurrences = 0;
+            let only_in_delim = lines.iter().all(|line| {
+                let line = line.as_bytes();
+                let n_delims = memchr::memmem::find_iter(line, delim).count();
+                n_occurrences += n_delims;
+                delim.iter().all(|&chr| {
+                    bytecount::count(line, chr) == n_delims * bytecount::count(delim, chr)
+                })
+            });
+            if n_occurrences > 0 && only_in_delim {
+                self.multibyte_delimiter = Some(delim.to_vec());
+                self.delimiter = Some(MULTIBYTE_REPLACEMENT);
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    // Infers quotes and delimiter from quoted (or possibly quoted) files. If quotes detected,
     // updates self.quote and self.delimiter. If quotes not detected, updates self.quote to
     // Quote::None. Only valid quote characters: " (double-quote), ' (single-quote), ` (back-tick).
     fn infer_quotes_delim<R: Read + Seek>(&mut self, reader: &mut R) -> Result<()> {
         if let (&Some(_), &Some(_)) = (&self.quote, &self.delimiter) {
             // nothing left to infer!
+            self.confidence.quote = 1.0;
             return Ok(());
         }
         let quote_guesses = match self.quote {
@@ -200,38 +2508,242 @@ impl Sniffer {
             Some(Quote::None) => {
                 // this function only checks quoted (or possibly quoted) files, nothing left to
                 // do if we know there are no quotes
+                self.confidence.quote = 1.0;
                 return Ok(());
             }
-            None => vec![b'\'', b'"', b'`'],
+            None => self.get_quote_candidates().to_vec(),
         };
-        let (quote_chr, (quote_cnt, delim_guess)) = quote_guesses.iter().try_fold(
-            (b'"', (0, b'\0')),
-            |acc, &chr| -> Result<(u8, (usize, u8))> {
-                let mut sample_reader = take_sample_from_start(reader, self.get_sample_size())?;
-                if let Some((cnt, delim_chr)) =
-                    quote_count(&mut sample_reader, char::from(chr), self.delimiter)?
-                {
-                    Ok(if cnt > acc.1 .0 {
-                        (chr, (cnt, delim_chr))
-                    } else {
-                        acc
-                    })
+        // keep track of the runner-up as well, to compute the confidence in the best guess
+        let (mut quote_chr, mut quote_cnt, mut delim_guess) = (b'"', 0, b'\0');
+        let mut runner_up_cnt = 0;
+        for &chr in &quote_guesses {
+            let mut sample_reader = self.take_sample(reader)?;
+            if let Some((cnt, delim_chr)) = quote_count(
+                &mut sample_reader,
+                char::from(chr),
+                self.get_quote_candidates(),
+                self.delimiter,
+                self.delimiter_candidates.as_deref(),
+            )? {
+                if cnt > quote_cnt {
+                    runner_up_cnt = quote_cnt;
+                    (quote_chr, quote_cnt, delim_guess) = (chr, cnt, delim_chr);
                 } else {
-                    Ok(acc)
+                    runner_up_cnt = runner_up_cnt.max(cnt);
                 }
-            },
-        )?;
+            }
+        }
         if quote_cnt == 0 {
             self.quote = Some(Quote::None);
+            // the more lines containing (unbalanced) candidate quote characters, the less sure we
+            // are that the file isn't quoted
+            let (mut n_lines, mut n_quote_lines) = (0, 0);
+            for line in self.take_sample(reader)? {
+                let line = line?;
+                n_lines += 1;
+                if line.bytes().any(|chr| quote_guesses.contains(&chr)) {
+                    n_quote_lines += 1;
+                }
+            }
+            self.confidence.quote = if n_lines == 0 {
+                1.0
+            } else {
+                1.0 - n_quote_lines as f64 / n_lines as f64
+            };
         } else {
             self.quote = Some(Quote::Some(quote_chr));
             self.delimiter = Some(delim_guess);
+            self.confidence.quote = quote_cnt as f64 / (quote_cnt + runner_up_cnt) as f64;
+        };
+        Ok(())
+    }
+
+    // Infers how quotes are escaped within quoted fields: with a backslash (e.g. \"), or by
+    // doubling them (e.g. ""). The escape character is chosen if it occurs more often in the sample
+    // than doubled quotes, and doubled quotes are recognized unless only backslash escapes were
+    // seen. Unquoted files have escapes disabled.
+    fn infer_escape<R: Read + Seek>(&mut self, reader: &mut R) -> Result<()> {
+        if self.escape.is_some() && self.double_quote.is_some() {
+            // nothing left to infer!
+            return Ok(());
+        }
+        let quote_chr = match self.quote {
+            Some(Quote::Some(chr)) => chr,
+            _ => {
+                self.escape.get_or_insert(Escape::Disabled);
+                self.double_quote.get_or_insert(true);
+                return Ok(());
+            }
+        };
+        let sample_iter = self.take_sample(reader)?;
+        let (mut n_backslash, mut n_doubled) = (0, 0);
+        for line in sample_iter {
+            let line = line?;
+            let (backslash, doubled) = escape_counts(line.as_bytes(), quote_chr, self.delimiter);
+            n_backslash += backslash;
+            n_doubled += doubled;
+        }
+        self.escape.get_or_insert(if n_backslash > n_doubled {
+            Escape::Enabled(b'\\')
+        } else {
+            Escape::Disabled
+        });
+        self.double_quote
+            .get_or_insert(n_doubled > 0 || n_backslash == 0);
+        Ok(())
+    }
+
+    // Infers how fields are quoted, by examining the raw fields of the data rows (preamble and
+    // header rows are skipped, since they're often quoted differently). Empty unquoted fields are
+    // ignored, since they don't tell us anything about the quote style.
+    fn infer_quote_style<R: Read + Seek>(&mut self, reader: &mut R) -> Result<()> {
+        let num_columns = self.types.len();
+        let (Some(Quote::Some(quote_chr)), Some(delim)) = (self.quote.clone(), self.delimiter)
+        else {
+            self.quote_style.get_or_insert(QuoteStyle::Never);
+            self.column_quoting = vec![ColumnQuoting::Never; num_columns];
+            return Ok(());
+        };
+        let escape = self.escape.clone().and_then(Into::into);
+        let n_skip =
+            self.num_preamble_rows.unwrap_or(0) + usize::from(self.has_header_row.unwrap_or(false));
+
+        // counts of quoted/unquoted fields, and of those which would be quoted by `Minimal` or
+        // `NonNumeric` quoting
+        let (mut n_quoted, mut n_unquoted) = (0, 0);
+        let (mut n_quoted_needed, mut n_quoted_nonnumeric) = (0, 0);
+        let mut n_unquoted_nonnumeric = 0;
+        // counts of quoted/unquoted fields in each column
+        let mut column_counts = vec![(0, 0); num_columns];
+        for line in self.take_sample(reader)?.skip(n_skip) {
+            let line = line?;
+            for (i, (field, quoted)) in split_fields(line.as_bytes(), delim, quote_chr, escape)
+                .into_iter()
+                .enumerate()
+            {
+                let is_numeric = simdutf8::basic::from_utf8(field)
+                    .is_ok_and(|field| field.trim().parse::<f64>().is_ok());
+                if let Some(counts) = column_counts.get_mut(i) {
+                    if quoted {
+                        counts.0 += 1;
+                    } else if !field.is_empty() {
+                        counts.1 += 1;
+                    }
+                }
+                if quoted {
+                    n_quoted += 1;
+                    if field.iter().any(|&chr| {
+                        chr == delim || chr == quote_chr || chr == b'\r' || chr == b'\n'
+                    }) {
+                        n_quoted_needed += 1;
+                    }
+                    if !is_numeric {
+                        n_quoted_nonnumeric += 1;
+                    }
+                } else if !field.is_empty() {
+                    n_unquoted += 1;
+                    if !is_numeric {
+                        n_unquoted_nonnumeric += 1;
+                    }
+                }
+            }
+        }
+        self.column_quoting = column_counts
+            .into_iter()
+            .map(|(n_quoted, n_unquoted)| match (n_quoted, n_unquoted) {
+                (0, _) => ColumnQuoting::Never,
+                (_, 0) => ColumnQuoting::Always,
+                _ => ColumnQuoting::Mixed,
+            })
+            .collect();
+        if self.quoted_numbers_as_text {
+            for (ty, quoting) in self.types.iter_mut().zip(&self.column_quoting) {
+                if *quoting == ColumnQuoting::Always
+                    && matches!(ty, Type::Unsigned | Type::Signed | Type::Float)
+                {
+                    *ty = Type::Text;
+                }
+            }
+        }
+        if self.quote_style.is_some() {
+            // nothing left to infer!
+            return Ok(());
+        }
+        self.quote_style = Some(if n_quoted == 0 {
+            QuoteStyle::Never
+        } else if n_unquoted == 0 {
+            QuoteStyle::Always
+        } else if n_quoted_nonnumeric == n_quoted
+            && n_unquoted_nonnumeric == 0
+            && n_quoted_needed < n_quoted
+        {
+            // every non-numeric field is quoted, even ones that don't need to be
+            QuoteStyle::NonNumeric
+        } else {
+            QuoteStyle::Minimal
+        });
+        Ok(())
+    }
+
+    // Infers whether the empty values of each column are quoted, by examining the raw fields of the
+    // data rows. Blank lines are ignored.
+    fn infer_empty_values<R: Read + Seek>(&mut self, reader: &mut R) -> Result<()> {
+        let num_columns = self.types.len();
+        self.empty_values = vec![EmptyValues::Absent; num_columns];
+        let Some(delim) = self.delimiter else {
+            return Ok(());
         };
+        let quote_chr = match self.quote {
+            Some(Quote::Some(quote_chr)) => Some(quote_chr),
+            _ => None,
+        };
+        let escape = self.escape.clone().and_then(Into::into);
+        let n_skip =
+            self.num_preamble_rows.unwrap_or(0) + usize::from(self.has_header_row.unwrap_or(false));
+
+        // counts of quoted/unquoted empty values in each column
+        let mut column_counts = vec![(0, 0); num_columns];
+        for line in self.take_sample(reader)?.skip(n_skip) {
+            let line = line?;
+            if line.is_empty() {
+                continue;
+            }
+            let fields = match quote_chr {
+                Some(quote_chr) => split_fields(line.as_bytes(), delim, quote_chr, escape),
+                None => line
+                    .as_bytes()
+                    .split(|&chr| chr == delim)
+                    .map(|field| (field, false))
+                    .collect(),
+            };
+            for ((field, quoted), counts) in fields.into_iter().zip(column_counts.iter_mut()) {
+                if field.is_empty() {
+                    if quoted {
+                        counts.0 += 1;
+                    } else {
+                        counts.1 += 1;
+                    }
+                }
+            }
+        }
+        self.empty_values = column_counts
+            .into_iter()
+            .map(|(n_quoted, n_unquoted)| match (n_quoted, n_unquoted) {
+                (0, 0) => EmptyValues::Absent,
+                (0, _) => EmptyValues::Unquoted,
+                (_, 0) => EmptyValues::Quoted,
+                _ => EmptyValues::Mixed,
+            })
+            .collect();
         Ok(())
     }
 
     // Updates delimiter frequency, number of preamble rows, and flexible boolean.
-    fn infer_preamble_known_delim<R: Read + Seek>(&mut self, reader: &mut R) -> Result<()> {
+    fn infer_preamble_known_delim<R: Read + Seek>(
+        &mut self,
+        reader: &mut R,
+        masked_quotes: &[u8],
+    ) -> Result<()> {
         // prerequisites for calling this function:
         if !(self.delimiter.is_some() && self.quote.is_some()) {
             // instead of assert, return an error
@@ -243,9 +2755,14 @@ impl Sniffer {
         // safety: unwraps for delimiter and quote are safe since we just checked above
         let (quote, delim) = (self.quote.clone().unwrap(), self.delimiter.unwrap());
 
-        let sample_iter = take_sample_from_start(reader, self.get_sample_size())?;
+        // a known preamble isn't observed, so it can't be mistaken for data
+        let sample_iter = self
+            .take_sample(reader)?
+            .skip(self.num_preamble_rows.unwrap_or(0));
 
         let mut chain = Chain::default();
+        // indices of the (non-blank) lines observed by the chain
+        let mut line_indices = vec![];
 
         if let Quote::Some(character) = quote {
             // since we have a quote, we need to run this data through the csv_core::Reader (which
@@ -253,124 +2770,761 @@ impl Sniffer {
             let mut csv_reader = csvc::ReaderBuilder::new()
                 .delimiter(delim)
                 .quote(character)
+                .escape(self.escape.clone().and_then(Into::into))
+                .double_quote(self.double_quote.unwrap_or(true))
                 .build();
 
-            let mut output = vec![];
-            let mut ends = vec![];
-            for line in sample_iter {
-                let line = line?;
-                if line.len() > output.len() {
-                    output.resize(line.len(), 0);
-                }
-                if line.len() > ends.len() {
-                    ends.resize(line.len(), 0);
+            let mut output = vec![];
+            let mut ends = vec![];
+            // quoted fields can contain line breaks, so a record can span several lines; it's
+            // observed once complete, as if it were on the line it started on
+            let (mut record_start, mut n_record_ends) = (None, 0);
+            for (i, line) in sample_iter.enumerate() {
+                let mut line = line?.into_bytes();
+                if line.is_empty() && record_start.is_none() {
+                    continue;
+                }
+                record_start.get_or_insert(i);
+                line.push(b'\n');
+                if line.len() > output.len() {
+                    output.resize(line.len(), 0);
+                }
+                if line.len() > ends.len() {
+                    ends.resize(line.len(), 0);
+                }
+                let (result, _, _, n_ends) = csv_reader.read_record(&line, &mut output, &mut ends);
+                n_record_ends += n_ends;
+                // check to make sure record was read correctly
+                match result {
+                    csvc::ReadRecordResult::OutputFull | csvc::ReadRecordResult::OutputEndsFull => {
+                        return Err(SnifferError::SniffingFailed(format!(
+                            "failure to read quoted CSV record: {result:?}"
+                        )));
+                    }
+                    csvc::ReadRecordResult::Record => {
+                        // safety: the start of the record was set above
+                        line_indices.push(record_start.take().unwrap());
+                        // n_record_ends is the number of boundaries after each field, so it's one
+                        // more than the number of delimiters
+                        chain.add_observation(n_record_ends.saturating_sub(1));
+                        n_record_ends = 0;
+                    }
+                    _ => {} // the record continues on the next line
+                }
+            }
+        } else {
+            for (i, line) in sample_iter.enumerate() {
+                let line = line?;
+                if line.is_empty() {
+                    continue;
+                }
+                line_indices.push(i);
+                let freq = bytecount::count(&strip_quoted(line.as_bytes(), masked_quotes), delim);
+                chain.add_observation(freq);
+            }
+        }
+        self.run_chains(vec![chain], &line_indices, &[])
+    }
+
+    // Updates delimiter, delimiter frequency, number of preamble rows, and flexible boolean.
+    // Characters within text quoted by one of `masked_quotes` aren't counted.
+    fn infer_delim_preamble<R: Read + Seek>(
+        &mut self,
+        reader: &mut R,
+        masked_quotes: &[u8],
+    ) -> Result<()> {
+        // a known preamble isn't observed, so it can't be mistaken for data
+        let sample_iter = self
+            .take_sample(reader)?
+            .skip(self.num_preamble_rows.unwrap_or(0));
+
+        const NUM_ASCII_CHARS: usize = 128;
+        let mut chains = vec![Chain::default(); NUM_ASCII_CHARS];
+        let mut lines = vec![];
+        // indices of the (non-blank) lines observed by the chains
+        let mut line_indices = vec![];
+        for (i, line) in sample_iter.enumerate() {
+            let line = line?;
+            // blank lines (e.g. between groups of records) are skipped by the csv reader, so
+            // they're not observed either
+            if line.is_empty() {
+                continue;
+            }
+            line_indices.push(i);
+            let mut freqs = [0; NUM_ASCII_CHARS];
+            for chr in strip_quoted(line.as_bytes(), masked_quotes) {
+                if chr < NUM_ASCII_CHARS as u8 {
+                    freqs[chr as usize] += 1;
+                }
+            }
+            for (chr, &freq) in freqs.iter().enumerate() {
+                // chains without any observations are ignored when finding the best delimiter
+                if self.is_delimiter_candidate(chr as u8) {
+                    chains[chr].add_observation(freq);
+                }
+            }
+            lines.push(line);
+        }
+
+        // European CSVs pair `;` delimiters with `,` decimals, which makes `,` look like a
+        // plausible delimiter as well. If both are plausible, choose the delimiter & decimal
+        // separator pair which splits every line (the header included) into as many fields, and
+        // under which more fields are numbers; if neither pair does, the chains choose.
+        let is_steady = |chain: &mut Chain| matches!(chain.viterbi().path.last(), Some(&(state, _)) if state != STATE_UNSTEADY);
+        let mut excluded = vec![];
+        if self.decimal_separator.is_none()
+            && is_steady(&mut chains[b',' as usize])
+            && is_steady(&mut chains[b';' as usize])
+        {
+            let score = |delim: u8, decimal_separator: u8| {
+                let is_consistent = self.has_steady_field_count(&lines, delim);
+                (
+                    is_consistent,
+                    self.count_numeric_fields(&lines, delim, decimal_separator),
+                )
+            };
+            let comma_score = score(b',', b'.');
+            let semicolon_score = score(b';', b',');
+            if semicolon_score.0 && semicolon_score > comma_score {
+                excluded.push((b',', Rejection::DecimalSeparator));
+            } else if comma_score.0 && comma_score > semicolon_score {
+                excluded.push((b';', Rejection::DecimalSeparator));
+            }
+        }
+        // Letters and digits are never plausible delimiters, unless they're explicitly listed as
+        // candidates
+        if self.delimiter_candidates.is_none() {
+            excluded.extend(
+                (0..NUM_ASCII_CHARS as u8)
+                    .filter(u8::is_ascii_alphanumeric)
+                    .map(|chr| (chr, Rejection::Alphanumeric)),
+            );
+        }
+        // Padded fields (e.g. `value , other`) make the padding look like a plausible delimiter as
+        // well, so ignore spaces if they only ever surround another plausible delimiter.
+        if is_steady(&mut chains[b' ' as usize]) {
+            let padded_delim = (0..NUM_ASCII_CHARS as u8).find(|&chr| {
+                chr != b' '
+                    && !excluded
+                        .iter()
+                        .any(|&(excluded_delim, _)| excluded_delim == chr)
+                    && is_padding_around(&lines, b' ', chr)
+                    && is_steady(&mut chains[chr as usize])
+            });
+            if padded_delim.is_some() {
+                excluded.push((b' ', Rejection::Padding));
+            }
+        }
+
+        // With only a row or two, any character occurring the same number of times in each row
+        // looks like a steady delimiter, so choose between them on their punctuation class and on
+        // how much the first row looks like a header.
+        if lines.len() <= MAX_SMALL_FILE_ROWS {
+            let plausible = (0..NUM_ASCII_CHARS as u8)
+                .filter(|&chr| {
+                    !excluded
+                        .iter()
+                        .any(|&(excluded_delim, _)| excluded_delim == chr)
+                        && is_steady(&mut chains[chr as usize])
+                })
+                .collect::<Vec<_>>();
+            let score = |chr: u8| {
+                (
+                    punctuation_class(chr),
+                    self.count_header_fields(&lines, chr),
+                )
+            };
+            // the first of the best-scoring delimiters
+            let best = plausible
+                .iter()
+                .copied()
+                .fold(None, |best, chr| match best {
+                    Some(best) if score(best) >= score(chr) => Some(best),
+                    _ => Some(chr),
+                });
+            excluded.extend(
+                plausible
+                    .into_iter()
+                    .filter(|&chr| Some(chr) != best)
+                    .map(|chr| (chr, Rejection::Punctuation)),
+            );
+        }
+
+        self.run_chains(chains, &line_indices, &excluded)
+    }
+
+    // Counts the fields of the first line which look like column names (non-empty, and not
+    // numbers, with either decimal separator since it isn't known yet), when splitting it with the
+    // provided delimiter. Only meaningful if there's a line after it, since a lone line is more
+    // likely to be data.
+    fn count_header_fields(&self, lines: &[String], delim: u8) -> usize {
+        if lines.len() < 2 {
+            return 0;
+        }
+        self.raw_fields(lines[0].as_bytes(), delim)
+            .into_iter()
+            .filter(|field| {
+                simdutf8::basic::from_utf8(field).is_ok_and(|field| {
+                    let field = field.trim();
+                    !field.is_empty()
+                        && parse_decimal(field, b'.').is_none()
+                        && parse_decimal(field, b',').is_none()
+                })
+            })
+            .count()
+    }
+
+    // Counts the fields which are numbers, when splitting the lines with the provided delimiter
+    // and parsing numbers with the provided decimal separator (and possibly grouped, as in
+    // `1.234.567,89`).
+    fn count_numeric_fields(&self, lines: &[String], delim: u8, decimal_separator: u8) -> usize {
+        lines
+            .iter()
+            .flat_map(|line| self.raw_fields(line.as_bytes(), delim))
+            .filter(|field| {
+                simdutf8::basic::from_utf8(field).is_ok_and(|field| {
+                    let number =
+                        strip_thousands(field.trim(), decimal_separator, &THOUSANDS_SEPARATORS);
+                    parse_decimal(&number, decimal_separator).is_some()
+                })
+            })
+            .count()
+    }
+
+    // Whether splitting the lines with the provided delimiter gives each of them as many fields.
+    fn has_steady_field_count(&self, lines: &[String], delim: u8) -> bool {
+        let mut field_counts = lines
+            .iter()
+            .map(|line| self.raw_fields(line.as_bytes(), delim).len());
+        let first = field_counts.next();
+        field_counts.all(|n_fields| Some(n_fields) == first)
+    }
+
+    // Infers the decimal separator from the numbers in the data rows containing a period or a
+    // comma. Periods are assumed, unless comma decimals are more common.
+    fn infer_decimal_separator<R: Read + Seek>(&mut self, reader: &mut R) -> Result<()> {
+        if self.decimal_separator.is_some() {
+            // nothing left to infer!
+            return Ok(());
+        }
+        let Some(delim) = self.delimiter else {
+            return Err(SnifferError::SniffingFailed(
+                "delimiter not known".to_string(),
+            ));
+        };
+        let (mut n_period, mut n_comma) = (0, 0);
+        for line in self
+            .take_sample(reader)?
+            .skip(self.num_preamble_rows.unwrap_or(0))
+        {
+            let line = line?;
+            for field in self.raw_fields(line.as_bytes(), delim) {
+                let Ok(field) = simdutf8::basic::from_utf8(field) else {
+                    continue;
+                };
+                let field = field.trim();
+                // numbers grouped with commas (e.g. `1,234,567`) use periods as decimal separators,
+                // and vice versa
+                if field.contains('.') && parse_decimal(field, b'.').is_some()
+                    || thousands_separator(field, b'.', &THOUSANDS_SEPARATORS) == Some(b',')
+                {
+                    n_period += 1;
+                } else if field.contains(',') && parse_decimal(field, b',').is_some()
+                    || thousands_separator(field, b',', &THOUSANDS_SEPARATORS) == Some(b'.')
+                {
+                    n_comma += 1;
+                }
+            }
+        }
+        self.decimal_separator = Some(if n_comma > n_period { b',' } else { b'.' });
+        Ok(())
+    }
+    // Counts the blank lines within the data. In single-column files, these are likely records with
+    // an empty field (i.e. missing values); otherwise, they likely separate groups of records.
+    fn infer_blank_lines<R: Read + Seek>(&mut self, reader: &mut R) -> Result<()> {
+        let mut num_blank_lines = 0;
+        let sample_iter = self
+            .take_sample(reader)?
+            .skip(self.num_preamble_rows.unwrap_or(0));
+        for line in sample_iter {
+            if line?.is_empty() {
+                num_blank_lines += 1;
+            }
+        }
+        self.num_blank_lines = Some(num_blank_lines);
+        if self.blank_lines.is_none() {
+            self.blank_lines = Some(if num_blank_lines > 0 && self.delimiter_freq == Some(0) {
+                BlankLinePolicy::EmptyRecord
+            } else {
+                BlankLinePolicy::Skip
+            });
+        }
+        Ok(())
+    }
+
+    // Infers the number of footer rows, by examining the lines at the end of the file. Going
+    // backwards, a line is part of the footer if it's blank, a comment, or starts with one of the
+    // footer keywords (e.g. `Total` or `END OF REPORT`) and is shaped differently from the data row
+    // before it. A few summary lines following a blank line are part of the footer as well. Nothing
+    // is considered a footer if it would leave no data rows.
+    fn infer_footer<R: Read + Seek>(&mut self, reader: &mut R) -> Result<()> {
+        if self.num_footer_rows.is_some() {
+            // nothing left to infer!
+            return Ok(());
+        }
+        let Some((tail_start, tail)) = self.read_tail(reader)? else {
+            // the end of the file is out of the sample's reach
+            self.num_footer_rows = Some(0);
+            return Ok(());
+        };
+
+        let record_end = self.get_record_end();
+        // line terminators ending the file (even a blank line or two) aren't footer rows
+        let n_trailing = tail
+            .iter()
+            .rev()
+            .take_while(|&&chr| chr == record_end || chr == b'\r')
+            .count();
+        let tail = &tail[..tail.len() - n_trailing];
+        let mut lines = tail.split(|&chr| chr == record_end).collect::<Vec<_>>();
+        // the first line is likely cut off
+        let mut line_start = tail_start;
+        if tail_start > 0 {
+            line_start += lines.remove(0).len() as u64 + 1;
+        }
+        let comment = self.get_comment();
+        let is_blank = |line: &[u8]| line.iter().all(u8::is_ascii_whitespace);
+        let starts_with_keyword = |line: &[u8]| {
+            let line = String::from_utf8_lossy(line).to_lowercase();
+            let line = line.trim_start_matches(|chr: char| {
+                chr.is_whitespace() || self.get_quote_candidates().contains(&(chr as u8))
+            });
+            FOOTER_KEYWORDS.iter().any(|keyword| {
+                line.strip_prefix(keyword)
+                    .is_some_and(|rest| !rest.starts_with(|chr: char| chr.is_alphanumeric()))
+            })
+        };
+        let is_footer_line = |line: &[u8]| {
+            is_blank(line) || line.first() == comment.as_ref() || starts_with_keyword(line)
+        };
+
+        let count_footer_lines = |lines: &[&[u8]]| {
+            let n_candidates = lines
+                .iter()
+                .rev()
+                .take_while(|line| is_footer_line(line))
+                .count();
+            let Some(data_line) = lines.len().checked_sub(n_candidates + 1).map(|i| lines[i])
+            else {
+                return n_candidates;
+            };
+            // rows starting with a keyword but shaped like the data row before them (e.g. an item
+            // named `Total widgets`) are data rows themselves, as are the rows before them
+            let candidates = &lines[lines.len() - n_candidates..];
+            candidates
+                .iter()
+                .rposition(|line| {
+                    starts_with_keyword(line)
+                        && line_shape(line, self.delimiter) == line_shape(data_line, self.delimiter)
+                })
+                .map_or(n_candidates, |i| n_candidates - i - 1)
+        };
+        let mut n_footer_rows = count_footer_lines(&lines);
+        // a few summary lines after a blank line, as long as they're shaped differently from the
+        // line before the blank one (otherwise, the blank line is likely between record groups)
+        let rest = &lines[..lines.len() - n_footer_rows];
+        if let Some(n_summary_rows) = rest
+            .iter()
+            .rev()
+            .take(MAX_SUMMARY_ROWS + 1)
+            .position(|line| is_blank(line))
+        {
+            let blank_idx = rest.len() - 1 - n_summary_rows;
+            let is_summary = n_summary_rows > 0
+                && blank_idx > 0
+                && rest[blank_idx + 1..].iter().all(|line| {
+                    common_delimiter_counts(line) != common_delimiter_counts(rest[blank_idx - 1])
+                });
+            if is_summary {
+                n_footer_rows += n_summary_rows + 1;
+                n_footer_rows += count_footer_lines(&lines[..lines.len() - n_footer_rows]);
+            }
+        }
+        // the top line of the file may be a header row, which leaves no data rows
+        if n_footer_rows + usize::from(tail_start == 0) >= lines.len() {
+            self.num_footer_rows = Some(0);
+            return Ok(());
+        }
+        self.num_footer_rows = Some(n_footer_rows);
+        if n_footer_rows > 0 {
+            let n_data_lines = lines.len() - n_footer_rows;
+            self.footer_start = Some(
+                line_start
+                    + lines[..n_data_lines]
+                        .iter()
+                        .map(|line| line.len() as u64 + 1)
+                        .sum::<u64>(),
+            );
+        }
+        Ok(())
+    }
+
+    // Reads the last few kilobytes of the reader, returning them along with their offset (or the
+    // end of the file fetched besides the sample, when the reader is the sample as-is). Readers
+    // rewriting a multi-byte delimiter can't seek from the end, so they're read through instead,
+    // but only as far as the sample reaches: `None` if the file is longer than that.
+    fn read_tail<R: Read + Seek>(&self, reader: &mut R) -> Result<Option<(u64, Vec<u8>)>> {
+        if let Ok(len) = reader.seek(SeekFrom::End(0)) {
+            #[cfg(feature = "http")]
+            if let Some(tail) = &self.tail {
+                return Ok(Some(tail.clone()));
+            }
+            let tail_start = len.saturating_sub(FOOTER_SAMPLE_BYTES);
+            reader.seek(SeekFrom::Start(tail_start))?;
+            let mut tail = vec![];
+            reader.read_to_end(&mut tail)?;
+            return Ok(Some((tail_start, tail)));
+        }
+        let max_len = match self.get_type_sample_size() {
+            SampleSize::Bytes(n_bytes)
+            | SampleSize::Adaptive {
+                max_bytes: n_bytes, ..
+            } => Some(n_bytes as u64 + SAMPLE_SLACK),
+            SampleSize::Duration(_) => Some(MAX_TIMED_SAMPLE_BYTES as u64),
+            SampleSize::Records(_) | SampleSize::All | SampleSize::Percent(_) => None,
+        };
+        reader.seek(SeekFrom::Start(0))?;
+        let mut reader = reader.take(max_len.map_or(u64::MAX, |max_len| max_len + 1));
+        let mut tail = vec![];
+        let mut buf = [0; 1 << 13];
+        let mut len = 0;
+        loop {
+            let n_read = reader.read(&mut buf)?;
+            if n_read == 0 {
+                break;
+            }
+            len += n_read as u64;
+            tail.extend_from_slice(&buf[..n_read]);
+            // keeping (at most) twice as much as needed, so that bytes aren't shifted every read
+            if tail.len() as u64 > 2 * FOOTER_SAMPLE_BYTES {
+                tail.drain(..tail.len() - FOOTER_SAMPLE_BYTES as usize);
+            }
+        }
+        if max_len.is_some_and(|max_len| len > max_len) {
+            return Ok(None);
+        }
+        let tail = tail.split_off(tail.len().saturating_sub(FOOTER_SAMPLE_BYTES as usize));
+        Ok(Some((len - tail.len() as u64, tail)))
+    }
+
+    // Infers whether unquoted fields contain C-style escape sequences (`\n`, `\r`, `\t` or `\\`).
+    // Backslashes are also common in unescaped text (e.g. Windows paths like `C:\Users\new`), so
+    // most of the backslashes in unquoted fields need to start one of these sequences.
+    fn infer_embedded_escapes<R: Read + Seek>(&mut self, reader: &mut R) -> Result<()> {
+        if self.embedded_escapes.is_some() {
+            // nothing left to infer!
+            return Ok(());
+        }
+        let Some(delim) = self.delimiter else {
+            return Err(SnifferError::SniffingFailed(
+                "delimiter not known".to_string(),
+            ));
+        };
+        let (mut n_escapes, mut n_backslashes) = (0, 0);
+        for line in self
+            .take_sample(reader)?
+            .skip(self.num_preamble_rows.unwrap_or(0))
+        {
+            let line = line?;
+            let fields = match self.quote {
+                Some(Quote::Some(quote)) => {
+                    let escape = self.escape.clone().and_then(Into::into);
+                    split_fields(line.as_bytes(), delim, quote, escape)
+                        .into_iter()
+                        .filter(|&(_, quoted)| !quoted)
+                        .map(|(field, _)| field)
+                        .collect()
                 }
-                let (result, _, _, n_ends) =
-                    csv_reader.read_record(line.as_bytes(), &mut output, &mut ends);
-                // check to make sure record was read correctly
-                match result {
-                    csvc::ReadRecordResult::OutputFull | csvc::ReadRecordResult::OutputEndsFull => {
-                        return Err(SnifferError::SniffingFailed(format!(
-                            "failure to read quoted CSV record: {result:?}"
-                        )));
+                _ => self.raw_fields(line.as_bytes(), delim),
+            };
+            for field in fields {
+                let mut i = 0;
+                while i < field.len() {
+                    if field[i] == b'\\' {
+                        n_backslashes += 1;
+                        if matches!(field.get(i + 1), Some(b'n' | b'r' | b't' | b'\\')) {
+                            n_escapes += 1;
+                            // skip the escaped character, so `\\` isn't counted twice
+                            i += 1;
+                        }
                     }
-                    _ => {} // non-error results, do nothing
+                    i += 1;
                 }
-                // n_ends is the number of barries between fields, so it's the same as the number
-                // of delimiters
-                chain.add_observation(n_ends);
-            }
-        } else {
-            for line in sample_iter {
-                let line = line?;
-                let freq = bytecount::count(line.as_bytes(), delim);
-                chain.add_observation(freq);
             }
         }
-        self.run_chains(vec![chain])
+        self.embedded_escapes =
+            Some(n_escapes >= MIN_EMBEDDED_ESCAPES && 2 * n_escapes > n_backslashes);
+        Ok(())
     }
 
-    // Updates delimiter, delimiter frequency, number of preamble rows, and flexible boolean.
-    fn infer_delim_preamble<R: Read + Seek>(&mut self, reader: &mut R) -> Result<()> {
-        let sample_iter = take_sample_from_start(reader, self.get_sample_size())?;
-
-        const NUM_ASCII_CHARS: usize = 128;
-        let mut chains = vec![Chain::default(); NUM_ASCII_CHARS];
-        for line in sample_iter {
+    // Infers whether fields are padded with whitespace (e.g. `value , other`). The header row and
+    // the data rows are considered separately; either is trimmed if at least half of its
+    // non-empty fields have leading or trailing whitespace.
+    fn infer_trim<R: Read + Seek>(&mut self, reader: &mut R) -> Result<()> {
+        if self.trim.is_some() {
+            // nothing left to infer!
+            return Ok(());
+        }
+        let Some(delim) = self.delimiter else {
+            return Err(SnifferError::SniffingFailed(
+                "delimiter not known".to_string(),
+            ));
+        };
+        let is_padding = |chr: &u8| (*chr == b' ' || *chr == b'\t') && *chr != delim;
+        // counts of (padded, non-empty) fields in the top row and in the remaining rows
+        let (mut top_counts, mut data_counts) = ((0, 0), (0, 0));
+        let sample_iter = self
+            .take_sample(reader)?
+            .skip(self.num_preamble_rows.unwrap_or(0));
+        for (i, line) in sample_iter.enumerate() {
             let line = line?;
-            let mut freqs = [0; NUM_ASCII_CHARS];
-            for &chr in line.as_bytes() {
-                if chr < NUM_ASCII_CHARS as u8 {
-                    freqs[chr as usize] += 1;
+            let counts = if i == 0 {
+                &mut top_counts
+            } else {
+                &mut data_counts
+            };
+            for field in self.raw_fields(line.as_bytes(), delim) {
+                if field.iter().all(is_padding) {
+                    continue;
+                }
+                counts.1 += 1;
+                if field.first().is_some_and(is_padding) || field.last().is_some_and(is_padding) {
+                    counts.0 += 1;
                 }
-            }
-            for (chr, &freq) in freqs.iter().enumerate() {
-                chains[chr].add_observation(freq);
             }
         }
+        let is_padded =
+            |(n_padded, n_fields): (usize, usize)| n_padded > 0 && 2 * n_padded >= n_fields;
+        self.trim = Some(match (is_padded(top_counts), is_padded(data_counts)) {
+            (true, true) => Trim::All,
+            (true, false) => Trim::Headers,
+            (false, true) => Trim::Fields,
+            (false, false) => Trim::None,
+        });
+        Ok(())
+    }
 
-        self.run_chains(chains)
+    // Splits a line into its raw fields, using the provided delimiter and the quote and escape
+    // characters found so far.
+    fn raw_fields<'a>(&self, line: &'a [u8], delim: u8) -> Vec<&'a [u8]> {
+        match self.quote {
+            Some(Quote::Some(quote)) => {
+                let escape = self.escape.clone().and_then(Into::into);
+                split_fields(line, delim, quote, escape)
+                    .into_iter()
+                    .map(|(field, _)| field)
+                    .collect()
+            }
+            _ => line.split(|&chr| chr == delim).collect(),
+        }
     }
 
     // Updates delimiter (if not already known), delimiter frequency, number of preamble rows, and
-    // flexible boolean.
-    fn run_chains(&mut self, mut chains: Vec<Chain>) -> Result<()> {
+    // flexible boolean. `line_indices` are the indices of the sampled lines the chains observed,
+    // and `excluded` lists the delimiters ruled out by earlier heuristics. Every chain which
+    // observed its delimiter is recorded as a candidate, for diagnostics.
+    fn run_chains(
+        &mut self,
+        mut chains: Vec<Chain>,
+        line_indices: &[usize],
+        excluded: &[(u8, Rejection)],
+    ) -> Result<()> {
         // Find the 'best' delimiter: choose strict (non-flexible) delimiters over flexible ones,
-        // and choose the one that had the highest probability markov chain in the end.
+        // and choose the one that had the highest probability markov chain in the end. The
+        // runner-up is tracked as well, to compute the confidence in the best delimiter.
         //
-        // In the case where delim is already known, 'best_delim' will be incorrect (since it won't
-        // correspond with position in a vector of Chains), but we'll just ignore it when
-        // constructing our return value later. 'best_state' and 'path' are necessary, though, to
-        // compute the preamble rows.
-        let (best_delim, delim_freq, best_state, path, _) = chains.iter_mut().enumerate().fold(
-            (b',', 0, STATE_UNSTEADY, vec![], 0.0),
-            |acc, (i, ref mut chain)| {
-                let (_, _, best_state, _, best_state_prob) = acc;
-                let ViterbiResults {
-                    max_delim_freq,
-                    path,
-                } = chain.viterbi();
-                if path.is_empty() {
-                    return acc;
-                }
-                let (final_state, final_viter) = path[path.len() - 1];
-                if final_state < best_state
-                    || (final_state == best_state && final_viter.prob > best_state_prob)
-                {
-                    (i as u8, max_delim_freq, final_state, path, final_viter.prob)
-                } else {
-                    acc
-                }
-            },
-        );
-        self.flexible = Some(match best_state {
-            STATE_STEADYSTRICT => false,
-            STATE_STEADYFLEX => true,
-            _ => {
-                return Err(SnifferError::SniffingFailed(
-                    "unable to find valid delimiter".to_string(),
-                ));
+        // In the case where delim is already known, there's only one chain, so 'best_delim' will
+        // be incorrect (since it won't correspond with position in a vector of Chains), but we'll
+        // just ignore it when constructing our return value later. 'best_state' and 'path' are
+        // necessary, though, to compute the preamble rows.
+        let is_better = |(state, prob): (usize, f64), (other_state, other_prob): (usize, f64)| {
+            state < other_state || (state == other_state && prob > other_prob)
+        };
+        let (mut best_delim, mut delim_freq, mut path) = (b',', 0, vec![]);
+        let (mut best_state, mut best_state_prob) = (STATE_UNSTEADY, 0.0);
+        let mut runner_up = (STATE_UNSTEADY, 0.0);
+        let mut candidates = vec![];
+        for (i, chain) in chains.iter_mut().enumerate() {
+            let ViterbiResults {
+                max_delim_freq,
+                path: chain_path,
+            } = chain.viterbi();
+            let Some(&(final_state, final_viter)) = chain_path.last() else {
+                continue;
+            };
+            let delimiter = self.delimiter.unwrap_or(i as u8);
+            // priors only come into play when comparing probabilities of chains in the same state
+            let weighted_prob = final_viter.prob * self.get_delimiter_prior(delimiter);
+            let rejection = excluded
+                .iter()
+                .find(|(excluded_delim, _)| *excluded_delim == delimiter)
+                .map(|&(_, rejection)| rejection);
+            if max_delim_freq > 0 {
+                candidates.push(DialectCandidate {
+                    delimiter,
+                    state: ChainState::from_state(final_state),
+                    probability: final_viter.prob,
+                    max_frequency: max_delim_freq,
+                    num_preamble_rows: count_preamble_rows(&chain_path, final_state, line_indices),
+                    rejection,
+                });
+            }
+            if rejection.is_some() {
+                continue;
+            }
+            if is_better((final_state, weighted_prob), (best_state, best_state_prob)) {
+                runner_up = (best_state, best_state_prob);
+                (best_delim, delim_freq, path) = (i as u8, max_delim_freq, chain_path);
+                (best_state, best_state_prob) = (final_state, weighted_prob);
+            } else if is_better((final_state, weighted_prob), runner_up) {
+                runner_up = (final_state, weighted_prob);
             }
+        }
+        // rank the candidates the same way as the best delimiter was chosen
+        let weighted_prob = |candidate: &DialectCandidate| {
+            candidate.probability * self.get_delimiter_prior(candidate.delimiter)
+        };
+        candidates.sort_by(|a, b| {
+            a.state
+                .cmp(&b.state)
+                .then(weighted_prob(b).total_cmp(&weighted_prob(a)))
         });
-
-        // Find the number of preamble rows (the number of rows during which the state fluctuated
-        // before getting to the final state).
-        let mut num_preamble_rows = 0;
-        // since path has an extra state as the beginning, skip one
-        for &(state, _) in path.iter().skip(2) {
-            if state == best_state {
-                break;
+        for candidate in &mut candidates {
+            if candidate.rejection.is_none()
+                && self.delimiter.unwrap_or(best_delim) != candidate.delimiter
+            {
+                candidate.rejection = Some(if candidate.state == ChainState::Unsteady {
+                    Rejection::Unsteady
+                } else {
+                    Rejection::Outscored
+                });
+            }
+        }
+        if best_state == STATE_UNSTEADY {
+            for candidate in &mut candidates {
+                candidate.rejection.get_or_insert(Rejection::Unsteady);
             }
-            num_preamble_rows += 1;
         }
-        if num_preamble_rows > 0 {
-            num_preamble_rows += 1;
+        if best_state == STATE_UNSTEADY {
+            return self.set_single_column(chains, candidates);
         }
+        self.candidates = candidates;
+        self.flexible.get_or_insert(best_state == STATE_STEADYFLEX);
+        self.single_column = Some(false);
+
+        // Find the number of preamble rows (the number of rows during which the state fluctuated
+        // before getting to the final state).
+        let num_preamble_rows = count_preamble_rows(&path, best_state, &[]);
+        // Delimiter confidence: the fraction of (non-preamble) rows with the maximum delimiter
+        // frequency, scaled down if the runner-up delimiter ended in the same state with a
+        // similar probability.
+        let observations = chains[best_delim as usize].observations();
+        let data_observations = &observations[num_preamble_rows.min(observations.len())..];
+        let n_deviating = data_observations
+            .iter()
+            .filter(|&&freq| freq != delim_freq)
+            .count();
+        let consistency = if data_observations.is_empty() {
+            1.0
+        } else {
+            1.0 - n_deviating as f64 / data_observations.len() as f64
+        };
+        let separation = if runner_up.0 == best_state && best_state_prob > 0.0 {
+            1.0 - runner_up.1 / best_state_prob
+        } else {
+            1.0
+        };
+        self.confidence.delimiter = consistency * separation;
+        // Flexible confidence: strict files are certain, while flexible files get more certain
+        // the more rows deviate from the maximum frequency (a single deviating row could just be
+        // a malformed record).
+        self.confidence.flexible = if best_state == STATE_STEADYSTRICT {
+            1.0
+        } else {
+            1.0 - 1.0 / (1.0 + n_deviating as f64)
+        };
+
         if self.delimiter.is_none() {
             self.delimiter = Some(best_delim);
         }
         self.delimiter_freq = Some(delim_freq);
-        self.num_preamble_rows = Some(num_preamble_rows);
+        self.num_preamble_rows
+            .get_or_insert_with(|| count_preamble_rows(&path, best_state, line_indices));
+        Ok(())
+    }
+
+    // Used when no candidate delimiter is plausible: the file is read as a single column, with a
+    // delimiter that doesn't occur in the sample (or with the known delimiter, if it doesn't
+    // occur). The more lines an unsteady candidate occurs in, the less sure we are that the file
+    // really only has a single column.
+    fn set_single_column(
+        &mut self,
+        chains: Vec<Chain>,
+        candidates: Vec<DialectCandidate>,
+    ) -> Result<()> {
+        let is_absent = |chain: &Chain| {
+            !chain.observations().is_empty() && chain.observations().iter().all(|&freq| freq == 0)
+        };
+        let delimiter = match self.delimiter {
+            Some(_) if chains.iter().all(is_absent) => self.delimiter,
+            Some(_) if self.get_strictness() == Strictness::Lenient => {
+                // best effort: read the records with the known delimiter, however irregular
+                let max_freq = chains
+                    .iter()
+                    .flat_map(Chain::observations)
+                    .max()
+                    .copied()
+                    .unwrap_or(0);
+                self.candidates = candidates;
+                self.single_column = Some(false);
+                self.flexible.get_or_insert(true);
+                self.confidence.flexible = 0.0;
+                self.delimiter_freq = Some(max_freq);
+                self.num_preamble_rows = Some(self.num_preamble_rows.unwrap_or(0));
+                return Ok(());
+            }
+            Some(_) => None,
+            None => Some(
+                SINGLE_COLUMN_DELIMITERS
+                    .into_iter()
+                    .find(|&chr| is_absent(&chains[chr as usize]))
+                    .unwrap_or(SINGLE_COLUMN_DELIMITERS[SINGLE_COLUMN_DELIMITERS.len() - 1]),
+            ),
+        };
+        let Some(delimiter) = delimiter else {
+            self.candidates = candidates;
+            return Err(SnifferError::SniffingFailed(
+                "unable to find valid delimiter".to_string(),
+            ));
+        };
+        let max_occurrence = candidates
+            .iter()
+            .filter(|candidate| candidate.rejection == Some(Rejection::Unsteady))
+            .filter_map(|candidate| chains.get(candidate.delimiter as usize))
+            .map(|chain| {
+                let observations = chain.observations();
+                let n_present = observations.iter().filter(|&&freq| freq > 0).count();
+                n_present as f64 / observations.len().max(1) as f64
+            })
+            .fold(0.0, f64::max);
+        self.confidence.delimiter = 1.0 - max_occurrence;
+        self.confidence.flexible = 1.0;
+        self.candidates = candidates;
+        self.delimiter = Some(delimiter);
+        self.single_column = Some(true);
+        self.flexible.get_or_insert(false);
+        self.delimiter_freq = Some(0);
+        self.num_preamble_rows = Some(self.num_preamble_rows.unwrap_or(0));
         Ok(())
     }
 
@@ -385,22 +3539,80 @@ impl Sniffer {
         }
         // safety: unwrap is safe as we just checked that delimiter_freq is Some
         let field_count = self.delimiter_freq.unwrap() + 1;
+        let type_options = self.get_type_options();
+        let utf8_handling = self.get_utf8_handling();
+        // whether there's a header row is only inferred if it wasn't specified
+        let known_header = self.has_header_row;
 
         let mut csv_reader = self.create_csv_reader(reader)?;
+        // the csv reader treats the top row as a header row, so it's all there is in a file with a
+        // single row
+        let top_row = csv_reader.byte_headers()?.clone();
         let mut records_iter = csv_reader.byte_records();
         let mut n_bytes = 0;
         let mut n_records = 0;
-        let sample_size = self.get_sample_size();
+        let sample_size = self.get_type_sample_size();
+
+        // whether every record (including the header) ends with an empty field
+        let mut trailing_delimiter = true;
+        // whether any field contains a line break
+        let has_line_break = |record: &ByteRecord| {
+            record
+                .iter()
+                .any(|field| field.contains(&b'\n') || field.contains(&b'\r'))
+        };
+        let mut has_multiline_fields = false;
+
+        // whether the top row is followed by other rows
+        let mut has_more_rows = true;
+
+        // distinct (lowercase) values of the columns which might be boolean, as a boolean column
+        // only contains the two values of a single pair
+        let mut boolean_values: Vec<Vec<String>> = vec![vec![]; field_count];
+        // distinct missing values of each column
+        let mut null_tokens: Vec<Vec<String>> = vec![vec![]; field_count];
 
         // Infer types for the top row. We'll save this set of types to check against the types
         // of the remaining rows to see if this is part of the data or a separate header row.
         let header_row_types = match records_iter.next() {
             Some(record) => {
                 let byte_record = record?;
-                let str_record = StringRecord::from_byte_record_lossy(byte_record);
+                trailing_delimiter &= ends_with_empty_field(&byte_record);
+                has_multiline_fields |= has_line_break(&byte_record);
+                let str_record = decode_record(byte_record, utf8_handling);
+                n_records += 1;
+                n_bytes += count_bytes(&str_record);
+                // the csv reader's header is the top row, so this is a data row either way
+                for (i, field) in str_record.iter().enumerate().take(field_count) {
+                    if type_options.is_null(field) {
+                        if !null_tokens[i].iter().any(|token| token == field) {
+                            null_tokens[i].push(field.to_string());
+                        }
+                        continue;
+                    }
+                    let value = field.to_lowercase();
+                    if !boolean_values[i].contains(&value) {
+                        boolean_values[i].push(value);
+                    }
+                }
+                infer_record_types(&str_record, &type_options)
+            }
+            None if !top_row.is_empty() => {
+                has_more_rows = false;
+                trailing_delimiter &= ends_with_empty_field(&top_row);
+                has_multiline_fields |= has_line_break(&top_row);
+                let str_record = decode_record(top_row.clone(), utf8_handling);
                 n_records += 1;
                 n_bytes += count_bytes(&str_record);
-                infer_record_types(&str_record)
+                infer_record_types(&str_record, &type_options)
+            }
+            None if self.get_strictness() == Strictness::Lenient => {
+                // best effort: there's nothing to infer types or a header row from
+                self.has_header_row = Some(false);
+                self.trailing_delimiter = Some(false);
+                self.has_multiline_fields = Some(false);
+                self.avg_record_len = Some(0);
+                return Ok(());
             }
             None => {
                 return Err(SnifferError::SniffingFailed(
@@ -410,66 +3622,207 @@ impl Sniffer {
         };
         let mut row_types = vec![TypeGuesses::all(); field_count];
 
-        for record in records_iter {
-            let record = record?;
-            for (i, field) in record.iter().enumerate() {
-                let str_field = String::from_utf8_lossy(field).to_string();
-                row_types[i] &= infer_types(&str_field);
-            }
+        // the records sampled past the sample (if any), once it's read
+        let mut random_records: Option<std::vec::IntoIter<ByteRecord>> = None;
+        loop {
+            let record = match random_records {
+                None => match records_iter.next() {
+                    Some(record) => record?,
+                    None => break,
+                },
+                Some(ref mut records) => match records.next() {
+                    Some(record) => record,
+                    None => break,
+                },
+            };
+            trailing_delimiter &= ends_with_empty_field(&record);
+            has_multiline_fields |= has_line_break(&record);
             n_records += 1;
             n_bytes += record.as_slice().len();
-            // break if we pass sample size limits
-            match sample_size {
-                SampleSize::Records(recs) => {
-                    if n_records > recs {
-                        break;
+            let str_record = decode_record(record, utf8_handling);
+            for (i, field) in str_record.iter().enumerate() {
+                let Some(types) = row_types.get_mut(i) else {
+                    // more fields than delimiters were counted (e.g. when lenient)
+                    break;
+                };
+                *types &= infer_types(field, &type_options);
+                if type_options.is_null(field) {
+                    if !null_tokens[i].iter().any(|token| token == field) {
+                        null_tokens[i].push(field.to_string());
                     }
+                    continue;
                 }
-                SampleSize::Bytes(bytes) => {
-                    if n_bytes > bytes {
-                        break;
+                if types.contains(TypeGuesses::BOOLEAN) {
+                    let value = field.to_lowercase();
+                    let values = &mut boolean_values[i];
+                    if !values.contains(&value) {
+                        values.push(value);
+                        if values.len() > 2 {
+                            types.remove(TypeGuesses::BOOLEAN);
+                        }
                     }
                 }
-                SampleSize::All => {}
+            }
+            // stop (or sample the rest of the file) if we pass sample size limits
+            let is_done = match sample_size {
+                SampleSize::Records(recs) => n_records > recs,
+                SampleSize::Bytes(bytes)
+                | SampleSize::Adaptive {
+                    max_bytes: bytes, ..
+                } => n_bytes > bytes,
+                SampleSize::Duration(_) => n_bytes > MAX_TIMED_SAMPLE_BYTES,
+                SampleSize::All | SampleSize::Percent(_) => false,
+            };
+            if is_done && random_records.is_none() {
+                if self.random_samples == 0 && !self.middle_and_tail {
+                    break;
+                }
+                let sample_len = records_iter.reader().position().byte();
+                let reader = records_iter.reader_mut().get_mut().get_mut();
+                random_records = Some(
+                    self.sample_later_records(reader, sample_len, field_count)?
+                        .into_iter(),
+                );
+            }
+        }
+        for (types, values) in row_types.iter_mut().zip(&boolean_values) {
+            if !type_options.is_boolean_column(values) {
+                types.remove(TypeGuesses::BOOLEAN);
+            }
+        }
+        self.null_tokens = null_tokens;
+        trailing_delimiter &= ends_with_empty_field(csv_reader.byte_headers()?);
+        has_multiline_fields |= has_line_break(csv_reader.byte_headers()?);
+        self.trailing_delimiter = Some(trailing_delimiter);
+        self.has_multiline_fields = Some(has_multiline_fields);
+        let drop_trailing_field = self.drop_trailing_field && trailing_delimiter;
+
+        if n_records == 1 && has_more_rows && known_header.is_none() {
+            // there's a top row and a single other row: it's a header row if it's all text, while
+            // the other row isn't
+            let top_row_types = get_best_types(&infer_record_types(
+                &decode_record(top_row.clone(), utf8_handling),
+                &type_options,
+            ));
+            let row_types = get_best_types(&header_row_types);
+            let n_typed = row_types
+                .iter()
+                .filter(|&&ty| ty != Type::Text && ty != Type::NULL)
+                .count();
+            if n_typed > 0 && top_row_types.iter().all(|&ty| ty == Type::Text) {
+                self.has_header_row = Some(true);
+                self.confidence.header = 0.5 + 0.5 * n_typed as f64 / row_types.len() as f64;
+                self.fields = decode_record(top_row.clone(), utf8_handling)
+                    .iter()
+                    .map(String::from)
+                    .collect();
+                self.fields_raw = top_row.iter().map(<[u8]>::to_vec).collect();
+                self.types = row_types;
+                if drop_trailing_field {
+                    self.fields.pop();
+                    self.fields_raw.pop();
+                    self.types.pop();
+                }
+                self.avg_record_len = Some(n_bytes);
+                return Ok(());
             }
         }
         if n_records == 1 {
             // there's only one row in the whole data file (the top row already parsed),
             // so we're going to assume it's a data row, not a header row.
-            self.has_header_row = Some(false);
+            self.has_header_row = Some(known_header.unwrap_or(false));
+            self.confidence.header = if known_header.is_some() { 1.0 } else { 0.5 };
+            if known_header == Some(true) {
+                let headers = csv_reader.byte_headers()?.clone();
+                self.fields = decode_record(headers.clone(), utf8_handling)
+                    .iter()
+                    .map(String::from)
+                    .collect();
+                self.fields_raw = headers.iter().map(<[u8]>::to_vec).collect();
+            }
             self.types = get_best_types(&header_row_types);
+            if drop_trailing_field {
+                self.fields.pop();
+                self.fields_raw.pop();
+                self.types.pop();
+            }
             self.avg_record_len = Some(n_bytes);
             return Ok(());
         }
 
-        if header_row_types
+        // Header confidence: starting from a coin-flip, the more columns where the top row's types
+        // differ from (or agree with) the data types, the more sure we are there is (or isn't) a
+        // header row. Text columns don't tell us anything either way.
+        let n_differing = header_row_types
             .iter()
             .zip(&row_types)
-            .any(|(header, data)| !data.allows(*header))
-        {
+            .filter(|(header, data)| !data.allows(**header))
+            .count();
+        let n_agreeing = header_row_types
+            .iter()
+            .zip(&row_types)
+            .filter(|(header, data)| data.allows(**header) && data.best() != Type::Text)
+            .count();
+        let n_columns = header_row_types.len().max(1) as f64;
+        if known_header.is_some() {
+            self.confidence.header = 1.0;
+        } else if n_differing > 0 {
+            self.confidence.header = 0.5 + 0.5 * n_differing as f64 / n_columns;
+        } else {
+            self.confidence.header = 0.5 + 0.5 * n_agreeing as f64 / n_columns;
+        }
+
+        if known_header.unwrap_or(n_differing > 0) {
             self.has_header_row = Some(true);
             // get field names in header
-            for field in csv_reader.byte_headers()? {
-                self.fields.push(String::from_utf8_lossy(field).to_string());
-            }
+            let headers = csv_reader.byte_headers()?.clone();
+            self.fields = decode_record(headers.clone(), utf8_handling)
+                .iter()
+                .map(String::from)
+                .collect();
+            self.fields_raw = headers.iter().map(<[u8]>::to_vec).collect();
         } else {
             self.has_header_row = Some(false);
         }
 
+        // the first record the csv reader returns is a data row either way (the top row being the
+        // csv reader's header row), so it counts towards the types as well
+        for (types, first_record_types) in row_types.iter_mut().zip(&header_row_types) {
+            *types &= *first_record_types;
+        }
         self.types = get_best_types(&row_types);
+        if drop_trailing_field {
+            self.fields.pop();
+            self.fields_raw.pop();
+            self.types.pop();
+        }
         self.avg_record_len = Some(n_bytes / n_records);
         Ok(())
     }
 
+    // Creates a CSV reader for the data rows (skipping the preamble, and stopping at the footer).
     fn create_csv_reader<'a, R: Read + Seek>(
         &self,
         mut reader: &'a mut R,
-    ) -> Result<Reader<&'a mut R>> {
+    ) -> Result<Reader<io::Take<&'a mut R>>> {
+        let data_start = self.seek_data_start(&mut reader)?;
+        let data_len = self.footer_start.map_or(u64::MAX, |footer_start| {
+            footer_start.saturating_sub(data_start)
+        });
+        Ok(self.csv_reader_builder().from_reader(reader.take(data_len)))
+    }
+
+    // Seeks to the start of the data (past the preamble rows), returning its offset.
+    fn seek_data_start<R: Read + Seek>(&self, mut reader: &mut R) -> Result<u64> {
         reader.seek(SeekFrom::Start(0))?;
-        if let Some(num_preamble_rows) = self.num_preamble_rows {
-            snip_preamble(&mut reader, num_preamble_rows)?;
-        }
+        let num_preamble_rows =
+            self.num_comment_preamble_rows.unwrap_or(0) + self.num_preamble_rows.unwrap_or(0);
+        snip_preamble(&mut reader, num_preamble_rows, self.get_record_end())?;
+        Ok(reader.stream_position()?)
+    }
 
+    // A builder of csv readers of the dialect inferred so far.
+    fn csv_reader_builder(&self) -> csv::ReaderBuilder {
         let mut builder = csv::ReaderBuilder::new();
         if let Some(delim) = self.delimiter {
             builder.delimiter(delim);
@@ -487,23 +3840,430 @@ impl Sniffer {
             }
             _ => {}
         }
+        if let Some(escape) = self.escape.clone() {
+            builder.escape(escape.into());
+        }
+        if let Some(double_quote) = self.double_quote {
+            builder.double_quote(double_quote);
+        }
+        if let Some(comment) = self.comment.clone() {
+            builder.comment(comment.into());
+        }
+        if let Some(terminator) = self.terminator {
+            builder.terminator(terminator.into());
+        }
+        if let Some(trim) = self.trim {
+            builder.trim(trim.into());
+        }
         if let Some(flexible) = self.flexible {
-            builder.flexible(flexible);
+            // lenient sniffing reads records of unexpected lengths, rather than failing on them
+            builder.flexible(flexible || self.get_strictness() == Strictness::Lenient);
+        }
+        builder
+    }
+
+    // Takes the records past the first `sample_len` bytes of the data: those at `random_samples`
+    // places, one at a pseudo-random offset within each of as many parts of the rest of the data,
+    // and those in the middle and at the end of it (as many bytes of each as the sample), if
+    // they're sampled too. Only the whole records following the line break after each offset are
+    // taken, and only those with `field_count` fields, as the line break may be within a
+    // multi-line field.
+    fn sample_later_records<R: Read + Seek>(
+        &self,
+        reader: &mut R,
+        sample_len: u64,
+        field_count: usize,
+    ) -> Result<Vec<ByteRecord>> {
+        let record_end = self.get_record_end();
+        let rest_start = self.seek_data_start(reader)? + sample_len;
+        let data_end = match self.footer_start {
+            Some(footer_start) => footer_start,
+            None => reader.seek(SeekFrom::End(0))?,
+        };
+        let mut records = vec![];
+        if rest_start >= data_end {
+            return Ok(records);
+        }
+        let rest_len = data_end - rest_start;
+        // the offsets of the places to read, and how many bytes to read at each
+        let mut places = vec![];
+        if self.random_samples > 0 {
+            let n_places = (self.random_samples as u64).min(rest_len);
+            let part_len = rest_len / n_places;
+            let mut state = data_end | 1;
+            for part in 0..n_places {
+                let offset = rest_start + part * part_len + next_random(&mut state) % part_len;
+                places.push((offset, RANDOM_SAMPLE_BYTES));
+            }
+        }
+        if self.middle_and_tail {
+            if rest_len <= 2 * sample_len {
+                places.push((rest_start, rest_len));
+            } else {
+                places.push((rest_start + (rest_len - sample_len) / 2, sample_len));
+                places.push((data_end - sample_len, sample_len));
+            }
+        }
+        let mut window = vec![];
+        for (offset, len) in places {
+            // from the byte before the offset, so that a record starting at it is taken
+            let start = offset.saturating_sub(1);
+            reader.seek(SeekFrom::Start(start))?;
+            window.clear();
+            reader
+                .by_ref()
+                .take((len + 1).min(data_end - start))
+                .read_to_end(&mut window)?;
+            let Some(first) = memchr::memchr(record_end, &window) else {
+                continue;
+            };
+            let last = if start + window.len() as u64 == data_end {
+                window.len()
+            } else {
+                memchr::memrchr(record_end, &window).map_or(0, |pos| pos + 1)
+            };
+            if last <= first + 1 {
+                continue;
+            }
+            let mut builder = self.csv_reader_builder();
+            builder.has_headers(false).flexible(true);
+            for record in builder.from_reader(&window[first + 1..last]).byte_records() {
+                // a line break within a quoted field misaligns the rest of the window
+                let Ok(record) = record else {
+                    break;
+                };
+                if record.len() == field_count {
+                    records.push(record);
+                }
+            }
+        }
+        Ok(records)
+    }
+}
+
+// Splits a line into its raw fields, returning each field's contents (without the enclosing quotes,
+// but with escapes left as-is) and whether or not it was quoted.
+fn split_fields(line: &[u8], delim: u8, quote: u8, escape: Option<u8>) -> Vec<(&[u8], bool)> {
+    let mut fields = vec![];
+    let mut i = 0;
+    loop {
+        if line.get(i) == Some(&quote) {
+            let start = i + 1;
+            i = start;
+            while i < line.len() {
+                if Some(line[i]) == escape {
+                    i += 1;
+                } else if line[i] == quote {
+                    if line.get(i + 1) != Some(&quote) {
+                        break;
+                    }
+                    // doubled quote
+                    i += 1;
+                }
+                i += 1;
+            }
+            fields.push((&line[start..i.min(line.len())], true));
+            // skip anything between the closing quote and the next delimiter
+            while i < line.len() && line[i] != delim {
+                i += 1;
+            }
+        } else {
+            let start = i;
+            while i < line.len() && line[i] != delim {
+                i += 1;
+            }
+            fields.push((&line[start..i], false));
+        }
+        if i >= line.len() {
+            break;
+        }
+        // skip the delimiter
+        i += 1;
+        if i == line.len() {
+            // line ends with a delimiter, so there's one more (empty) field
+            fields.push((&line[i..], false));
+            break;
+        }
+    }
+    fields
+}
+
+// Advances a xorshift generator of pseudo-random numbers (whose state mustn't be zero).
+pub(crate) fn next_random(state: &mut u64) -> u64 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    *state
+}
+
+// The size of the sample of the reader which can be sniffed within `budget`, estimated from the
+// time sniffing the first `TIMING_SAMPLE_BYTES` (with the options of `sniffer`, e.g. its custom
+// type detectors) takes. Only part of the rest of the budget is used, as the file may be slower to
+// sniff further on.
+fn timed_sample_size<R: Read + Seek>(
+    sniffer: &Sniffer,
+    reader: &mut R,
+    budget: Duration,
+) -> Result<SampleSize> {
+    let start = Instant::now();
+    let timing = sniffer
+        .clone()
+        .sample_size(SampleSize::Bytes(TIMING_SAMPLE_BYTES))
+        .sniff_sample(&mut *reader);
+    let elapsed = start.elapsed();
+    reader.seek(SeekFrom::Start(0))?;
+    let n_timed = timing.map_or(TIMING_SAMPLE_BYTES as u64, |metadata| {
+        metadata.sampled_bytes
+    });
+    let rate = n_timed.max(1) as f64 / elapsed.as_secs_f64().max(1e-6);
+    let n_bytes = rate * budget.saturating_sub(elapsed).as_secs_f64() * TIMED_BUDGET_FRACTION;
+    Ok(SampleSize::Bytes(
+        (n_bytes as usize).max(MIN_TIMED_SAMPLE_BYTES),
+    ))
+}
+
+// The size of the sample of the reader which is sniffed (with the options of `sniffer`)
+// confidently, doubling from `ADAPTIVE_START_BYTES` until every confidence in the dialect and the
+// types is at least `min_confidence`, the sample covers the whole file, or it reaches `max_bytes`.
+// A sample which fails to be sniffed (e.g. too short to find the dialect in) isn't confident.
+fn adaptive_sample_size<R: Read + Seek>(
+    sniffer: &Sniffer,
+    reader: &mut R,
+    min_confidence: f64,
+    max_bytes: usize,
+) -> Result<SampleSize> {
+    let len = reader.seek(SeekFrom::End(0))?;
+    reader.seek(SeekFrom::Start(0))?;
+    let mut n_bytes = ADAPTIVE_START_BYTES.min(max_bytes);
+    while n_bytes < max_bytes && (n_bytes as u64) < len {
+        let pilot = sniffer
+            .clone()
+            .sample_size(SampleSize::Bytes(n_bytes))
+            .sniff_sample(&mut *reader);
+        reader.seek(SeekFrom::Start(0))?;
+        if let Ok(metadata) = pilot {
+            let confidence = metadata.confidence;
+            if [
+                confidence.delimiter,
+                confidence.quote,
+                confidence.header,
+                confidence.flexible,
+            ]
+            .iter()
+            .chain(&metadata.type_confidences)
+            .all(|&confidence| confidence >= min_confidence)
+            {
+                break;
+            }
+        }
+        n_bytes = n_bytes.saturating_mul(2).min(max_bytes);
+    }
+    Ok(SampleSize::Bytes(n_bytes))
+}
+
+// The metadata of a file whose first `n_lines` lines (`n_bytes` bytes) were skipped, as that of
+// the whole file: the skipped lines are preamble rows, and invalid UTF-8 is located from the start.
+fn with_skipped_lines(mut metadata: Metadata, n_lines: usize, n_bytes: u64) -> Metadata {
+    metadata.dialect.header.num_preamble_rows += n_lines;
+    if let Some(ref mut invalid_utf8) = metadata.invalid_utf8 {
+        for location in &mut invalid_utf8.locations {
+            location.line += n_lines;
+            location.offset += n_bytes;
+        }
+    }
+    metadata
+}
+
+// A field name without zero-width and control characters, and with plain spaces for no-break ones.
+fn clean_field_name(name: &str) -> String {
+    name.chars()
+        .filter(|&chr| {
+            !chr.is_control() && !matches!(chr, '\u{200B}'..='\u{200D}' | '\u{2060}' | '\u{FEFF}')
+        })
+        .map(|chr| match chr {
+            '\u{A0}' | '\u{202F}' => ' ',
+            _ => chr,
+        })
+        .collect()
+}
+
+// The narrowest type fitting the values of columns of both types: integers are widened to
+// `Signed` or `Float`, and other mixed types to `Text`.
+fn merge_type(a: Type, b: Type) -> Type {
+    match (a, b) {
+        _ if a == b => a,
+        (Type::NULL, ty) | (ty, Type::NULL) => ty,
+        (Type::Unsigned | Type::Signed, Type::Unsigned | Type::Signed) => Type::Signed,
+        (
+            Type::Unsigned | Type::Signed | Type::Float,
+            Type::Unsigned | Type::Signed | Type::Float,
+        ) => Type::Float,
+        _ => Type::Text,
+    }
+}
+
+// The integer width holding every value of columns of both widths.
+fn wider_integer(a: IntegerWidth, b: IntegerWidth) -> IntegerWidth {
+    let rank = |width| match width {
+        IntegerWidth::I32 => 0,
+        IntegerWidth::I64 => 1,
+        IntegerWidth::U64 => 2,
+        IntegerWidth::Big => 3,
+    };
+    if rank(a) >= rank(b) {
+        a
+    } else {
+        b
+    }
+}
+
+// Finds the number of preamble rows from a chain's Viterbi path: the number of rows during which
+// the state fluctuated before getting to the final state. If provided, `line_indices` maps the
+// observations to the indices of the sampled lines (the preamble then ends at the line of the first
+// data observation, as blank lines skipped by the chains may be part of the preamble).
+fn count_preamble_rows(
+    path: &[(usize, VIteration)],
+    final_state: usize,
+    line_indices: &[usize],
+) -> usize {
+    let mut num_preamble_rows = 0;
+    // since path has an extra state as the beginning, skip one
+    for &(state, _) in path.iter().skip(2) {
+        if state == final_state {
+            break;
+        }
+        num_preamble_rows += 1;
+    }
+    if num_preamble_rows > 0 {
+        num_preamble_rows += 1;
+        num_preamble_rows = line_indices
+            .get(num_preamble_rows)
+            .copied()
+            .unwrap_or(num_preamble_rows);
+    }
+    num_preamble_rows
+}
+
+// Returns the bytes of a line outside of quoted text (dropping the quotes as well), so delimiters
+// within quoted fields aren't counted. A quote only starts quoted text at the start of a token
+// (e.g. not the apostrophe in `it's`) if the line has a balanced number of them, and the quoted
+// text only ends at a closing quote at the end of a token (doubled quotes are skipped).
+fn strip_quoted(line: &[u8], quotes: &[u8]) -> Vec<u8> {
+    let mut stripped = Vec::with_capacity(line.len());
+    let mut i = 0;
+    while i < line.len() {
+        let chr = line[i];
+        let is_opening = quotes.contains(&chr)
+            && (i == 0 || !line[i - 1].is_ascii_alphanumeric())
+            && bytecount::count(line, chr) % 2 == 0;
+        if is_opening {
+            let mut end = i + 1;
+            while end < line.len() {
+                if line[end] == chr {
+                    if line.get(end + 1) != Some(&chr) {
+                        break;
+                    }
+                    // doubled quote
+                    end += 1;
+                }
+                end += 1;
+            }
+            let is_closed = end < line.len()
+                && line
+                    .get(end + 1)
+                    .map_or(true, |next| !next.is_ascii_alphanumeric());
+            if is_closed {
+                i = end + 1;
+                continue;
+            }
         }
+        stripped.push(chr);
+        i += 1;
+    }
+    stripped
+}
 
-        Ok(builder.from_reader(reader))
+// Ranks how plausible a character is as a delimiter, from its punctuation class: the common
+// delimiters rank highest, and punctuation which is common within values (in numbers, dates,
+// times, e-mail addresses, etc.) ranks lowest.
+fn punctuation_class(chr: u8) -> usize {
+    match chr {
+        b',' | b'\t' | b';' | b'|' => 3,
+        b'.' | b'-' | b'/' | b':' | b'@' | b'_' | b'+' | b'%' | b'$' | b'\'' | b'"' => 1,
+        _ => 2,
     }
 }
 
+// Counts the occurrences of the most common delimiters in a line, to compare the shape of lines
+// before the delimiter is known.
+fn common_delimiter_counts(line: &[u8]) -> [usize; 4] {
+    [b',', b';', b'\t', b'|'].map(|delim| bytecount::count(line, delim))
+}
+
+// The shape of a line: the occurrences of the delimiter (or, if it isn't known yet, of each of the
+// most common delimiters), along with the number of empty fields they split the line into (so that,
+// say, a totals row with empty fields stands out from the rows above it).
+fn line_shape(line: &[u8], delimiter: Option<u8>) -> Vec<(usize, usize)> {
+    let delims = match delimiter {
+        Some(delim) => vec![delim],
+        None => vec![b',', b';', b'\t', b'|'],
+    };
+    delims
+        .into_iter()
+        .map(|delim| {
+            let n_empty = line
+                .split(|&chr| chr == delim)
+                .filter(|field| field.iter().all(u8::is_ascii_whitespace))
+                .count();
+            (bytecount::count(line, delim), n_empty)
+        })
+        .collect()
+}
+
+// Whether every run of `pad` bytes in the lines is next to a `delim` byte (or at the start or end of
+// the line), i.e. `pad` is only ever used to pad fields separated by `delim`.
+fn is_padding_around(lines: &[String], pad: u8, delim: u8) -> bool {
+    lines.iter().all(|line| {
+        let line = line.as_bytes();
+        let mut i = 0;
+        while i < line.len() {
+            if line[i] != pad {
+                i += 1;
+                continue;
+            }
+            let start = i;
+            while i < line.len() && line[i] == pad {
+                i += 1;
+            }
+            if start > 0 && i < line.len() && line[start - 1] != delim && line[i] != delim {
+                return false;
+            }
+        }
+        true
+    })
+}
+
 fn quote_count<R: Read>(
     sample_iter: &mut SampleIter<R>,
     character: char,
+    quote_candidates: &[u8],
     delim: Option<u8>,
+    delim_candidates: Option<&[u8]>,
 ) -> Result<Option<(usize, u8)>> {
+    let character = regex::escape(&character.to_string());
     let pattern = if let Some(delim) = delim {
+        let delim = regex::escape(&char::from(delim).to_string());
         format!(r#"{character}\s*?{delim}\s*{character}"#)
     } else {
-        format!(r#"{character}\s*?(?P<delim>[^\w\n'"`])\s*{character}"#)
+        // the delimiter can't be a word character, any of the candidate quote characters, or a
+        // curly quote (e.g. in "\u{201C}Hi\u{201D}, she said")
+        let not_quotes = quote_candidates
+            .iter()
+            .map(|&chr| char::from(chr))
+            .chain(CURLY_QUOTES)
+            .map(|chr| regex::escape(&chr.to_string()))
+            .collect::<String>();
+        format!(r#"{character}\s*?(?P<delim>[^\w\n{not_quotes}])\s*{character}"#)
     };
     // safety: unwrap is safe as we know the pattern is valid
     let re = Regex::new(&pattern).unwrap();
@@ -513,10 +4273,13 @@ fn quote_count<R: Read>(
     for line in sample_iter {
         let line = line?;
         for cap in re.captures_iter(&line) {
-            count += 1;
             // if we already know delimiter, we don't need to count
             if delim.is_some() {
-            } else {
+                count += 1;
+            } else if delim_candidates.map_or(true, |candidates| {
+                cap["delim"].len() == 1 && candidates.contains(&cap["delim"].as_bytes()[0])
+            }) {
+                count += 1;
                 *delim_count_map.entry(cap["delim"].to_string()).or_insert(0) += 1;
             }
         }
@@ -558,6 +4321,71 @@ fn quote_count<R: Read>(
     Ok(Some((count, delim)))
 }
 
+// Counts the backslash-escaped quotes (\") and the doubled quotes ("") in a line. Doubled quotes
+// that make up an empty field (e.g. ,"",) are not counted, since they aren't escapes.
+fn escape_counts(line: &[u8], quote: u8, delim: Option<u8>) -> (usize, usize) {
+    let is_boundary = |chr: Option<&u8>| match chr {
+        None => true,
+        Some(&chr) => Some(chr) == delim,
+    };
+    let (mut n_backslash, mut n_doubled) = (0, 0);
+    let mut i = 0;
+    while i + 1 < line.len() {
+        if line[i] == b'\\' && line[i + 1] == quote {
+            n_backslash += 1;
+            // skip past the escaped quote, so it isn't mistaken for the start of a doubled quote
+            i += 2;
+        } else if line[i] == quote
+            && line[i + 1] == quote
+            && !(is_boundary(i.checked_sub(1).and_then(|j| line.get(j)))
+                && is_boundary(line.get(i + 2)))
+        {
+            n_doubled += 1;
+            // skip past the doubled quote, so runs of quotes aren't double-counted
+            i += 2;
+        } else {
+            i += 1;
+        }
+    }
+    (n_backslash, n_doubled)
+}
+
+// Whether the last field of a (multi-field) record is empty, i.e. the record ends with a delimiter.
+fn ends_with_empty_field(record: &ByteRecord) -> bool {
+    record.len() > 1 && record.get(record.len() - 1) == Some(b"")
+}
+
+// Decodes a record read by the csv reader, replacing invalid UTF-8 sequences (e.g. Latin-1
+// characters, or a stray 0x92 smart quote) and noting that the file isn't all UTF-8: records may
+// be beyond the lines of the sample (with exhaustive type inference).
+fn decode_record(record: ByteRecord, utf8_handling: Utf8Handling) -> StringRecord {
+    if !record.as_slice().is_ascii() {
+        IS_ASCII.with(|flag| {
+            *flag.borrow_mut() = false;
+        });
+    }
+    if record
+        .iter()
+        .all(|field| simdutf8::basic::from_utf8(field).is_ok())
+    {
+        return StringRecord::from_byte_record_lossy(record);
+    }
+    IS_UTF8.with(|flag| {
+        *flag.borrow_mut() = false;
+    });
+    match utf8_handling {
+        Utf8Handling::Raw => record
+            .iter()
+            .map(|field| match std::str::from_utf8(field) {
+                Ok(field) => Cow::Borrowed(field),
+                Err(_) => Cow::Owned(field.iter().map(|&b| char::from(b)).collect()),
+            })
+            .collect::<Vec<Cow<str>>>()
+            .into(),
+        Utf8Handling::Strict | Utf8Handling::Lossy => StringRecord::from_byte_record_lossy(record),
+    }
+}
+
 fn count_bytes(record: &StringRecord) -> usize {
     record.iter().fold(0, |acc, field| acc + field.len())
 }
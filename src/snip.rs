@@ -1,40 +1,70 @@
-use std::io::{Read, Seek, SeekFrom};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
 
 use crate::error::Result;
 
-pub fn preamble_skipcount<R: Read>(reader: &mut R, n_preamble_rows: usize) -> Result<usize> {
-    if n_preamble_rows == 0 {
-        return Ok(0);
-    }
+pub fn preamble_skipcount<R: Read>(
+    reader: &mut R,
+    n_preamble_rows: usize,
+    record_end: u8,
+) -> Result<usize> {
     let mut skipcount = 0;
-    loop {
-        let cap = 1 << 12;
-        let mut buffer = vec![0; cap];
-        unsafe {
-            buffer.set_len(cap);
-        }
+    let mut n_left = n_preamble_rows;
+    let mut buffer = vec![0; 1 << 12];
+    // the preamble may span several buffers
+    while n_left > 0 {
         let n_read = reader.read(&mut buffer)?;
-        let mut crlf_pos = 0;
-        let mut found = true;
-        for _ in 0..n_preamble_rows {
-            if let Some(pos) = memchr::memchr(b'\n', &buffer[crlf_pos..]) {
-                crlf_pos += pos + 1;
-            } else {
-                found = false;
-                break;
+        if n_read == 0 {
+            // the file ends within the preamble
+            break;
+        }
+        let mut pos = 0;
+        while n_left > 0 {
+            match memchr::memchr(record_end, &buffer[pos..n_read]) {
+                Some(found) => {
+                    pos += found + 1;
+                    n_left -= 1;
+                }
+                None => {
+                    pos = n_read;
+                    break;
+                }
             }
         }
-        if found {
-            skipcount += crlf_pos;
+        skipcount += pos;
+    }
+    Ok(skipcount)
+}
+
+/// Counts the lines at the start of the reader which start with one of the provided prefixes (such
+/// as `# Generated: ...`).
+pub fn comment_preamble_count<R: Read>(
+    reader: R,
+    prefixes: &[&str],
+    record_end: u8,
+) -> Result<usize> {
+    let mut buf_reader = BufReader::new(reader);
+    let mut line = vec![];
+    let mut count = 0;
+    loop {
+        line.clear();
+        if buf_reader.read_until(record_end, &mut line)? == 0
+            || !prefixes
+                .iter()
+                .any(|prefix| line.starts_with(prefix.as_bytes()))
+        {
             break;
         }
-        skipcount += cap.min(n_read);
+        count += 1;
     }
-    Ok(skipcount)
+    Ok(count)
 }
 
-pub fn snip_preamble<R: Read + Seek>(mut reader: R, n_preamble_rows: usize) -> Result<()> {
-    let seek_point = preamble_skipcount(&mut reader, n_preamble_rows)?;
+pub fn snip_preamble<R: Read + Seek>(
+    mut reader: R,
+    n_preamble_rows: usize,
+    record_end: u8,
+) -> Result<()> {
+    let seek_point = preamble_skipcount(&mut reader, n_preamble_rows, record_end)?;
     reader.seek(SeekFrom::Start(seek_point as u64))?;
     Ok(())
 }
@@ -0,0 +1,197 @@
+/*!
+Reading of the CSV files in ZIP archives.
+*/
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use flate2::read::DeflateDecoder;
+
+// Signatures of the records of a ZIP archive.
+const LOCAL_HEADER_SIGNATURE: u32 = 0x0403_4b50;
+const CENTRAL_HEADER_SIGNATURE: u32 = 0x0201_4b50;
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x0605_4b50;
+
+// Size of the end of central directory record (without its comment), and the longest comment.
+const END_OF_CENTRAL_DIRECTORY_LEN: usize = 22;
+const MAX_COMMENT_LEN: usize = 0xFFFF;
+
+// Extensions (compared case-insensitively) of the entries sniffed when no entry is named.
+const DELIMITED_EXTENSIONS: [&str; 4] = [".csv", ".tsv", ".tab", ".psv"];
+
+/// A reader over the (decompressed) contents of an entry of a ZIP archive (with the `zip`
+/// feature). Stored and deflated entries are supported; their checksums aren't verified.
+#[derive(Debug)]
+pub struct ZipEntryReader(EntryData);
+
+#[derive(Debug)]
+enum EntryData {
+    Stored(io::Take<File>),
+    Deflated(DeflateDecoder<io::Take<File>>),
+}
+
+impl Read for ZipEntryReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.0 {
+            EntryData::Stored(ref mut data) => data.read(buf),
+            EntryData::Deflated(ref mut data) => data.read(buf),
+        }
+    }
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("invalid ZIP archive: {msg}"),
+    )
+}
+
+fn u16_at(bytes: &[u8], pos: usize) -> u16 {
+    u16::from_le_bytes([bytes[pos], bytes[pos + 1]])
+}
+
+fn u32_at(bytes: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes([bytes[pos], bytes[pos + 1], bytes[pos + 2], bytes[pos + 3]])
+}
+
+// An entry of the central directory.
+struct Entry {
+    name: String,
+    flags: u16,
+    method: u16,
+    compressed_size: u64,
+    local_header_offset: u64,
+}
+
+// The entries listed in the central directory of the archive.
+fn read_entries(file: &mut File) -> io::Result<Vec<Entry>> {
+    let file_len = file.seek(SeekFrom::End(0))?;
+    let tail_len = file_len.min((END_OF_CENTRAL_DIRECTORY_LEN + MAX_COMMENT_LEN) as u64);
+    file.seek(SeekFrom::Start(file_len - tail_len))?;
+    let mut tail = vec![];
+    file.read_to_end(&mut tail)?;
+    let end = (0..=tail.len().saturating_sub(END_OF_CENTRAL_DIRECTORY_LEN))
+        .rev()
+        .find(|&pos| {
+            tail.len() >= END_OF_CENTRAL_DIRECTORY_LEN
+                && u32_at(&tail, pos) == END_OF_CENTRAL_DIRECTORY_SIGNATURE
+        })
+        .ok_or_else(|| invalid_data("no end of central directory record"))?;
+    let n_entries = u16_at(&tail, end + 10);
+    let directory_len = u32_at(&tail, end + 12);
+    let directory_offset = u32_at(&tail, end + 16);
+    if n_entries == 0xFFFF || directory_len == u32::MAX || directory_offset == u32::MAX {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "ZIP64 archives are not supported",
+        ));
+    }
+
+    file.seek(SeekFrom::Start(u64::from(directory_offset)))?;
+    let mut directory = vec![];
+    file.take(u64::from(directory_len))
+        .read_to_end(&mut directory)?;
+    let mut entries = vec![];
+    let mut pos = 0;
+    for _ in 0..n_entries {
+        if directory.len() < pos + 46 || u32_at(&directory, pos) != CENTRAL_HEADER_SIGNATURE {
+            return Err(invalid_data("truncated central directory"));
+        }
+        let name_len = usize::from(u16_at(&directory, pos + 28));
+        let extra_len = usize::from(u16_at(&directory, pos + 30));
+        let comment_len = usize::from(u16_at(&directory, pos + 32));
+        let name = directory
+            .get(pos + 46..pos + 46 + name_len)
+            .ok_or_else(|| invalid_data("truncated central directory"))?;
+        entries.push(Entry {
+            name: String::from_utf8_lossy(name).into_owned(),
+            flags: u16_at(&directory, pos + 8),
+            method: u16_at(&directory, pos + 10),
+            compressed_size: u64::from(u32_at(&directory, pos + 20)),
+            local_header_offset: u64::from(u32_at(&directory, pos + 42)),
+        });
+        pos += 46 + name_len + extra_len + comment_len;
+    }
+    Ok(entries)
+}
+
+/// Open the entry of the ZIP archive at `path` with the provided name, or if `name` is `None`, the
+/// first entry named like a delimited file (e.g. `*.csv` or `*.tsv`). Returns the name of the
+/// entry, and a reader over its contents.
+pub(crate) fn open_entry(path: &Path, name: Option<&str>) -> io::Result<(String, ZipEntryReader)> {
+    let mut file = File::open(path)?;
+    let entries = read_entries(&mut file)?;
+    let entry = entries
+        .into_iter()
+        .find(|entry| match name {
+            Some(name) => entry.name == name,
+            None => {
+                let lowercase = entry.name.to_lowercase();
+                !lowercase.starts_with("__macosx/")
+                    && DELIMITED_EXTENSIONS
+                        .iter()
+                        .any(|extension| lowercase.ends_with(extension))
+            }
+        })
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                match name {
+                    Some(name) => format!("no entry named {name} in the ZIP archive"),
+                    None => "no CSV entry in the ZIP archive".to_string(),
+                },
+            )
+        })?;
+    if entry.flags & 1 != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("ZIP entry {} is encrypted", entry.name),
+        ));
+    }
+
+    let mut header = [0; 30];
+    file.seek(SeekFrom::Start(entry.local_header_offset))?;
+    file.read_exact(&mut header)?;
+    if u32_at(&header, 0) != LOCAL_HEADER_SIGNATURE {
+        return Err(invalid_data("bad local header"));
+    }
+    let data_offset = entry.local_header_offset
+        + 30
+        + u64::from(u16_at(&header, 26))
+        + u64::from(u16_at(&header, 28));
+    file.seek(SeekFrom::Start(data_offset))?;
+    let data = file.take(entry.compressed_size);
+    let data = match entry.method {
+        0 => EntryData::Stored(data),
+        8 => EntryData::Deflated(DeflateDecoder::new(data)),
+        method => {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!(
+                    "ZIP entry {} uses an unsupported compression method ({method})",
+                    entry.name
+                ),
+            ))
+        }
+    };
+    Ok((entry.name, ZipEntryReader(data)))
+}
+
+/// A reader over the contents of the entry of the ZIP archive at `path` with the provided name,
+/// with its first `n_skip` lines skipped.
+pub(crate) fn open_entry_skipped(
+    path: &Path,
+    name: &str,
+    n_skip: usize,
+    record_end: u8,
+) -> io::Result<BufReader<ZipEntryReader>> {
+    let mut reader = BufReader::new(open_entry(path, Some(name))?.1);
+    let mut line = vec![];
+    for _ in 0..n_skip {
+        line.clear();
+        if reader.read_until(record_end, &mut line)? == 0 {
+            break;
+        }
+    }
+    Ok(reader)
+}
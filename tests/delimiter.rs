@@ -17,36 +17,52 @@ fn test_semicolon() {
         .sniff_path(data_filepath)
         .unwrap();
     assert_eq!(
-        metadata,
-        Metadata {
-            dialect: Dialect {
-                delimiter: b';',
-                header: Header {
-                    has_header_row: true,
-                    num_preamble_rows: 0,
-                },
-                quote: Quote::None,
-                flexible: false,
-                is_utf8: true
+        metadata.dialect,
+        Dialect {
+            delimiter: b';',
+            multibyte_delimiter: None,
+            single_column: false,
+            header: Header {
+                has_header_row: true,
+                num_preamble_rows: 0,
+                num_comment_preamble_rows: 0,
             },
-            avg_record_len: 35,
-            num_fields: 5,
-            fields: vec![
-                "Name".to_string(),
-                "Party".to_string(),
-                "Vote Count".to_string(),
-                "Voting Method".to_string(),
-                "Voting Preinct".to_string()
-            ],
-            types: vec![
-                Type::Text,
-                Type::Text,
-                Type::Unsigned,
-                Type::Text,
-                Type::Text
-            ]
+            quote: Quote::None,
+            quote_style: QuoteStyle::Never,
+            escape: Escape::Disabled,
+            double_quote: true,
+            comment: Comment::Disabled,
+            terminator: Terminator::CRLF,
+            trailing_delimiter: false,
+            embedded_escapes: false,
+            trim: Trim::None,
+            blank_lines: BlankLinePolicy::Skip,
+            flexible: false,
+            is_utf8: true
         }
     );
+    assert_eq!(metadata.avg_record_len, 35);
+    assert_eq!(metadata.num_fields, 5);
+    assert_eq!(
+        metadata.fields,
+        vec![
+            "Name".to_string(),
+            "Party".to_string(),
+            "Vote Count".to_string(),
+            "Voting Method".to_string(),
+            "Voting Preinct".to_string()
+        ]
+    );
+    assert_eq!(
+        metadata.types,
+        vec![
+            Type::Text,
+            Type::Text,
+            Type::Unsigned,
+            Type::Text,
+            Type::Text
+        ]
+    );
 }
 
 #[test]
@@ -60,36 +76,52 @@ fn test_comma() {
         .sniff_path(data_filepath)
         .unwrap();
     assert_eq!(
-        metadata,
-        Metadata {
-            dialect: Dialect {
-                delimiter: b',',
-                header: Header {
-                    has_header_row: true,
-                    num_preamble_rows: 0,
-                },
-                quote: Quote::None,
-                flexible: false,
-                is_utf8: true,
+        metadata.dialect,
+        Dialect {
+            delimiter: b',',
+            multibyte_delimiter: None,
+            single_column: false,
+            header: Header {
+                has_header_row: true,
+                num_preamble_rows: 0,
+                num_comment_preamble_rows: 0,
             },
-            avg_record_len: 30,
-            num_fields: 5,
-            fields: vec![
-                "Month".to_string(),
-                "Door Count".to_string(),
-                "Web Site Visits".to_string(),
-                "Catalog Visits".to_string(),
-                "Overdrive Visits".to_string()
-            ],
-            types: vec![
-                Type::Text,
-                Type::Unsigned,
-                Type::Unsigned,
-                Type::Unsigned,
-                Type::Unsigned
-            ]
+            quote: Quote::None,
+            quote_style: QuoteStyle::Never,
+            escape: Escape::Disabled,
+            double_quote: true,
+            comment: Comment::Disabled,
+            terminator: Terminator::LF,
+            trailing_delimiter: false,
+            embedded_escapes: false,
+            trim: Trim::None,
+            blank_lines: BlankLinePolicy::Skip,
+            flexible: false,
+            is_utf8: true,
         }
     );
+    assert_eq!(metadata.avg_record_len, 30);
+    assert_eq!(metadata.num_fields, 5);
+    assert_eq!(
+        metadata.fields,
+        vec![
+            "Month".to_string(),
+            "Door Count".to_string(),
+            "Web Site Visits".to_string(),
+            "Catalog Visits".to_string(),
+            "Overdrive Visits".to_string()
+        ]
+    );
+    assert_eq!(
+        metadata.types,
+        vec![
+            Type::Text,
+            Type::Unsigned,
+            Type::Unsigned,
+            Type::Unsigned,
+            Type::Unsigned
+        ]
+    );
 }
 
 #[test]
@@ -103,38 +135,54 @@ fn test_boolean() {
         .sniff_path(data_filepath)
         .unwrap();
     assert_eq!(
-        metadata,
-        Metadata {
-            dialect: Dialect {
-                delimiter: b',',
-                header: Header {
-                    has_header_row: true,
-                    num_preamble_rows: 0,
-                },
-                quote: Quote::None,
-                flexible: false,
-                is_utf8: true,
+        metadata.dialect,
+        Dialect {
+            delimiter: b',',
+            multibyte_delimiter: None,
+            single_column: false,
+            header: Header {
+                has_header_row: true,
+                num_preamble_rows: 0,
+                num_comment_preamble_rows: 0,
             },
-            avg_record_len: 31,
-            num_fields: 6,
-            fields: vec![
-                "Month".to_string(),
-                "Door Count".to_string(),
-                "Web Site Visits".to_string(),
-                "Catalog Visits".to_string(),
-                "Overdrive Visits".to_string(),
-                "Vacation".to_string()
-            ],
-            types: vec![
-                Type::Text,
-                Type::Unsigned,
-                Type::Unsigned,
-                Type::Unsigned,
-                Type::Unsigned,
-                Type::Boolean
-            ]
+            quote: Quote::None,
+            quote_style: QuoteStyle::Never,
+            escape: Escape::Disabled,
+            double_quote: true,
+            comment: Comment::Disabled,
+            terminator: Terminator::LF,
+            trailing_delimiter: false,
+            embedded_escapes: false,
+            trim: Trim::None,
+            blank_lines: BlankLinePolicy::Skip,
+            flexible: false,
+            is_utf8: true,
         }
     );
+    assert_eq!(metadata.avg_record_len, 31);
+    assert_eq!(metadata.num_fields, 6);
+    assert_eq!(
+        metadata.fields,
+        vec![
+            "Month".to_string(),
+            "Door Count".to_string(),
+            "Web Site Visits".to_string(),
+            "Catalog Visits".to_string(),
+            "Overdrive Visits".to_string(),
+            "Vacation".to_string()
+        ]
+    );
+    assert_eq!(
+        metadata.types,
+        vec![
+            Type::Text,
+            Type::Unsigned,
+            Type::Unsigned,
+            Type::Unsigned,
+            Type::Unsigned,
+            Type::Boolean
+        ]
+    );
 }
 
 #[test]
@@ -148,40 +196,56 @@ fn test_flexible() {
         .sniff_path(data_filepath)
         .unwrap();
     assert_eq!(
-        metadata,
-        Metadata {
-            dialect: Dialect {
-                delimiter: b',',
-                header: Header {
-                    has_header_row: true,
-                    num_preamble_rows: 0,
-                },
-                quote: Quote::None,
-                flexible: true,
-                is_utf8: true,
+        metadata.dialect,
+        Dialect {
+            delimiter: b',',
+            multibyte_delimiter: None,
+            single_column: false,
+            header: Header {
+                has_header_row: true,
+                num_preamble_rows: 0,
+                num_comment_preamble_rows: 0,
             },
-            avg_record_len: 112,
-            num_fields: 7,
-            fields: vec![
-                "municipality".to_string(),
-                "agency_id".to_string(),
-                "route_id".to_string(),
-                "route_short_name".to_string(),
-                "route_long_name".to_string(),
-                "min_headway_minutes".to_string(),
-                "route_url".to_string()
-            ],
-            types: vec![
-                Type::Text,
-                Type::Unsigned,
-                Type::Unsigned,
-                Type::Text,
-                Type::Text,
-                Type::Unsigned,
-                Type::Text
-            ]
+            quote: Quote::None,
+            quote_style: QuoteStyle::Never,
+            escape: Escape::Disabled,
+            double_quote: true,
+            comment: Comment::Disabled,
+            terminator: Terminator::LF,
+            trailing_delimiter: false,
+            embedded_escapes: false,
+            trim: Trim::None,
+            blank_lines: BlankLinePolicy::Skip,
+            flexible: true,
+            is_utf8: true,
         }
     );
+    assert_eq!(metadata.avg_record_len, 112);
+    assert_eq!(metadata.num_fields, 7);
+    assert_eq!(
+        metadata.fields,
+        vec![
+            "municipality".to_string(),
+            "agency_id".to_string(),
+            "route_id".to_string(),
+            "route_short_name".to_string(),
+            "route_long_name".to_string(),
+            "min_headway_minutes".to_string(),
+            "route_url".to_string()
+        ]
+    );
+    assert_eq!(
+        metadata.types,
+        vec![
+            Type::Text,
+            Type::Unsigned,
+            Type::Unsigned,
+            Type::Text,
+            Type::Text,
+            Type::Unsigned,
+            Type::Url
+        ]
+    );
 }
 
 #[test]
@@ -195,48 +259,64 @@ fn test_utf8_again() {
         .sniff_path(data_filepath)
         .unwrap();
     assert_eq!(
-        metadata,
-        Metadata {
-            dialect: Dialect {
-                delimiter: b';',
-                header: Header {
-                    has_header_row: true,
-                    num_preamble_rows: 0
-                },
-                quote: Quote::None,
-                flexible: false,
-                is_utf8: false
+        metadata.dialect,
+        Dialect {
+            delimiter: b';',
+            multibyte_delimiter: None,
+            single_column: false,
+            header: Header {
+                has_header_row: true,
+                num_preamble_rows: 0,
+                num_comment_preamble_rows: 0
             },
-            avg_record_len: 137,
-            num_fields: 11,
-            fields: vec![
-                "DIA.DESEMB".to_string(),
-                "COD.SUBITEM.NCM".to_string(),
-                "VMLE.DOLAR.BAL.EXP".to_string(),
-                "PESO.LIQ.MERC.BAL.EXP".to_string(),
-                "COD.IMPDR.EXPDR".to_string(),
-                "NOME.IMPDR.EXPDR".to_string(),
-                "PAIS.ORIGEM.DESTINO".to_string(),
-                "UA.LOCAL.DESBQ.EMBQ".to_string(),
-                "NOME.IMPORTADOR.ESTRANGEIRO".to_string(),
-                "NUM.DDE".to_string(),
-                "NUM.RE".to_string()
-            ],
-            types: vec![
-                Type::Text,
-                Type::Unsigned,
-                Type::Float,
-                Type::Float,
-                Type::Text,
-                Type::Text,
-                Type::Text,
-                Type::Text,
-                Type::Text,
-                Type::Text,
-                Type::Text
-            ]
+            quote: Quote::None,
+            quote_style: QuoteStyle::Never,
+            escape: Escape::Disabled,
+            double_quote: true,
+            comment: Comment::Disabled,
+            terminator: Terminator::LF,
+            trailing_delimiter: false,
+            embedded_escapes: false,
+            trim: Trim::None,
+            blank_lines: BlankLinePolicy::Skip,
+            flexible: false,
+            is_utf8: false
         }
     );
+    assert_eq!(metadata.avg_record_len, 137);
+    assert_eq!(metadata.num_fields, 11);
+    assert_eq!(
+        metadata.fields,
+        vec![
+            "DIA.DESEMB".to_string(),
+            "COD.SUBITEM.NCM".to_string(),
+            "VMLE.DOLAR.BAL.EXP".to_string(),
+            "PESO.LIQ.MERC.BAL.EXP".to_string(),
+            "COD.IMPDR.EXPDR".to_string(),
+            "NOME.IMPDR.EXPDR".to_string(),
+            "PAIS.ORIGEM.DESTINO".to_string(),
+            "UA.LOCAL.DESBQ.EMBQ".to_string(),
+            "NOME.IMPORTADOR.ESTRANGEIRO".to_string(),
+            "NUM.DDE".to_string(),
+            "NUM.RE".to_string()
+        ]
+    );
+    assert_eq!(
+        metadata.types,
+        vec![
+            Type::Text,
+            Type::Unsigned,
+            Type::Float,
+            Type::Float,
+            Type::Text,
+            Type::Text,
+            Type::Text,
+            Type::Text,
+            Type::Text,
+            Type::Text,
+            Type::Text
+        ]
+    );
 }
 
 #[test]
@@ -250,82 +330,107 @@ fn test_date_sniffing() {
         .sniff_path(data_filepath)
         .unwrap();
     assert_eq!(
-        metadata,
-        Metadata {
-            dialect: Dialect {
-                delimiter: b',',
-                header: Header {
-                    has_header_row: true,
-                    num_preamble_rows: 0
-                },
-                quote: Quote::None,
-                flexible: false,
-                is_utf8: true
+        metadata.dialect,
+        Dialect {
+            delimiter: b',',
+            multibyte_delimiter: None,
+            single_column: false,
+            header: Header {
+                has_header_row: true,
+                num_preamble_rows: 0,
+                num_comment_preamble_rows: 0
             },
-            avg_record_len: 433,
-            num_fields: 29,
-            fields: vec![
-                "case_enquiry_id".to_string(),
-                "open_dt".to_string(),
-                "target_dt".to_string(),
-                "closed_dt".to_string(),
-                "ontime".to_string(),
-                "case_status".to_string(),
-                "closure_reason".to_string(),
-                "case_title".to_string(),
-                "subject".to_string(),
-                "reason".to_string(),
-                "type".to_string(),
-                "queue".to_string(),
-                "department".to_string(),
-                "submittedphoto".to_string(),
-                "closedphoto".to_string(),
-                "location".to_string(),
-                "fire_district".to_string(),
-                "pwd_district".to_string(),
-                "city_council_district".to_string(),
-                "police_district".to_string(),
-                "neighborhood".to_string(),
-                "neighborhood_services_district".to_string(),
-                "ward".to_string(),
-                "precinct".to_string(),
-                "location_street_name".to_string(),
-                "location_zipcode".to_string(),
-                "latitude".to_string(),
-                "longitude".to_string(),
-                "source".to_string(),
-            ],
-            types: vec![
-                Type::Unsigned,
-                Type::DateTime,
-                Type::DateTime,
-                Type::DateTime,
-                Type::Text,
-                Type::Text,
-                Type::Text,
-                Type::Text,
-                Type::Text,
-                Type::Text,
-                Type::Text,
-                Type::Text,
-                Type::Text,
-                Type::Text,
-                Type::NULL,
-                Type::Text,
-                Type::Unsigned,
-                Type::Text,
-                Type::Unsigned,
-                Type::Text,
-                Type::Text,
-                Type::Unsigned,
-                Type::Text,
-                Type::Unsigned,
-                Type::Text,
-                Type::Unsigned,
-                Type::Float,
-                Type::Float,
-                Type::Text
-            ]
+            quote: Quote::None,
+            quote_style: QuoteStyle::Never,
+            escape: Escape::Disabled,
+            double_quote: true,
+            comment: Comment::Disabled,
+            terminator: Terminator::LF,
+            trailing_delimiter: false,
+            embedded_escapes: false,
+            trim: Trim::None,
+            blank_lines: BlankLinePolicy::Skip,
+            flexible: false,
+            is_utf8: true
         }
     );
+    assert_eq!(metadata.avg_record_len, 433);
+    assert_eq!(metadata.num_fields, 29);
+    assert_eq!(
+        metadata.fields,
+        vec![
+            "case_enquiry_id".to_string(),
+            "open_dt".to_string(),
+            "target_dt".to_string(),
+            "closed_dt".to_string(),
+            "ontime".to_string(),
+            "case_status".to_string(),
+            "closure_reason".to_string(),
+            "case_title".to_string(),
+            "subject".to_string(),
+            "reason".to_string(),
+            "type".to_string(),
+            "queue".to_string(),
+            "department".to_string(),
+            "submittedphoto".to_string(),
+            "closedphoto".to_string(),
+            "location".to_string(),
+            "fire_district".to_string(),
+            "pwd_district".to_string(),
+            "city_council_district".to_string(),
+            "police_district".to_string(),
+            "neighborhood".to_string(),
+            "neighborhood_services_district".to_string(),
+            "ward".to_string(),
+            "precinct".to_string(),
+            "location_street_name".to_string(),
+            "location_zipcode".to_string(),
+            "latitude".to_string(),
+            "longitude".to_string(),
+            "source".to_string(),
+        ]
+    );
+    assert_eq!(
+        metadata.types,
+        vec![
+            Type::Unsigned,
+            Type::DateTime,
+            Type::DateTime,
+            Type::DateTime,
+            Type::Text,
+            Type::Text,
+            Type::Text,
+            Type::Text,
+            Type::Text,
+            Type::Text,
+            Type::Text,
+            Type::Text,
+            Type::Text,
+            Type::Url,
+            Type::NULL,
+            Type::Text,
+            Type::Unsigned,
+            Type::Text,
+            Type::Unsigned,
+            Type::Text,
+            Type::Text,
+            Type::Unsigned,
+            Type::Text,
+            Type::Text,
+            Type::Text,
+            Type::Text,
+            Type::Float,
+            Type::Float,
+            Type::Text
+        ]
+    );
+    // precinct and location_zipcode are zero-padded
+    let codes = metadata
+        .looks_like_code
+        .iter()
+        .enumerate()
+        .filter(|&(_, &is_code)| is_code)
+        .map(|(i, _)| metadata.fields[i].as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(codes, vec!["precinct", "location_zipcode"]);
 }
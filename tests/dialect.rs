@@ -0,0 +1,3323 @@
+extern crate csv;
+extern crate qsv_sniffer;
+
+use std::io::{Cursor, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use qsv_sniffer::metadata::*;
+use qsv_sniffer::{
+    DatePreference, Locale, NegativeStyle, ReplayReader, SampleSize, SkipStart, Sniffer,
+    Strictness, Type, TypeDetector, Utf8Handling,
+};
+
+#[test]
+fn test_backslash_escape() {
+    let data_filepath = Path::new(file!())
+        .parent()
+        .unwrap()
+        .join("data/backslash-escapes.csv");
+    let metadata = Sniffer::new()
+        .sample_size(SampleSize::All)
+        .sniff_path(&data_filepath)
+        .unwrap();
+    assert_eq!(metadata.dialect.quote, Quote::Some(b'"'));
+    assert_eq!(metadata.dialect.escape, Escape::Enabled(b'\\'));
+    assert!(!metadata.dialect.double_quote);
+
+    // the configured reader should unescape the quotes
+    let mut reader = metadata.dialect.open_path(data_filepath).unwrap();
+    let record = reader.records().next().unwrap().unwrap();
+    assert_eq!(&record[2], "She said \"hello\" twice");
+}
+
+#[test]
+fn test_doubled_quote_escape() {
+    let data_filepath = Path::new(file!())
+        .parent()
+        .unwrap()
+        .join("data/doubled-quotes.csv");
+    let metadata = Sniffer::new()
+        .sample_size(SampleSize::All)
+        .sniff_path(data_filepath)
+        .unwrap();
+    assert_eq!(metadata.dialect.quote, Quote::Some(b'"'));
+    assert_eq!(metadata.dialect.escape, Escape::Disabled);
+    assert!(metadata.dialect.double_quote);
+}
+
+#[test]
+fn test_comment() {
+    let data_filepath = Path::new(file!())
+        .parent()
+        .unwrap()
+        .join("data/commented.csv");
+    let metadata = Sniffer::new()
+        .sample_size(SampleSize::All)
+        .sniff_path(&data_filepath)
+        .unwrap();
+    assert_eq!(metadata.dialect.delimiter, b',');
+    assert_eq!(metadata.dialect.comment, Comment::Enabled(b'#'));
+    assert_eq!(metadata.dialect.header.num_comment_preamble_rows, 1);
+    assert!(metadata.dialect.header.has_header_row);
+    assert_eq!(metadata.fields, vec!["id", "name", "score"]);
+
+    // commented lines shouldn't show up as records
+    let mut reader = metadata.dialect.open_path(data_filepath).unwrap();
+    assert_eq!(reader.records().count(), 5);
+
+    // `//` comments are read as comments starting with `/`
+    let data = "id,name\n1,a\n// note\n2,b\n3,c\n4,d\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    assert_eq!(metadata.dialect.comment, Comment::Enabled(b'/'));
+    let mut reader = metadata.dialect.open_reader(Cursor::new(data)).unwrap();
+    assert_eq!(reader.records().count(), 4);
+
+    // unless other lines start with `/`, which would be skipped too
+    let data = "path,size\n/usr/bin,10\n// note\n/etc,20\n/var,30\n/tmp,40\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    assert_eq!(metadata.dialect.comment, Comment::Disabled);
+}
+
+#[test]
+fn test_terminator() {
+    let data_filepath = Path::new(file!())
+        .parent()
+        .unwrap()
+        .join("data/2016_presidential_election_durham.csv");
+    let metadata = Sniffer::new()
+        .sample_size(SampleSize::All)
+        .sniff_path(data_filepath)
+        .unwrap();
+    assert_eq!(metadata.dialect.terminator, Terminator::CRLF);
+
+    let data_filepath = Path::new(file!())
+        .parent()
+        .unwrap()
+        .join("data/mixed-terminators.csv");
+    let metadata = Sniffer::new()
+        .sample_size(SampleSize::All)
+        .sniff_path(data_filepath)
+        .unwrap();
+    assert_eq!(metadata.dialect.terminator, Terminator::Mixed);
+}
+
+#[test]
+fn test_multibyte_delimiter() {
+    let data_filepath = Path::new(file!())
+        .parent()
+        .unwrap()
+        .join("data/multibyte-delimiter.csv");
+    let metadata = Sniffer::new()
+        .sample_size(SampleSize::All)
+        .sniff_path(&data_filepath)
+        .unwrap();
+    assert_eq!(metadata.dialect.multibyte_delimiter, Some(b"||".to_vec()));
+    assert_eq!(metadata.num_fields, 4);
+    assert_eq!(metadata.fields, vec!["id", "name", "city", "score"]);
+
+    // the csv crate can't read multi-byte delimiters directly
+    assert!(metadata.dialect.open_path(&data_filepath).is_err());
+
+    let file = std::fs::File::open(data_filepath).unwrap();
+    let mut reader = metadata.dialect.open_rewritten_reader(file).unwrap();
+    let records = reader.records().collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(records.len(), 4);
+    assert_eq!(&records[3][2], "Chapel Hill");
+}
+
+#[test]
+fn test_trailing_delimiter() {
+    let data_filepath = Path::new(file!())
+        .parent()
+        .unwrap()
+        .join("data/trailing-delimiter.csv");
+    let metadata = Sniffer::new()
+        .sample_size(SampleSize::All)
+        .sniff_path(&data_filepath)
+        .unwrap();
+    assert!(metadata.dialect.trailing_delimiter);
+    assert_eq!(metadata.num_fields, 4);
+    assert_eq!(metadata.types.len(), 4);
+
+    let metadata = Sniffer::new()
+        .sample_size(SampleSize::All)
+        .drop_trailing_field(true)
+        .sniff_path(&data_filepath)
+        .unwrap();
+    assert!(metadata.dialect.trailing_delimiter);
+    assert_eq!(metadata.num_fields, 3);
+    assert_eq!(metadata.fields, vec!["id", "name", "score"]);
+    assert_eq!(
+        metadata.types,
+        vec![Type::Unsigned, Type::Text, Type::Unsigned]
+    );
+}
+
+#[test]
+fn test_quote_style() {
+    let quote_style = |file: &str| {
+        let data_filepath = Path::new(file!()).parent().unwrap().join(file);
+        Sniffer::new()
+            .sample_size(SampleSize::All)
+            .sniff_path(data_filepath)
+            .unwrap()
+            .dialect
+            .quote_style
+    };
+    assert_eq!(quote_style("data/always-quoted.csv"), QuoteStyle::Always);
+    assert_eq!(quote_style("data/minimal-quoted.csv"), QuoteStyle::Minimal);
+    assert_eq!(
+        quote_style("data/doubled-quotes.csv"),
+        QuoteStyle::NonNumeric
+    );
+    assert_eq!(quote_style("data/library-visitors.csv"), QuoteStyle::Never);
+}
+
+#[test]
+fn test_delimiter_candidates() {
+    let data_filepath = Path::new(file!())
+        .parent()
+        .unwrap()
+        .join("data/timestamps.csv");
+    let metadata = Sniffer::new()
+        .sample_size(SampleSize::All)
+        .delimiter_candidates(b",\t;|")
+        .sniff_path(data_filepath)
+        .unwrap();
+    assert_eq!(metadata.dialect.delimiter, b'|');
+    assert_eq!(metadata.num_fields, 3);
+    assert_eq!(metadata.fields, vec!["logged_at", "level", "message"]);
+}
+
+#[test]
+fn test_fixed_width() {
+    let data_filepath = Path::new(file!())
+        .parent()
+        .unwrap()
+        .join("data/fixed-width.txt");
+    let result = Sniffer::new()
+        .sample_size(SampleSize::All)
+        .sniff_layout_path(data_filepath)
+        .unwrap();
+    assert_eq!(result, SniffResult::FixedWidth(vec![0, 12, 17]));
+
+    let data_filepath = Path::new(file!())
+        .parent()
+        .unwrap()
+        .join("data/library-visitors.csv");
+    let result = Sniffer::new()
+        .sample_size(SampleSize::All)
+        .sniff_layout_path(data_filepath)
+        .unwrap();
+    assert!(
+        matches!(result, SniffResult::Delimited(metadata) if metadata.dialect.delimiter == b',')
+    );
+}
+
+#[test]
+fn test_quote_candidates() {
+    let data_filepath = Path::new(file!())
+        .parent()
+        .unwrap()
+        .join("data/tilde-quoted.csv");
+    let metadata = Sniffer::new()
+        .sample_size(SampleSize::All)
+        .quote_candidates(b"\"~")
+        .sniff_path(data_filepath)
+        .unwrap();
+    assert_eq!(metadata.dialect.quote, Quote::Some(b'~'));
+    assert_eq!(metadata.dialect.delimiter, b',');
+    assert_eq!(metadata.num_fields, 3);
+}
+
+#[test]
+fn test_confidence() {
+    let data_filepath = Path::new(file!())
+        .parent()
+        .unwrap()
+        .join("data/2016_presidential_election_durham.csv");
+    let metadata = Sniffer::new()
+        .sample_size(SampleSize::All)
+        .sniff_path(data_filepath)
+        .unwrap();
+    assert_eq!(metadata.confidence.delimiter, 1.0);
+    assert_eq!(metadata.confidence.quote, 1.0);
+    assert_eq!(metadata.confidence.flexible, 1.0);
+
+    // ';' and '|' occur equally often on every line, so the delimiter is a coin-flip
+    let data_filepath = Path::new(file!())
+        .parent()
+        .unwrap()
+        .join("data/ambiguous-delimiters.csv");
+    let metadata = Sniffer::new()
+        .sample_size(SampleSize::All)
+        .sniff_path(data_filepath)
+        .unwrap();
+    assert!(metadata.confidence.delimiter < 0.5);
+}
+
+#[test]
+fn test_control_character_separators() {
+    // fields separated by 0x1F (unit separator), records terminated by 0x1E (record separator)
+    let data_filepath = Path::new(file!())
+        .parent()
+        .unwrap()
+        .join("data/unit-separated.csv");
+    let metadata = Sniffer::new()
+        .sample_size(SampleSize::All)
+        .sniff_path(&data_filepath)
+        .unwrap();
+    assert_eq!(metadata.dialect.delimiter, 0x1F);
+    assert_eq!(metadata.dialect.terminator, Terminator::RS);
+    assert!(metadata.dialect.header.has_header_row);
+    assert_eq!(metadata.fields, vec!["id", "name", "city", "score"]);
+    assert_eq!(
+        metadata.types,
+        vec![Type::Unsigned, Type::Text, Type::Text, Type::Float]
+    );
+
+    let mut reader = metadata.dialect.open_path(data_filepath).unwrap();
+    let records = reader.records().collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(records.len(), 5);
+    assert_eq!(&records[2][1], "Carol, Jr.");
+}
+
+#[test]
+fn test_comma_decimal_separator() {
+    // both ';' and ',' occur twice on every line
+    let data_filepath = Path::new(file!())
+        .parent()
+        .unwrap()
+        .join("data/semicolon-comma-decimal.csv");
+    let metadata = Sniffer::new()
+        .sample_size(SampleSize::All)
+        .sniff_path(data_filepath)
+        .unwrap();
+    assert_eq!(metadata.dialect.delimiter, b';');
+    assert_eq!(metadata.decimal_separator, b',');
+    assert_eq!(metadata.num_fields, 3);
+    assert_eq!(metadata.types, vec![Type::Text, Type::Float, Type::Float]);
+
+    let data_filepath = Path::new(file!())
+        .parent()
+        .unwrap()
+        .join("data/boston311.csv");
+    let metadata = Sniffer::new()
+        .sample_size(SampleSize::All)
+        .sniff_path(data_filepath)
+        .unwrap();
+    assert_eq!(metadata.decimal_separator, b'.');
+
+    // grouped comma decimals, and a header row which `,` would leave in one piece
+    let data = "id;v\nalpha;1.234.567,89\nbeta;12,5\ngamma;3.000,00\ndelta;7,25\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    assert_eq!(metadata.dialect.delimiter, b';');
+    assert_eq!(metadata.decimal_separator, b',');
+    assert_eq!(metadata.fields, vec!["id", "v"]);
+    assert_eq!(metadata.types, vec![Type::Text, Type::Float]);
+
+    // comma decimals are detected in files delimited by something else than ';' too
+    let data = "price\tqty\tdelta\n1,25\t3\t-0,5\n2,5\t4\t1,5e3\n0,75\t5\t,25\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    assert_eq!(metadata.dialect.delimiter, b'\t');
+    assert_eq!(metadata.decimal_separator, b',');
+    assert_eq!(
+        metadata.types,
+        vec![Type::Float, Type::Unsigned, Type::Float]
+    );
+
+    // a configured decimal separator is used as is
+    let metadata = Sniffer::new()
+        .decimal_separator(b'.')
+        .sniff_reader(Cursor::new(data))
+        .unwrap();
+    assert_eq!(metadata.types, vec![Type::Text, Type::Unsigned, Type::Text]);
+    let metadata = Sniffer::new()
+        .decimal_separator(b',')
+        .sniff_reader(Cursor::new("a;b\n1.5;2,5\n3,5;4\n"))
+        .unwrap();
+    assert_eq!(metadata.types, vec![Type::Text, Type::Float]);
+}
+
+#[test]
+fn test_trim() {
+    let data_filepath = Path::new(file!())
+        .parent()
+        .unwrap()
+        .join("data/padded-fields.csv");
+    let metadata = Sniffer::new()
+        .sample_size(SampleSize::All)
+        .sniff_path(&data_filepath)
+        .unwrap();
+    assert_eq!(metadata.dialect.trim, Trim::All);
+    assert_eq!(metadata.fields, vec!["id", "name", "age", "score"]);
+    assert_eq!(
+        metadata.types,
+        vec![Type::Unsigned, Type::Text, Type::Unsigned, Type::Float]
+    );
+
+    // the configured reader should trim the fields
+    let mut reader = metadata.dialect.open_path(data_filepath).unwrap();
+    let record = reader.records().next().unwrap().unwrap();
+    assert_eq!(&record[1], "Alice");
+}
+
+#[test]
+fn test_comment_preamble() {
+    // the preamble rows contain the delimiter, but are recognized by their comment prefix
+    let data_filepath = Path::new(file!())
+        .parent()
+        .unwrap()
+        .join("data/comment-preamble.csv");
+    let metadata = Sniffer::new()
+        .sample_size(SampleSize::All)
+        .sniff_path(&data_filepath)
+        .unwrap();
+    assert_eq!(
+        metadata.dialect.header,
+        Header {
+            has_header_row: true,
+            num_preamble_rows: 3,
+            num_comment_preamble_rows: 3,
+        }
+    );
+    assert_eq!(metadata.dialect.comment, Comment::Disabled);
+    assert_eq!(metadata.fields, vec!["time", "ch1", "ch2"]);
+    assert_eq!(metadata.types, vec![Type::Float, Type::Float, Type::Float]);
+
+    let mut reader = metadata.dialect.open_path(data_filepath).unwrap();
+    assert_eq!(reader.records().count(), 5);
+}
+
+#[test]
+fn test_footer() {
+    // a totals row, then a blank line and an end-of-report marker
+    let data_filepath = Path::new(file!()).parent().unwrap().join("data/footer.csv");
+    let metadata = Sniffer::new()
+        .sample_size(SampleSize::All)
+        .sniff_path(data_filepath)
+        .unwrap();
+    assert_eq!(metadata.num_footer_rows, 3);
+    assert!(!metadata.dialect.flexible);
+    assert_eq!(
+        metadata.types,
+        vec![Type::Text, Type::Text, Type::Unsigned, Type::Float]
+    );
+
+    // summary lines after a blank line
+    let data_filepath = Path::new(file!())
+        .parent()
+        .unwrap()
+        .join("data/summary-footer.csv");
+    let metadata = Sniffer::new()
+        .sample_size(SampleSize::All)
+        .sniff_path(data_filepath)
+        .unwrap();
+    assert_eq!(metadata.num_footer_rows, 3);
+    assert_eq!(metadata.num_fields, 3);
+
+    let data_filepath = Path::new(file!())
+        .parent()
+        .unwrap()
+        .join("data/library-visitors.csv");
+    let metadata = Sniffer::new()
+        .sample_size(SampleSize::All)
+        .sniff_path(data_filepath)
+        .unwrap();
+    assert_eq!(metadata.num_footer_rows, 0);
+
+    // rows starting with a keyword, shaped like the rest, are data
+    let data = "item,qty\nTotal widgets,2\nSum beans,4\nTotal,6\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    assert_eq!(metadata.num_footer_rows, 0);
+    assert!(metadata.dialect.header.has_header_row);
+    assert_eq!(metadata.fields, vec!["item", "qty"]);
+
+    // a blank line ending the file isn't a footer row
+    let data = "a,b\n1,2\n3,4\n\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    assert_eq!(metadata.num_footer_rows, 0);
+
+    // the end of a file with a multi-byte delimiter is only examined if the sample reaches it
+    let mut data = String::from("id||name||score\n");
+    for i in 0..5000 {
+        data.push_str(&format!("{i}||item {i}||{}\n", i % 7));
+    }
+    data.push_str("Total||||15000\n");
+    let metadata = Sniffer::new()
+        .multibyte_delimiter(b"||")
+        .sample_size(SampleSize::All)
+        .sniff_reader(Cursor::new(&data))
+        .unwrap();
+    assert_eq!(metadata.num_footer_rows, 1);
+    let metadata = Sniffer::new()
+        .multibyte_delimiter(b"||")
+        .sample_size(SampleSize::Bytes(1024))
+        .sniff_reader(Cursor::new(&data))
+        .unwrap();
+    assert_eq!(metadata.num_footer_rows, 0);
+}
+
+#[test]
+fn test_blank_lines() {
+    // blank lines between groups of records
+    let data_filepath = Path::new(file!())
+        .parent()
+        .unwrap()
+        .join("data/blank-lines.csv");
+    let metadata = Sniffer::new()
+        .sample_size(SampleSize::All)
+        .sniff_path(&data_filepath)
+        .unwrap();
+    assert_eq!(metadata.dialect.delimiter, b',');
+    assert!(!metadata.dialect.flexible);
+    assert_eq!(metadata.dialect.blank_lines, BlankLinePolicy::Skip);
+    assert_eq!(metadata.num_blank_lines, 2);
+    assert_eq!(metadata.num_footer_rows, 0);
+
+    let mut reader = metadata.dialect.open_path(data_filepath).unwrap();
+    assert_eq!(reader.records().count(), 9);
+}
+
+#[test]
+fn test_diagnostics() {
+    let data_filepath = Path::new(file!())
+        .parent()
+        .unwrap()
+        .join("data/semicolon-comma-decimal.csv");
+    let (metadata, diagnostics) = Sniffer::new()
+        .sample_size(SampleSize::All)
+        .sniff_with_diagnostics(std::fs::File::open(data_filepath).unwrap())
+        .unwrap();
+    assert_eq!(metadata.dialect.delimiter, b';');
+
+    let candidate = |delim: u8| {
+        diagnostics
+            .candidates
+            .iter()
+            .find(|candidate| candidate.delimiter == delim)
+            .unwrap()
+    };
+    assert_eq!(candidate(b';').state, ChainState::SteadyStrict);
+    assert_eq!(candidate(b';').rejection, None);
+    assert_eq!(candidate(b',').state, ChainState::SteadyStrict);
+    assert_eq!(candidate(b',').rejection, Some(Rejection::DecimalSeparator));
+    // every other candidate is rejected
+    assert!(diagnostics
+        .candidates
+        .iter()
+        .filter(|candidate| candidate.delimiter != b';')
+        .all(|candidate| candidate.rejection.is_some()));
+}
+
+#[test]
+fn test_quoted_delimiters() {
+    let data_filepath = Path::new(file!())
+        .parent()
+        .unwrap()
+        .join("data/tab-quoted-commas.csv");
+    let (metadata, diagnostics) = Sniffer::new()
+        .sample_size(SampleSize::All)
+        .sniff_with_diagnostics(std::fs::File::open(data_filepath).unwrap())
+        .unwrap();
+    assert_eq!(metadata.dialect.delimiter, b'\t');
+    assert_eq!(
+        metadata.fields[..3],
+        ["id".to_string(), "comment".to_string(), "score".to_string()]
+    );
+    // the commas within the quoted comments aren't counted
+    assert!(diagnostics
+        .candidates
+        .iter()
+        .all(|candidate| candidate.delimiter != b','));
+}
+
+#[test]
+fn test_delimiter_prior() {
+    let data_dir = Path::new(file!()).parent().unwrap().join("data");
+    // both delimiters are equally consistent, so the prior breaks the tie
+    let metadata = Sniffer::new()
+        .sniff_path(data_dir.join("ambiguous-delimiters.csv"))
+        .unwrap();
+    assert_eq!(metadata.dialect.delimiter, b';');
+    let metadata = Sniffer::new()
+        .delimiter_prior(b'|', 2.0)
+        .sniff_path(data_dir.join("ambiguous-delimiters.csv"))
+        .unwrap();
+    assert_eq!(metadata.dialect.delimiter, b'|');
+
+    // priors don't override a clearly better delimiter
+    let metadata = Sniffer::new()
+        .delimiter_prior(b'\t', 2.0)
+        .sniff_path(data_dir.join("library-visitors.csv"))
+        .unwrap();
+    assert_eq!(metadata.dialect.delimiter, b',');
+}
+
+#[test]
+fn test_single_column() {
+    let data_dir = Path::new(file!()).parent().unwrap().join("data");
+    let metadata = Sniffer::new()
+        .sniff_path(data_dir.join("single-column.csv"))
+        .unwrap();
+    assert!(metadata.dialect.single_column);
+    // the file contains commas, so a delimiter which doesn't occur is used instead
+    assert_eq!(metadata.dialect.delimiter, b'\t');
+    assert_eq!(metadata.num_fields, 1);
+    assert_eq!(metadata.fields, vec!["city".to_string()]);
+
+    // blank lines within a single column are empty values
+    let metadata = Sniffer::new()
+        .sniff_path(data_dir.join("single-column-blank-lines.csv"))
+        .unwrap();
+    assert!(metadata.dialect.single_column);
+    assert_eq!(metadata.dialect.delimiter, b',');
+    assert_eq!(metadata.dialect.blank_lines, BlankLinePolicy::EmptyRecord);
+    assert_eq!(metadata.num_blank_lines, 2);
+    assert_eq!(metadata.types, vec![Type::Unsigned]);
+}
+
+#[test]
+fn test_presets() {
+    let excel = Dialect::preset("excel").unwrap();
+    assert_eq!(excel.delimiter, b',');
+    assert_eq!(excel.quote, Quote::Some(b'"'));
+    assert_eq!(excel.terminator, Terminator::CRLF);
+    assert_eq!(Dialect::preset("excel-tab").unwrap().delimiter, b'\t');
+    assert_eq!(Dialect::preset("rfc4180"), Some(excel));
+    assert_eq!(Dialect::preset("unknown"), None);
+
+    let closest_preset = |filename: &str| {
+        let data_filepath = Path::new(file!())
+            .parent()
+            .unwrap()
+            .join("data")
+            .join(filename);
+        Sniffer::new()
+            .sniff_path(data_filepath)
+            .unwrap()
+            .closest_preset()
+    };
+    assert_eq!(closest_preset("always-quoted.csv"), Some("unix"));
+    assert_eq!(closest_preset("minimal-quoted.csv"), Some("excel"));
+    assert_eq!(closest_preset("tab-quoted-commas.csv"), Some("excel-tab"));
+    assert_eq!(
+        closest_preset("2016_presidential_election_durham.csv"),
+        None
+    );
+}
+
+#[test]
+fn test_column_quoting() {
+    let data_dir = Path::new(file!()).parent().unwrap().join("data");
+    let metadata = Sniffer::new()
+        .sniff_path(data_dir.join("minimal-quoted.csv"))
+        .unwrap();
+    assert_eq!(
+        metadata.column_quoting,
+        vec![
+            ColumnQuoting::Never,
+            ColumnQuoting::Mixed,
+            ColumnQuoting::Mixed
+        ]
+    );
+
+    let metadata = Sniffer::new()
+        .sniff_path(data_dir.join("always-quoted.csv"))
+        .unwrap();
+    assert_eq!(metadata.column_quoting, vec![ColumnQuoting::Always; 3]);
+    assert_eq!(metadata.types[..2], [Type::Unsigned, Type::Text]);
+    // quoted numbers can be treated as text by intent
+    let metadata = Sniffer::new()
+        .quoted_numbers_as_text(true)
+        .sniff_path(data_dir.join("always-quoted.csv"))
+        .unwrap();
+    assert_eq!(metadata.types, vec![Type::Text; 3]);
+}
+
+#[test]
+fn test_strictness() {
+    let data_dir = Path::new(file!()).parent().unwrap().join("data");
+    let sniff = |strictness: Strictness, filename: &str| {
+        Sniffer::new()
+            .strictness(strictness)
+            .sniff_path(data_dir.join(filename))
+    };
+
+    // ';' and '|' are equally likely
+    assert_eq!(
+        sniff(Strictness::Balanced, "ambiguous-delimiters.csv")
+            .unwrap()
+            .dialect
+            .delimiter,
+        b';'
+    );
+    let err = sniff(Strictness::Strict, "ambiguous-delimiters.csv").unwrap_err();
+    assert!(err.to_string().contains("contradictory delimiters"));
+
+    // a quoted field is never closed
+    let err = sniff(Strictness::Strict, "unbalanced-quotes.csv").unwrap_err();
+    assert!(err.to_string().contains("unbalanced quotes"));
+    assert_eq!(
+        sniff(Strictness::Lenient, "unbalanced-quotes.csv")
+            .unwrap()
+            .dialect
+            .delimiter,
+        b','
+    );
+
+    // consistent files sniff the same regardless of strictness
+    assert_eq!(
+        sniff(Strictness::Strict, "library-visitors.csv").unwrap(),
+        sniff(Strictness::Lenient, "library-visitors.csv").unwrap()
+    );
+
+    // lenient sniffing returns a dialect even when there's no data
+    assert!(Sniffer::new().sniff_reader(Cursor::new(b"")).is_err());
+    let metadata = Sniffer::new()
+        .strictness(Strictness::Lenient)
+        .sniff_reader(Cursor::new(b""))
+        .unwrap();
+    assert_eq!(metadata.num_fields, 1);
+    assert!(metadata.types.is_empty());
+}
+
+#[test]
+fn test_canonical_string() {
+    let data_dir = Path::new(file!()).parent().unwrap().join("data");
+    for filename in [
+        "library-visitors.csv",
+        "2016_presidential_election_durham.csv",
+        "tab-quoted-commas.csv",
+        "multibyte-delimiter.csv",
+        "commented.csv",
+    ] {
+        let dialect = Sniffer::new()
+            .sniff_path(data_dir.join(filename))
+            .unwrap()
+            .dialect;
+        let encoded = dialect.to_canonical_string();
+        assert_eq!(encoded.parse::<Dialect>().unwrap(), dialect, "{encoded}");
+    }
+
+    let dialect = Sniffer::new()
+        .sniff_path(data_dir.join("2016_presidential_election_durham.csv"))
+        .unwrap()
+        .dialect;
+    assert!(dialect
+        .to_canonical_string()
+        .starts_with("delim=\\x3b;single_column=0;header=1;preamble=0;"));
+
+    // components which are left out are taken from the excel preset
+    let dialect: Dialect = "delim=\\t;header=0;quote=".parse().unwrap();
+    assert_eq!(dialect.delimiter, b'\t');
+    assert!(!dialect.header.has_header_row);
+    assert_eq!(dialect.quote, Quote::None);
+    assert_eq!(dialect.terminator, Terminator::CRLF);
+
+    assert!("delim=,;colour=blue".parse::<Dialect>().is_err());
+    assert!("delim=,,".parse::<Dialect>().is_err());
+    assert!("header=yes".parse::<Dialect>().is_err());
+}
+
+#[test]
+fn test_embedded_escapes() {
+    let data_dir = Path::new(file!()).parent().unwrap().join("data");
+    let metadata = Sniffer::new()
+        .sniff_path(data_dir.join("embedded-escapes.csv"))
+        .unwrap();
+    assert!(metadata.dialect.embedded_escapes);
+    assert_eq!(metadata.num_fields, 2);
+
+    // backslashes escaping quotes within quoted fields aren't embedded escapes
+    let metadata = Sniffer::new()
+        .sniff_path(data_dir.join("backslash-escapes.csv"))
+        .unwrap();
+    assert!(!metadata.dialect.embedded_escapes);
+}
+
+#[test]
+fn test_multiline_fields() {
+    let data_dir = Path::new(file!()).parent().unwrap().join("data");
+    let metadata = Sniffer::new()
+        .sniff_path(data_dir.join("multiline-fields.csv"))
+        .unwrap();
+    assert!(metadata.has_multiline_fields);
+    // the line break within the quoted field doesn't look like a change in the number of fields
+    assert_eq!(metadata.dialect.header.num_preamble_rows, 0);
+    assert!(!metadata.dialect.flexible);
+    assert_eq!(metadata.num_fields, 3);
+
+    let metadata = Sniffer::new()
+        .sniff_path(data_dir.join("minimal-quoted.csv"))
+        .unwrap();
+    assert!(!metadata.has_multiline_fields);
+}
+
+#[test]
+fn test_single_record() {
+    // a header row and a single data row, in which '.' occurs as often as ';'
+    let data_filepath = Path::new(file!())
+        .parent()
+        .unwrap()
+        .join("data/single-record.csv");
+    let metadata = Sniffer::new().sniff_path(data_filepath).unwrap();
+    assert_eq!(metadata.dialect.delimiter, b';');
+    assert!(metadata.dialect.header.has_header_row);
+    assert_eq!(
+        metadata.fields,
+        vec!["time.start".to_string(), "time.end".to_string()]
+    );
+    assert_eq!(metadata.types, vec![Type::Float, Type::Float]);
+
+    // a single row, in which ':' occurs more often than ';'
+    let metadata = Sniffer::new()
+        .sniff_reader(Cursor::new(b"10:30;11:45;12:00\n"))
+        .unwrap();
+    assert_eq!(metadata.dialect.delimiter, b';');
+    assert!(!metadata.dialect.header.has_header_row);
+    assert_eq!(metadata.num_fields, 3);
+}
+
+#[test]
+fn test_with_known() {
+    let data_dir = Path::new(file!()).parent().unwrap().join("data");
+    // the delimiter and header are locked, the quote is still sniffed
+    let metadata = Sniffer::new()
+        .with_known(&DialectHints {
+            delimiter: Some(b','),
+            has_header_row: Some(false),
+            ..DialectHints::default()
+        })
+        .sniff_path(data_dir.join("minimal-quoted.csv"))
+        .unwrap();
+    assert_eq!(metadata.dialect.delimiter, b',');
+    assert_eq!(metadata.dialect.quote, Quote::Some(b'"'));
+    assert!(!metadata.dialect.header.has_header_row);
+    assert!(metadata.fields.is_empty());
+
+    // a locked preamble isn't sniffed, even though it contains the delimiter
+    let data = "Visitors, by month, 2013\nMonth,Door Count\n2013-09,131680\n2013-10,144662\n";
+    let metadata = Sniffer::new()
+        .with_known(&DialectHints {
+            num_preamble_rows: Some(1),
+            terminator: Some(Terminator::CRLF),
+            ..DialectHints::default()
+        })
+        .sniff_reader(Cursor::new(data))
+        .unwrap();
+    assert_eq!(metadata.dialect.delimiter, b',');
+    assert_eq!(metadata.dialect.header.num_preamble_rows, 1);
+    assert_eq!(metadata.dialect.terminator, Terminator::CRLF);
+    assert!(!metadata.dialect.flexible);
+    assert_eq!(
+        metadata.fields,
+        vec!["Month".to_string(), "Door Count".to_string()]
+    );
+}
+
+#[test]
+fn test_date_formats() {
+    let data_filepath = Path::new(file!())
+        .parent()
+        .unwrap()
+        .join("data/boston311.csv");
+    let metadata = Sniffer::new()
+        .sample_size(SampleSize::All)
+        .sniff_path(data_filepath)
+        .unwrap();
+    assert_eq!(metadata.date_formats.len(), metadata.types.len());
+    assert_eq!(metadata.date_formats[0], None);
+    assert_eq!(
+        metadata.date_formats[1],
+        Some("%Y-%m-%d %H:%M:%S".to_string())
+    );
+
+    // the day/month order of ambiguous dates follows the date preference
+    let data = "date,amount\n01/02/2020,5\n03/04/2020,7\n05/06/2020,1\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    assert_eq!(metadata.types[0], Type::Date);
+    assert_eq!(metadata.date_formats[0], Some("%m/%d/%Y".to_string()));
+    let metadata = Sniffer::new()
+        .date_preference(DatePreference::DmyFormat)
+        .sniff_reader(Cursor::new(data))
+        .unwrap();
+    assert_eq!(metadata.date_formats[0], Some("%d/%m/%Y".to_string()));
+
+    // ...unless the values rule it out
+    let data = "date,amount\n01/02/2020,5\n03/04/2020,7\n05/31/2020,1\n";
+    let metadata = Sniffer::new()
+        .date_preference(DatePreference::DmyFormat)
+        .sniff_reader(Cursor::new(data))
+        .unwrap();
+    assert_eq!(metadata.date_formats[0], Some("%m/%d/%Y".to_string()));
+}
+
+#[test]
+fn test_timezones() {
+    let data = "id,created,updated,logged\n\
+                1,2020-01-01T10:00:00+02:00,2020-01-01 10:00:00,2020-01-01 10:00:00+0200\n\
+                2,2020-01-02T02:00:00+02:00,2020-01-02 11:30:00,2020-01-02 11:30:00-0500\n\
+                3,2020-03-04T12:15:00+02:00,2020-03-04 12:00:00,2020-03-04 12:00:00+0000\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    // a value with a UTC offset is a datetime, even at midnight UTC
+    assert_eq!(
+        metadata.types,
+        vec![
+            Type::Unsigned,
+            Type::DateTime,
+            Type::DateTime,
+            Type::DateTime
+        ]
+    );
+    assert_eq!(
+        metadata.date_formats,
+        vec![
+            None,
+            Some("%Y-%m-%dT%H:%M:%S%:z".to_string()),
+            Some("%Y-%m-%d %H:%M:%S".to_string()),
+            Some("%Y-%m-%d %H:%M:%S%z".to_string()),
+        ]
+    );
+    assert_eq!(
+        metadata.timezones,
+        vec![
+            None,
+            Some(Timezone::Aware),
+            Some(Timezone::Naive),
+            Some(Timezone::Aware)
+        ]
+    );
+}
+
+#[test]
+fn test_prefer_dmy() {
+    // every value is ambiguous, so the order is up to the preference
+    let data = "date,amount\n01/02/2020,5\n03/04/2020,7\n05/06/2020,1\n";
+    let metadata = Sniffer::new()
+        .prefer_dmy(true)
+        .sniff_reader(Cursor::new(data))
+        .unwrap();
+    assert_eq!(metadata.date_formats[0], Some("%d/%m/%Y".to_string()));
+    assert_eq!(
+        metadata.date_orders,
+        vec![
+            Some(DateOrder {
+                order: DatePreference::DmyFormat,
+                from_preference: true
+            }),
+            None
+        ]
+    );
+
+    // a day after the 12th settles the order, whatever the preference
+    let data = "date,amount\n01/02/2020,5\n25/04/2020,7\n05/06/2020,1\n";
+    for prefer_dmy in [false, true] {
+        let metadata = Sniffer::new()
+            .prefer_dmy(prefer_dmy)
+            .sniff_reader(Cursor::new(data))
+            .unwrap();
+        assert_eq!(metadata.date_formats[0], Some("%d/%m/%Y".to_string()));
+        assert_eq!(
+            metadata.date_orders[0],
+            Some(DateOrder {
+                order: DatePreference::DmyFormat,
+                from_preference: false
+            })
+        );
+    }
+
+    // formats without a day/month order don't report one
+    let metadata = Sniffer::new()
+        .prefer_dmy(true)
+        .sniff_reader(Cursor::new("date,amount\n2020-01-02,5\n2020-04-25,7\n"))
+        .unwrap();
+    assert_eq!(metadata.date_orders, vec![None, None]);
+}
+
+#[test]
+fn test_time_of_day() {
+    let data = "opens,closes,lunch,ratio\n\
+                09:00,17:30:00,12:15 PM,1:2\n\
+                08:30,18:00:00.500,1:05 PM,3:4\n\
+                10:15,16:45:30,11:45 AM,2:3\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    assert!(metadata.dialect.header.has_header_row);
+    assert_eq!(
+        metadata.types,
+        vec![Type::Time, Type::Time, Type::Time, Type::Text]
+    );
+    assert_eq!(
+        metadata.date_formats,
+        vec![
+            Some("%H:%M".to_string()),
+            Some("%H:%M:%S%.f".to_string()),
+            Some("%I:%M %p".to_string()),
+            None
+        ]
+    );
+    assert_eq!(metadata.timezones, vec![None; 4]);
+}
+
+#[test]
+fn test_boolean_values() {
+    let data = "name,active,enrolled,mixed\nana,Si,yes,yes\nbo,No,no,true\ncy,si,YES,false\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    // values from different pairs don't make a boolean column
+    assert_eq!(
+        metadata.types,
+        vec![Type::Text, Type::Text, Type::Boolean, Type::Text]
+    );
+
+    let metadata = Sniffer::new()
+        .boolean_values("si", "no")
+        .sniff_reader(Cursor::new(data))
+        .unwrap();
+    assert_eq!(
+        metadata.types,
+        vec![Type::Text, Type::Boolean, Type::Boolean, Type::Text]
+    );
+}
+
+#[test]
+fn test_null_tokens() {
+    let data = "id,count,ratio,note\n1,12,0.5,a\n2,NA,,b\n3,7,n/a,c\n4,-,0.25,d\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    // missing values don't demote numeric columns to text
+    assert_eq!(
+        metadata.types,
+        vec![Type::Unsigned, Type::Unsigned, Type::Float, Type::Text]
+    );
+    assert_eq!(metadata.nullable, vec![false, true, true, false]);
+    assert_eq!(
+        metadata.null_tokens,
+        vec![
+            vec![],
+            vec!["NA".to_string(), "-".to_string()],
+            vec!["".to_string(), "n/a".to_string()],
+            vec![]
+        ]
+    );
+
+    let metadata = Sniffer::new()
+        .null_tokens(&["-"])
+        .sniff_reader(Cursor::new(data))
+        .unwrap();
+    assert_eq!(
+        metadata.types,
+        vec![Type::Unsigned, Type::Text, Type::Text, Type::Text]
+    );
+    assert_eq!(metadata.null_tokens[1], vec!["-".to_string()]);
+}
+
+#[test]
+fn test_integer_widths() {
+    let data = "small,negative,large,huge,account\n\
+                1,-5,3000000000,18446744073709551615,123456789012345678901234567890\n\
+                2,-2147483649,12,1,-98765432109876543210\n\
+                3,7,-1,2,100000000000000000000\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    assert_eq!(
+        metadata.types,
+        vec![
+            Type::Unsigned,
+            Type::Signed,
+            Type::Signed,
+            Type::Unsigned,
+            Type::Text
+        ]
+    );
+    assert_eq!(
+        metadata.integer_widths,
+        vec![
+            Some(IntegerWidth::I32),
+            Some(IntegerWidth::I64),
+            Some(IntegerWidth::I64),
+            Some(IntegerWidth::U64),
+            Some(IntegerWidth::Big)
+        ]
+    );
+}
+
+#[test]
+fn test_thousands_separators() {
+    let data = "name\tpopulation\tarea\tcode\n\
+                a\t1,234,567\t12,345.5\t1\n\
+                b\t987,654\t6.25\t2\n\
+                c\t12\t1,000\t3\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    assert_eq!(metadata.decimal_separator, b'.');
+    assert_eq!(
+        metadata.types,
+        vec![Type::Text, Type::Unsigned, Type::Float, Type::Unsigned]
+    );
+    let format = |thousands_separator| {
+        Some(NumberFormat {
+            decimal_separator: b'.',
+            thousands_separator,
+        })
+    };
+    assert_eq!(
+        metadata.number_formats,
+        vec![None, format(Some(b',')), format(Some(b',')), format(None)]
+    );
+    assert_eq!(metadata.integer_widths[1], Some(IntegerWidth::I32));
+
+    let data = "name;amount\n\
+                a;1.234.567,89\n\
+                b;12,5\n\
+                c;3.000\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    assert_eq!(metadata.decimal_separator, b',');
+    assert_eq!(metadata.types, vec![Type::Text, Type::Float]);
+    assert_eq!(
+        metadata.number_formats[1],
+        Some(NumberFormat {
+            decimal_separator: b',',
+            thousands_separator: Some(b'.'),
+        })
+    );
+
+    // inconsistent grouping isn't numeric
+    let data = "name\tamount\n\
+                a\t1,234,567\n\
+                b\t1 234 567\n\
+                c\t12\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    assert_eq!(metadata.types, vec![Type::Text, Type::Text]);
+    assert_eq!(metadata.number_formats, vec![None, None]);
+}
+
+#[test]
+fn test_currency() {
+    let data = "item\tprice\tbalance\tpreis\tmixed\n\
+                a\t$1,234.56\t(1,000.00)\t€ 12,00\t$5\n\
+                b\t$3.50\t$250.00\t€ 1.250,50\t€5\n\
+                c\t$0.99\t($7.25)\t7,5 €\t$6\n\
+                d\t-$12.00\t$3.00\t€ 0,99\t$7\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    assert_eq!(
+        metadata.types,
+        vec![
+            Type::Text,
+            Type::Currency {
+                symbol: "$",
+                negative: Some(NegativeStyle::Minus)
+            },
+            Type::Currency {
+                symbol: "$",
+                negative: Some(NegativeStyle::Parentheses)
+            },
+            Type::Currency {
+                symbol: "€",
+                negative: None
+            },
+            Type::Text
+        ]
+    );
+}
+
+#[test]
+fn test_percent() {
+    let data = "region\tgrowth\tshare\tnote\n\
+                north\t12.5%\t40 %\t5% off\n\
+                south\t-3%\t35,5 %\tnone\n\
+                east\t0.25%\t24,5 %\t10%\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    assert_eq!(
+        metadata.types,
+        vec![Type::Text, Type::Percent, Type::Percent, Type::Text]
+    );
+}
+
+#[test]
+fn test_scientific_notation() {
+    let data = "name\tsmall\tlarge\tplain\tno_exponent\tidentifier\tnot_a_number\n\
+                a\t1.2e-5\t1E+10\t3e8\t1e\t12E45F\tinf\n\
+                b\t-4.5E-3\t2.5e10\t7\t2e\t13E46F\tNaN\n\
+                c\t.5e2\t6E6\t1.5\t3e\t14E47F\tinfinity\n";
+    let metadata = Sniffer::new()
+        .non_finite_floats(false)
+        .sniff_reader(Cursor::new(data))
+        .unwrap();
+    assert_eq!(
+        metadata.types,
+        vec![
+            Type::Text,
+            Type::Float,
+            Type::Float,
+            Type::Float,
+            Type::Text,
+            Type::Text,
+            Type::Text
+        ]
+    );
+
+    // with a comma decimal separator
+    let data = "name;value\n\
+                a;1,5e-3\n\
+                b;2,25E4\n\
+                c;3e8\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    assert_eq!(metadata.decimal_separator, b',');
+    assert_eq!(metadata.types, vec![Type::Text, Type::Float]);
+}
+
+#[test]
+fn test_leading_zero_codes() {
+    let data = "zip,account,count\n\
+                00420,0123,7\n\
+                10001,042,12\n\
+                94105,07,0\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    assert_eq!(metadata.types, vec![Type::Text, Type::Text, Type::Unsigned]);
+    assert_eq!(metadata.looks_like_code, vec![true, true, false]);
+    assert_eq!(
+        metadata.integer_widths,
+        vec![None, None, Some(IntegerWidth::I32)]
+    );
+
+    // a single zero-padded value among numbers of different widths
+    let data = "id,count\na,1\nb,25\nc,007\nd,300\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    assert_eq!(metadata.types, vec![Type::Text, Type::Unsigned]);
+    assert_eq!(metadata.looks_like_code, vec![false, false]);
+}
+
+#[test]
+fn test_uuid() {
+    let data = "id,ref,name\n\
+                123e4567-e89b-12d3-a456-426614174000,{6F9619FF-8B86-D011-B42D-00C04FC964FF},a\n\
+                00000000-0000-0000-0000-000000000000,{0f8fad5b-d9cb-469f-a165-70867728950e},b\n\
+                7c9e6679-7425-40de-944b-e07fc1f90ae7,{7C9E6679-7425-40DE-944B-E07FC1F90AE7},c\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    assert_eq!(metadata.types, vec![Type::Uuid, Type::Uuid, Type::Text]);
+
+    // not all groups have the canonical length
+    let data = "id,name\n\
+                123e4567-e89b-12d3-a456-42661417400,a\n\
+                00000000-0000-0000-0000-000000000000,b\n\
+                7c9e6679-7425-40de-944b-e07fc1f90ae7,c\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    assert_eq!(metadata.types, vec![Type::Text, Type::Text]);
+}
+
+#[test]
+fn test_email_and_url() {
+    let data = "name,email,homepage,note\n\
+                a,jane.doe+csv@example.co.uk,https://example.com/jane?tab=1,see me@home\n\
+                b,j_smith@mail.example.org,http://localhost:8080/,www.example.com\n\
+                c,x@y.io,ftp://user@files.example.net/pub,mailto:x@y.io\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    assert_eq!(
+        metadata.types,
+        vec![Type::Text, Type::Email, Type::Url, Type::Text]
+    );
+}
+
+#[test]
+fn test_network_addresses() {
+    let data = "host\tipv4\tipv6\tmac\tmixed\n\
+                a\t192.168.0.1\t2001:db8::ff00:42:8329\t00:1A:2b:3C:4d:5E\t10.0.0.1\n\
+                b\t10.0.0.254\t::1\t00-1A-2B-3C-4D-5F\t::1\n\
+                c\t8.8.8.8\tfe80::1ff:fe23:4567:890a\t001a.2b3c.4d60\t172.16.0.1\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    assert_eq!(
+        metadata.types,
+        vec![
+            Type::Text,
+            Type::Ipv4,
+            Type::Ipv6,
+            Type::MacAddress,
+            Type::Text
+        ]
+    );
+}
+
+#[test]
+fn test_json() {
+    let data = "id\tattributes\ttags\tbroken\n\
+                1\t{\"color\": \"red\", \"size\": {\"w\": 1.5e2, \"h\": -3}}\t[\"a\", \"b\"]\t{\"a\": 1\n\
+                2\t{}\t[]\t{\"a\": 2}\n\
+                3\t{\"ok\": true, \"note\": null, \"name\": \"caf\\u00e9\"}\t[1, [2, 3]]\t[1, 2,]\n";
+    // the JSON documents have commas, so the delimiter is provided
+    let metadata = Sniffer::new()
+        .delimiter(b'\t')
+        .sniff_reader(Cursor::new(data))
+        .unwrap();
+    assert_eq!(
+        metadata.types,
+        vec![Type::Unsigned, Type::Json, Type::Json, Type::Text]
+    );
+}
+
+#[test]
+fn test_binary() {
+    let data = "id,thumbnail,token,word\n\
+                1,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk,q8V-3kZ_pL2mX9wQ4tYb,Representations1\n\
+                2,R0lGODlhAQABAIAAAP///wAAACH5BAEAAAAALAAAAAABAAEAAAICRAEAOw==,Zx7_Pq2LmN0vB4cR-sTu,Internationalize\n\
+                3,/9j/4AAQSkZJRgABAQEASABIAAD/2wBDAAoHBwgHBgoICAgLCgoLDhgQ,Hj3kL9mN2pQ8rS-tUv_W,Responsibilities\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    assert_eq!(
+        metadata.types,
+        vec![Type::Unsigned, Type::Binary, Type::Binary, Type::Text]
+    );
+}
+
+#[test]
+fn test_categories() {
+    let data = "id,status,priority,note\n\
+                1,open,high,a\n\
+                2,closed,low,b\n\
+                3, open,high,c\n\
+                4,pending,medium,\n\
+                5,closed,low,d\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    let categories = |values: &[&str]| Some(values.iter().map(ToString::to_string).collect());
+    assert_eq!(
+        metadata.categories,
+        vec![
+            None,
+            categories(&["open", "closed", "pending"]),
+            categories(&["high", "low", "medium"]),
+            None
+        ]
+    );
+
+    let metadata = Sniffer::new()
+        .category_threshold(2)
+        .sniff_reader(Cursor::new(data))
+        .unwrap();
+    assert_eq!(metadata.categories, vec![None, None, None, None]);
+}
+
+#[test]
+fn test_constant_values() {
+    let data = "id,country,source,empty\n\
+                1,US,import,\n\
+                2,US,,\n\
+                3,US,import,\n\
+                4,US,manual,\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    assert_eq!(
+        metadata.constant_values,
+        vec![None, Some("US".to_string()), None, None]
+    );
+
+    // missing values are ignored
+    let data = "id,status\n\
+                1,active\n\
+                2,NA\n\
+                3,active\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    assert_eq!(
+        metadata.constant_values,
+        vec![None, Some("active".to_string())]
+    );
+}
+
+#[test]
+fn test_type_fallbacks() {
+    let mut data = "id,day,amount\n".to_string();
+    for i in 1..=19 {
+        data.push_str(&format!("{i},2021-03-{i:02},{i}.5\n"));
+    }
+    data.push_str("20,unknown,-7\n");
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    assert_eq!(
+        metadata.types,
+        vec![Type::Unsigned, Type::Text, Type::Float]
+    );
+    assert_eq!(metadata.type_confidences, vec![1.0, 1.0, 1.0]);
+
+    let fallbacks = metadata
+        .type_fallbacks
+        .iter()
+        .map(|fits| {
+            fits.iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(" → ")
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        fallbacks,
+        vec![
+            "Unsigned(1.00) → Signed(1.00) → Float(1.00) → Text(1.00)",
+            "Date(0.95) → Text(1.00)",
+            "Float(1.00) → Text(1.00)"
+        ]
+    );
+}
+
+#[derive(Debug)]
+struct NhsNumberDetector;
+
+impl TypeDetector for NhsNumberDetector {
+    fn name(&self) -> &'static str {
+        "nhs_number"
+    }
+
+    fn priority(&self) -> i32 {
+        1
+    }
+
+    // ten digits, the last one being a (modulus 11) check digit
+    fn matches(&self, value: &[u8]) -> bool {
+        if value.len() != 10 || !value.iter().all(u8::is_ascii_digit) {
+            return false;
+        }
+        let digits = value
+            .iter()
+            .map(|b| u32::from(b - b'0'))
+            .collect::<Vec<_>>();
+        let sum: u32 = (0..9).map(|i| digits[i] * (10 - i as u32)).sum();
+        let check = (11 - sum % 11) % 11;
+        check != 10 && check == digits[9]
+    }
+}
+
+#[derive(Debug)]
+struct TenDigitDetector;
+
+impl TypeDetector for TenDigitDetector {
+    fn name(&self) -> &'static str {
+        "ten_digits"
+    }
+
+    fn matches(&self, value: &[u8]) -> bool {
+        value.len() == 10 && value.iter().all(u8::is_ascii_digit)
+    }
+}
+
+#[test]
+fn test_type_detectors() {
+    // sniffers can be sent to other threads, with or without detectors
+    fn assert_send<T: Send + Sync>(_: T) {}
+    assert_send(Sniffer::new());
+    assert_send(
+        Sniffer::new()
+            .register_type_detector(NhsNumberDetector)
+            .clone(),
+    );
+
+    let data = "patient,nhs_number,phone,visits\n\
+                a,9434765919,0123456789,3\n\
+                b,4010232137,0987654321,1\n\
+                c,1234567881,0111222333,7\n";
+    let metadata = Sniffer::new()
+        .register_type_detector(TenDigitDetector)
+        .register_type_detector(NhsNumberDetector)
+        .sniff_reader(Cursor::new(data))
+        .unwrap();
+    assert_eq!(
+        metadata.types,
+        vec![
+            Type::Text,
+            Type::Custom("nhs_number"),
+            Type::Custom("ten_digits"),
+            Type::Unsigned
+        ]
+    );
+    assert_eq!(metadata.looks_like_code, vec![false; 4]);
+}
+
+#[test]
+fn test_custom_date_formats() {
+    let data = "id,shipped,day_of_year,logged\n\
+                1,05-Mar-21,2021-064,2021.03.05 14h30\n\
+                2,17-Apr-21,2021-107,2021.04.17 09h05\n\
+                3,30-Nov-21,2021-334,2021.11.30 23h59\n";
+    let metadata = Sniffer::new()
+        .date_formats(&["%d-%b-%y", "%Y-%j", "%Y.%m.%d %Hh%M"])
+        .sniff_reader(Cursor::new(data))
+        .unwrap();
+    assert_eq!(
+        metadata.types,
+        vec![Type::Unsigned, Type::Date, Type::Date, Type::DateTime]
+    );
+    assert_eq!(
+        metadata.date_formats,
+        vec![
+            None,
+            Some("%d-%b-%y".to_string()),
+            Some("%Y-%j".to_string()),
+            Some("%Y.%m.%d %Hh%M".to_string())
+        ]
+    );
+
+    // without the formats, these aren't dates (ordinal dates are known anyway)
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    assert_eq!(metadata.types[1], Type::Text);
+    assert_eq!(metadata.types[2], Type::Date);
+    assert_eq!(metadata.types[3], Type::Text);
+}
+
+#[test]
+fn test_locale() {
+    let data = "name\tamount\tfiled\tdue\n\
+                a\t1.234.567,89\t5 März 2021\t03.04.2021\n\
+                b\t12,5\t17 Okt 2021\t25.04.2021\n\
+                c\t3.000\t1 Dezember 2021\t01.12.2021\n";
+    let metadata = Sniffer::new()
+        .locale(Locale::De)
+        .sniff_reader(Cursor::new(data))
+        .unwrap();
+    assert_eq!(metadata.decimal_separator, b',');
+    assert_eq!(
+        metadata.types,
+        vec![Type::Text, Type::Float, Type::Date, Type::Date]
+    );
+    assert_eq!(
+        metadata.number_formats[1],
+        Some(NumberFormat {
+            decimal_separator: b',',
+            thousands_separator: Some(b'.'),
+        })
+    );
+    assert_eq!(metadata.date_formats[2], Some("%d %B %Y".to_string()));
+    assert_eq!(metadata.date_formats[3], Some("%d.%m.%Y".to_string()));
+    assert_eq!(
+        metadata.date_orders[3],
+        Some(DateOrder {
+            order: DatePreference::DmyFormat,
+            from_preference: false,
+        })
+    );
+
+    // French numbers are grouped with spaces, so other groupings aren't numbers
+    let data = "name\tamount\tother\n\
+                a\t1 234 567,89\t1'234\n\
+                b\t12,5\t5'678\n\
+                c\t3 000\t9'999\n";
+    let metadata = Sniffer::new()
+        .locale(Locale::Fr)
+        .sniff_reader(Cursor::new(data))
+        .unwrap();
+    assert_eq!(metadata.types, vec![Type::Text, Type::Float, Type::Text]);
+}
+
+#[test]
+fn test_geospatial() {
+    let data = "name\tgeom\tpickup_lat\tpickup_lng\tscore\n\
+                a\tPOINT (30 10)\t42.3601\t-71.0589\t12.5\n\
+                b\tSRID=4326;POLYGON ((30 10, 40 40, 20 40, 30 10))\t40.7128\t-74.006\t99.5\n\
+                c\tLINESTRING Z (30 10 1, 10 30 2)\t-33.8688\t151.2093\t150.25\n";
+    let metadata = Sniffer::new()
+        .delimiter(b'\t')
+        .sniff_reader(Cursor::new(data))
+        .unwrap();
+    assert_eq!(
+        metadata.types,
+        vec![Type::Text, Type::Wkt, Type::Float, Type::Float, Type::Float]
+    );
+    assert_eq!(
+        metadata.coordinates,
+        vec![
+            None,
+            None,
+            Some(Coordinate::Latitude),
+            Some(Coordinate::Longitude),
+            None
+        ]
+    );
+
+    // unnamed pair of latitude and longitude columns
+    let data = "id,a,b,c\n\
+                1,51.5074,-0.1278,500.5\n\
+                2,48.8566,2.3522,120.25\n\
+                3,35.6762,139.6503,7.5\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    assert_eq!(
+        metadata.coordinates,
+        vec![
+            None,
+            Some(Coordinate::Latitude),
+            Some(Coordinate::Longitude),
+            None
+        ]
+    );
+}
+
+#[test]
+fn test_duration() {
+    let data = "lap,split,ride_duration,billed,iso,opens\n\
+                1,01:23:45,00:45:10,3d 4h,PT2H30M,09:00:00\n\
+                2,27:15:00,01:02:03,1h30m,P3DT4H,10:30:00\n\
+                3,100:00:00.5,00:10:00,2 hours 5 minutes,P1W,08:15:00\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    assert_eq!(
+        metadata.types,
+        vec![
+            Type::Unsigned,
+            Type::Duration,
+            Type::Duration,
+            Type::Duration,
+            Type::Duration,
+            Type::Time
+        ]
+    );
+
+    // not durations
+    let data = "id,a,b,c\n\
+                1,PT,3h 4d,01:75:00\n\
+                2,P1H,1x,02:00:00\n\
+                3,PT1D,h,03:00:00\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    assert_eq!(
+        metadata.types,
+        vec![Type::Unsigned, Type::Text, Type::Text, Type::Text]
+    );
+}
+
+#[cfg(feature = "phone")]
+#[test]
+fn test_phone_numbers() {
+    let data = "name\tphone\tmobile\tnote\n\
+                a\t(415) 555-2671\t+14155552671\tcall 555-2671\n\
+                b\t+44 20 7946 0958\t+442079460958\t555-2671\n\
+                c\t020.7946.0958\t+33612345678\t(555\n";
+    let metadata = Sniffer::new()
+        .delimiter(b'\t')
+        .sniff_reader(Cursor::new(data))
+        .unwrap();
+    assert_eq!(metadata.phone_numbers, vec![false, true, true, false]);
+}
+
+#[test]
+fn test_exhaustive_types() {
+    let mut data = "id,count\n".to_string();
+    for i in 1..=50 {
+        data.push_str(&format!("{i},{}\n", i * 3));
+    }
+    data.push_str("51,unknown\n");
+    let metadata = Sniffer::new()
+        .sample_size(SampleSize::Records(10))
+        .sniff_reader(Cursor::new(&data))
+        .unwrap();
+    assert_eq!(metadata.types, vec![Type::Unsigned, Type::Unsigned]);
+
+    let metadata = Sniffer::new()
+        .sample_size(SampleSize::Records(10))
+        .exhaustive_types(true)
+        .sniff_reader(Cursor::new(&data))
+        .unwrap();
+    assert_eq!(metadata.types, vec![Type::Unsigned, Type::Text]);
+    assert_eq!(metadata.dialect.delimiter, b',');
+}
+
+#[test]
+fn test_type_unions() {
+    let mut data = "id,score\n".to_string();
+    for i in 1..=10 {
+        let score = match i {
+            1..=6 => i.to_string(),
+            7 => "0.5".to_string(),
+            _ => "pending".to_string(),
+        };
+        data.push_str(&format!("{i},{score}\n"));
+    }
+    let metadata = Sniffer::new()
+        .type_unions(true)
+        .sniff_reader(Cursor::new(&data))
+        .unwrap();
+    assert_eq!(metadata.types, vec![Type::Unsigned, Type::Text]);
+    assert_eq!(metadata.type_unions[0], None);
+    assert_eq!(
+        metadata.type_unions[1],
+        Some(vec![
+            TypeFit {
+                ty: Type::Float,
+                confidence: 0.7
+            },
+            TypeFit {
+                ty: Type::Text,
+                confidence: 0.3
+            },
+        ])
+    );
+
+    // not reported unless enabled
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(&data)).unwrap();
+    assert_eq!(metadata.type_unions, vec![None, None]);
+}
+
+#[test]
+fn test_empty_values() {
+    let data = "\
+id,name,note,code
+1,\"Ann\",,\"x\"
+2,\"\",,\"\"
+3,\"Cy\",\"\",\"y\"
+4,\"Di\",,
+";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    assert_eq!(
+        metadata.empty_values,
+        vec![
+            EmptyValues::Absent,
+            EmptyValues::Quoted,
+            EmptyValues::Mixed,
+            EmptyValues::Mixed
+        ]
+    );
+
+    let data = "id,name\n1,a\n2,\n3,c\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    assert_eq!(
+        metadata.empty_values,
+        vec![EmptyValues::Absent, EmptyValues::Unquoted]
+    );
+}
+
+#[test]
+fn test_non_finite_floats() {
+    let data = "name\treading\tratio\n\
+                a\t1.5\t0.25\n\
+                b\tNaN\t0.5\n\
+                c\t-Infinity\t0.75\n\
+                d\tinf\t1\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    assert_eq!(metadata.types, vec![Type::Text, Type::Float, Type::Float]);
+    assert_eq!(metadata.has_non_finite, vec![false, true, false]);
+
+    let metadata = Sniffer::new()
+        .non_finite_floats(false)
+        .sniff_reader(Cursor::new(data))
+        .unwrap();
+    assert_eq!(metadata.types, vec![Type::Text, Type::Text, Type::Float]);
+    assert_eq!(metadata.has_non_finite, vec![false; 3]);
+
+    // columns of nothing but non-finite values still have a header row
+    let data = "name,city\nInf,Nan\nNaN,Infinity\nnan,inf\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    assert!(metadata.dialect.header.has_header_row);
+    assert_eq!(metadata.fields, vec!["name", "city"]);
+    assert_eq!(metadata.types, vec![Type::Float, Type::Float]);
+    assert_eq!(metadata.has_non_finite, vec![true, true]);
+}
+
+#[test]
+fn test_prefixed_integers() {
+    let data = "hex,octal,offset,mixed,name\n\
+                0x1A2B,0o777,-0x10,0x1F,a\n\
+                0xff,0o17,0x20,0o17,b\n\
+                0X0,0O0,0x7FFFFFFFFF,0x2,c\n";
+    let metadata = Sniffer::new()
+        .prefixed_integers(true)
+        .sniff_reader(Cursor::new(data))
+        .unwrap();
+    assert_eq!(
+        metadata.types,
+        vec![
+            Type::Unsigned,
+            Type::Unsigned,
+            Type::Signed,
+            Type::Text,
+            Type::Text
+        ]
+    );
+    assert_eq!(
+        metadata.radixes,
+        vec![
+            Some(Radix::Hexadecimal),
+            Some(Radix::Octal),
+            Some(Radix::Hexadecimal),
+            None,
+            None
+        ]
+    );
+    assert_eq!(
+        metadata.integer_widths,
+        vec![
+            Some(IntegerWidth::I32),
+            Some(IntegerWidth::I32),
+            Some(IntegerWidth::I64),
+            None,
+            None
+        ]
+    );
+
+    // hexadecimal-looking identifiers are text by default
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    assert_eq!(metadata.types[..3], [Type::Text; 3]);
+    assert_eq!(metadata.radixes, vec![None; 5]);
+}
+
+#[test]
+fn test_semver() {
+    let data = "package,version,release,not_semver\n\
+                serde,1.0.197,v2.0.0-rc.1,1.2\n\
+                csv,1.3.0,1.0.0+build.5,01.2.3\n\
+                chrono,0.4.38,3.1.4-alpha.beta+exp.sha.5114f85,1.2.3.4\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    assert_eq!(
+        metadata.types,
+        vec![Type::Text, Type::SemVer, Type::SemVer, Type::Text]
+    );
+}
+
+#[test]
+fn test_iso_week_and_ordinal_dates() {
+    let data = "id,week,compact_week,ordinal\n\
+                1,2023-W05-1,2023W051,2023-123\n\
+                2,2023-W52-7,2023W527,2023-001\n\
+                3,2020-W53-5,2020W535,2020-366\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    assert_eq!(
+        metadata.types,
+        vec![Type::Unsigned, Type::Date, Type::Date, Type::Date]
+    );
+    assert_eq!(
+        metadata.date_formats,
+        vec![
+            None,
+            Some("%G-W%V-%u".to_string()),
+            Some("%GW%V%u".to_string()),
+            Some("%Y-%j".to_string())
+        ]
+    );
+}
+
+#[test]
+fn test_value_ranges() {
+    let data = "id\ttemperature\tday\tname\tok\n\
+                3\t-1.5\t05/03/2021\tpear\ttrue\n\
+                12\t22.25\t17/01/2021\tapple\tfalse\n\
+                7\t1,200.5\t01/12/2020\tzucchini\ttrue\n";
+    let metadata = Sniffer::new()
+        .date_preference(DatePreference::DmyFormat)
+        .delimiter(b'\t')
+        .sniff_reader(Cursor::new(data))
+        .unwrap();
+    let range = |min: &str, max: &str| {
+        Some(ValueRange {
+            min: min.to_string(),
+            max: max.to_string(),
+        })
+    };
+    assert_eq!(
+        metadata.value_ranges,
+        vec![
+            range("3", "12"),
+            range("-1.5", "1,200.5"),
+            range("01/12/2020", "05/03/2021"),
+            range("apple", "zucchini"),
+            None
+        ]
+    );
+}
+
+#[test]
+fn test_non_utf8() {
+    // Latin-1 accents, and a Windows-1252 smart quote
+    let mut data = b"name,city,visits\n".to_vec();
+    data.extend_from_slice(b"Ren\xe9,Montr\xe9al,1\nO\x92Brien,Cork,2\nAnn,Oslo,3\n");
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(&data)).unwrap();
+    assert!(!metadata.dialect.is_utf8);
+    assert_eq!(metadata.dialect.delimiter, b',');
+    assert_eq!(metadata.types, vec![Type::Text, Type::Text, Type::Unsigned]);
+    assert_eq!(
+        metadata.invalid_utf8,
+        Some(InvalidUtf8 {
+            locations: vec![
+                Utf8ErrorLocation {
+                    line: 2,
+                    offset: 20
+                },
+                Utf8ErrorLocation {
+                    line: 2,
+                    offset: 27
+                },
+                Utf8ErrorLocation {
+                    line: 3,
+                    offset: 34
+                },
+            ],
+            count: 3,
+        })
+    );
+    assert_eq!(
+        metadata.invalid_utf8.unwrap().to_string(),
+        "3 sequence(s) in the sample, first at line 2 (byte 20), line 2 (byte 27), line 3 (byte 34)"
+    );
+
+    // invalid bytes beyond the sample (and beyond where the encoding is detected) are only seen
+    // when inferring types from every row
+    let mut data = b"name,visits\n".to_vec();
+    for i in 0..2000 {
+        data.extend_from_slice(format!("user{i},{i}\n").as_bytes());
+    }
+    data.extend_from_slice(b"Jos\xe9,2000\n");
+    let metadata = Sniffer::new()
+        .sample_size(SampleSize::Records(5))
+        .sniff_reader(Cursor::new(&data))
+        .unwrap();
+    assert!(metadata.dialect.is_utf8);
+    assert_eq!(metadata.invalid_utf8, None);
+    let metadata = Sniffer::new()
+        .sample_size(SampleSize::Records(5))
+        .exhaustive_types(true)
+        .sniff_reader(Cursor::new(&data))
+        .unwrap();
+    assert!(!metadata.dialect.is_utf8);
+    let invalid_utf8 = metadata.invalid_utf8.unwrap();
+    assert_eq!(invalid_utf8.count, 1);
+    assert_eq!(invalid_utf8.locations[0].line, 2002);
+}
+
+#[test]
+fn test_encodings() {
+    let text = "name,city,visits\nRené,Zürich,1\nJosé,Montréal,2\nAnn,Oslo,3\n";
+    let utf16 = |big_endian: bool, bom: bool| {
+        let mut bytes = vec![];
+        for unit in bom.then_some(0xFEFF).into_iter().chain(text.encode_utf16()) {
+            bytes.extend(if big_endian {
+                u16::to_be_bytes(unit)
+            } else {
+                u16::to_le_bytes(unit)
+            });
+        }
+        bytes
+    };
+    for (bytes, encoding) in [
+        (text.as_bytes().to_vec(), Encoding::Utf8),
+        (utf16(false, true), Encoding::Utf16Le),
+        (utf16(true, false), Encoding::Utf16Be),
+    ] {
+        let metadata = Sniffer::new().sniff_reader(Cursor::new(&bytes)).unwrap();
+        assert_eq!(metadata.encoding, encoding);
+        assert_eq!(metadata.dialect.is_utf8, encoding == Encoding::Utf8);
+        assert_eq!(metadata.fields, vec!["name", "city", "visits"]);
+        assert_eq!(metadata.types, vec![Type::Text, Type::Text, Type::Unsigned]);
+        assert_eq!(metadata.num_fields, 3);
+    }
+
+    // the same text in windows-1252 (which is what Latin-1 files usually are)
+    let windows_1252 = text
+        .chars()
+        .map(|chr| u8::try_from(u32::from(chr)).unwrap())
+        .collect::<Vec<_>>();
+    let metadata = Sniffer::new()
+        .sniff_reader(Cursor::new(&windows_1252))
+        .unwrap();
+    assert_eq!(metadata.encoding, Encoding::Windows1252);
+    assert_eq!(metadata.encoding.to_string(), "windows-1252");
+
+    // "東京,1" and "大阪,2" in Shift-JIS, which isn't detected (or decoded)
+    let shift_jis = b"city,n\n\x93\x8c\x8b\x9e,1\n\x91\xe5\x8d\xe3,2\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(shift_jis)).unwrap();
+    assert_eq!(metadata.encoding, Encoding::Windows1252);
+    assert_eq!(metadata.types, vec![Type::Text, Type::Unsigned]);
+}
+
+#[test]
+fn test_byte_order_marks() {
+    let data = "\u{feff}id,name\n1,a\n2,b\n3,c\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    assert_eq!(metadata.bom, Some(Encoding::Utf8));
+    assert_eq!(metadata.encoding, Encoding::Utf8);
+    assert_eq!(metadata.fields, vec!["id", "name"]);
+    assert_eq!(metadata.types, vec![Type::Unsigned, Type::Text]);
+
+    // without a header row, the first value isn't mistaken for text
+    let data = "\u{feff}1,2.5\n2,3.5\n3,4.5\n";
+    let metadata = Sniffer::new()
+        .header(&Header {
+            has_header_row: false,
+            num_preamble_rows: 0,
+            num_comment_preamble_rows: 0,
+        })
+        .sniff_reader(Cursor::new(data))
+        .unwrap();
+    assert_eq!(metadata.types, vec![Type::Unsigned, Type::Float]);
+
+    let mut data = vec![0xFF, 0xFE];
+    for unit in "id,name\n1,a\n2,b\n".encode_utf16() {
+        data.extend(unit.to_le_bytes());
+    }
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    assert_eq!(metadata.bom, Some(Encoding::Utf16Le));
+    assert_eq!(metadata.fields, vec!["id", "name"]);
+
+    let metadata = Sniffer::new()
+        .sniff_reader(Cursor::new("id,name\n1,a\n2,b\n"))
+        .unwrap();
+    assert_eq!(metadata.bom, None);
+}
+
+#[test]
+fn test_decoded_reader() {
+    let data = b"name,quote\nRen\xe9,It\x92s\nAnn,ok\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    assert_eq!(metadata.encoding, Encoding::Windows1252);
+    let mut reader = metadata.open_reader(Cursor::new(data), true).unwrap();
+    let records = reader
+        .records()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap()
+        .iter()
+        .map(|record| record.iter().map(str::to_string).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        records,
+        vec![vec!["René", "It\u{2019}s"], vec!["Ann", "ok"]]
+    );
+
+    // read as-is unless decoding
+    let mut reader = metadata.open_reader(Cursor::new(data), false).unwrap();
+    let record = reader.byte_records().next().unwrap().unwrap();
+    assert_eq!(&record[0], b"Ren\xe9");
+
+    let mut data = vec![0xFE, 0xFF];
+    for unit in "id,name\n1,Zoë\n2,b\n".encode_utf16() {
+        data.extend(unit.to_be_bytes());
+    }
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(&data)).unwrap();
+    let mut reader = metadata.open_reader(Cursor::new(&data), true).unwrap();
+    assert_eq!(reader.headers().unwrap(), vec!["id", "name"]);
+    let record = reader.records().next().unwrap().unwrap();
+    assert_eq!(record, vec!["1", "Zoë"]);
+
+    let shift_jis = b"city,n\n\x93\x8c\x8b\x9e,1\n\x91\xe5\x8d\xe3,2\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(shift_jis)).unwrap();
+    // every encoding which can be sniffed can be decoded
+    let mut reader = metadata.open_reader(Cursor::new(shift_jis), true).unwrap();
+    assert_eq!(reader.records().count(), 2);
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn test_gzip() {
+    use std::io::Write;
+
+    let mut data = "# exported 2024-01-01\nid;name;score\n".to_string();
+    for i in 0..5000 {
+        data.push_str(&format!("{i};name {i};{}.5\n", i % 100));
+    }
+    // recognized by its magic bytes, not its name
+    let path = std::env::temp_dir().join(format!("qsv-sniffer-{}-gzip.csv", std::process::id()));
+    let mut encoder = flate2::write::GzEncoder::new(
+        std::fs::File::create(&path).unwrap(),
+        flate2::Compression::default(),
+    );
+    encoder.write_all(data.as_bytes()).unwrap();
+    encoder.finish().unwrap();
+
+    for sample_size in [
+        SampleSize::Bytes(1 << 12),
+        SampleSize::Records(50),
+        SampleSize::All,
+    ] {
+        let metadata = Sniffer::new()
+            .sample_size(sample_size)
+            .sniff_path(&path)
+            .unwrap();
+        assert_eq!(metadata.dialect.delimiter, b';');
+        assert_eq!(metadata.dialect.header.num_preamble_rows, 1);
+        assert_eq!(metadata.fields, vec!["id", "name", "score"]);
+        assert_eq!(
+            metadata.types,
+            vec![Type::Unsigned, Type::Text, Type::Float]
+        );
+
+        let mut reader = metadata.dialect.open_gzip_path(&path).unwrap();
+        assert_eq!(reader.headers().unwrap(), vec!["id", "name", "score"]);
+        assert_eq!(reader.records().count(), 5000);
+    }
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_unsupported_compression() {
+    let magic_bytes: [(&[u8], Compression); 3] = [
+        (&[0x28, 0xB5, 0x2F, 0xFD, 0x04, 0x58], Compression::Zstd),
+        (b"BZh91AY&SY\x8a\x1b", Compression::Bzip2),
+        (
+            &[0xFD, b'7', b'z', b'X', b'Z', 0x00, 0x00, 0x04],
+            Compression::Xz,
+        ),
+    ];
+    for (i, (magic, compression)) in magic_bytes.into_iter().enumerate() {
+        let path = std::env::temp_dir().join(format!("qsv-sniffer-{}-{i}.csv", std::process::id()));
+        std::fs::write(&path, magic).unwrap();
+        match Sniffer::new().sniff_path(&path) {
+            Err(qsv_sniffer::error::SnifferError::UnsupportedCompression(c)) => {
+                assert_eq!(c, compression);
+            }
+            result => panic!("expected an unsupported compression error, got {result:?}"),
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    // plain text starting like a bzip2 header is sniffed as usual
+    let path = std::env::temp_dir().join(format!("qsv-sniffer-{}-bzh.csv", std::process::id()));
+    std::fs::write(&path, "BZh,b\n1,2\n3,4\n").unwrap();
+    let metadata = Sniffer::new().sniff_path(&path).unwrap();
+    assert_eq!(metadata.fields, vec!["BZh", "b"]);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_unseekable_reader() {
+    // a reader without `Seek`, as for standard input
+    struct Pipe<'a>(&'a [u8]);
+    impl std::io::Read for Pipe<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.0.read(buf)
+        }
+    }
+
+    let mut data = "# exported 2024-01-01\nid|name|score\n".to_string();
+    for i in 0..5000 {
+        data.push_str(&format!("{i}|name {i}|{}.5\n", i % 100));
+    }
+    for sample_size in [
+        SampleSize::Bytes(1 << 12),
+        SampleSize::Records(50),
+        SampleSize::All,
+    ] {
+        let metadata = Sniffer::new()
+            .sample_size(sample_size)
+            .sniff_reader_unseekable(Pipe(data.as_bytes()))
+            .unwrap();
+        assert_eq!(metadata.dialect.delimiter, b'|');
+        assert_eq!(metadata.dialect.header.num_preamble_rows, 1);
+        assert_eq!(metadata.fields, vec!["id", "name", "score"]);
+        assert_eq!(
+            metadata.types,
+            vec![Type::Unsigned, Type::Text, Type::Float]
+        );
+
+        let mut reader = Sniffer::new()
+            .sample_size(sample_size)
+            .open_reader_unseekable(Pipe(data.as_bytes()))
+            .unwrap();
+        assert_eq!(reader.headers().unwrap(), vec!["id", "name", "score"]);
+        let records = reader.records().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(records.len(), 5000);
+        assert_eq!(&records[4999][1], "name 4999");
+    }
+}
+
+#[test]
+fn test_sample_buffer() {
+    let mut data = "# exported 2024-01-01\nid|name|score\n".to_string();
+    for i in 0..5000 {
+        data.push_str(&format!("{i}|name {i}|{}.5\n", i % 100));
+    }
+    let cases = [
+        (SampleSize::Bytes(1 << 12), None),
+        (SampleSize::Records(50), None),
+        (SampleSize::Records(50), Some(SkipStart::Rows(1))),
+        (SampleSize::Records(50), Some(SkipStart::Bytes(5))),
+        (SampleSize::All, None),
+    ];
+    for (sample_size, skip) in cases {
+        let new_sniffer = || {
+            let mut sniffer = Sniffer::new();
+            sniffer.sample_size(sample_size);
+            if let Some(skip) = skip {
+                sniffer.skip_start(skip);
+            }
+            sniffer
+        };
+        // pushed in chunks, as they'd be read from an asynchronous reader
+        let mut buffer = new_sniffer().sample_buffer();
+        let mut n_pushed = 0;
+        for chunk in data.as_bytes().chunks(1000) {
+            let n_kept = buffer.push(chunk);
+            n_pushed += n_kept;
+            if buffer.is_full() {
+                break;
+            }
+            assert_eq!(n_kept, chunk.len());
+        }
+        assert_eq!(buffer.as_bytes(), &data.as_bytes()[..n_pushed]);
+        assert_eq!(buffer.is_full(), !matches!(sample_size, SampleSize::All));
+        if buffer.is_full() {
+            // nothing more is needed
+            assert_eq!(buffer.push(b"1|x|2.5\n"), 0);
+        }
+        // the same as reading the stream
+        let metadata = new_sniffer().sniff_sample_buffer(&buffer).unwrap();
+        assert_eq!(
+            metadata,
+            new_sniffer()
+                .sniff_reader_unseekable(data.as_bytes())
+                .unwrap()
+        );
+        assert_eq!(metadata.fields, vec!["id", "name", "score"]);
+        assert_eq!(
+            metadata.types,
+            vec![Type::Unsigned, Type::Text, Type::Float]
+        );
+    }
+
+    // a stream ending before the buffer is full is sniffed whole
+    let mut sniffer = Sniffer::new();
+    sniffer.sample_size(SampleSize::Records(50));
+    let mut buffer = sniffer.sample_buffer();
+    buffer.push(b"a,b\n1,2\n3,4");
+    assert!(!buffer.is_full());
+    let metadata = sniffer.sniff_sample_buffer(&buffer).unwrap();
+    assert_eq!(metadata.fields, vec!["a", "b"]);
+    // including the last line, though it doesn't end with a line break
+    assert_eq!(
+        metadata.value_ranges[0],
+        Some(ValueRange {
+            min: "1".to_string(),
+            max: "3".to_string()
+        })
+    );
+    assert_eq!(buffer.into_bytes(), b"a,b\n1,2\n3,4");
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn test_sniff_url() {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    let mut data = "id,city,population\n".to_string();
+    for i in 0..5000 {
+        data.push_str(&format!("{i},city {i},{}\n", i * 7));
+    }
+    data.push_str("Total,87482500\n");
+    let data_len = data.len();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/old/cities.csv", listener.local_addr().unwrap());
+    // a sample and the tail for the first two sample sizes (both redirected the first time), and
+    // only a sample for the last one
+    let server = std::thread::spawn(move || {
+        let mut requests = vec![];
+        let mut n_whole = 0;
+        for stream in listener.incoming().take(7) {
+            let mut stream = stream.unwrap();
+            let mut path = String::new();
+            let mut range = None;
+            for line in BufReader::new(&stream).lines() {
+                let line = line.unwrap();
+                if line.is_empty() {
+                    break;
+                }
+                if let Some(request) = line.strip_prefix("GET ") {
+                    path = request.split(' ').next().unwrap().to_string();
+                }
+                if let Some(value) = line.strip_prefix("Range: bytes=") {
+                    range = Some(value.to_string());
+                }
+            }
+            requests.push((path.clone(), range.clone()));
+            if path == "/old/cities.csv" {
+                write!(
+                    stream,
+                    "HTTP/1.1 301 Moved Permanently\r\nLocation: /cities.csv\r\n\
+                     Content-Length: 0\r\n\r\n"
+                )
+                .unwrap();
+                continue;
+            }
+            let (start, end) = match range.as_deref().map(|range| range.split_once('-').unwrap()) {
+                Some(("", len)) => (data_len - len.parse::<usize>().unwrap(), data_len - 1),
+                Some((start, end)) => (start.parse().unwrap(), end.parse().unwrap()),
+                None => (0, data_len - 1),
+            };
+            let body = &data.as_bytes()[start..=end.min(data_len - 1)];
+            if range.is_none() {
+                n_whole += 1;
+            }
+            if n_whole == 2 {
+                // chunked
+                n_whole += 1;
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n"
+                )
+                .unwrap();
+                for chunk in body.chunks(1000) {
+                    write!(stream, "{:x}\r\n", chunk.len()).unwrap();
+                    stream.write_all(chunk).unwrap();
+                    write!(stream, "\r\n").unwrap();
+                }
+                write!(stream, "0\r\n\r\n").unwrap();
+            } else {
+                let status = if range.is_some() {
+                    format!(
+                        "206 Partial Content\r\nContent-Range: bytes {start}-{}/{data_len}",
+                        start + body.len() - 1
+                    )
+                } else {
+                    "200 OK".to_string()
+                };
+                write!(
+                    stream,
+                    "HTTP/1.1 {status}\r\nContent-Length: {}\r\n\r\n",
+                    body.len()
+                )
+                .unwrap();
+                // the client may hang up once it has its sample
+                let _ = stream.write_all(body);
+            }
+        }
+        requests
+    });
+
+    let mut url = url;
+    for sample_size in [
+        SampleSize::Bytes(1 << 12),
+        SampleSize::Records(50),
+        SampleSize::All,
+    ] {
+        let metadata = Sniffer::new()
+            .sample_size(sample_size)
+            .sniff_url(&url)
+            .unwrap();
+        assert_eq!(metadata.dialect.delimiter, b',');
+        assert_eq!(metadata.fields, vec!["id", "city", "population"]);
+        assert_eq!(
+            metadata.types,
+            vec![Type::Unsigned, Type::Text, Type::Unsigned]
+        );
+        // found in the tail asked for separately, unless the whole file is sampled
+        assert_eq!(metadata.num_footer_rows, 1);
+        url = url.replace("/old/", "/");
+    }
+    let requests = server.join().unwrap();
+    let paths = requests.iter().map(|(path, _)| path).collect::<Vec<_>>();
+    assert_eq!(
+        paths[..4],
+        [
+            "/old/cities.csv",
+            "/cities.csv",
+            "/old/cities.csv",
+            "/cities.csv"
+        ]
+    );
+    let ranges = requests
+        .iter()
+        .map(|(_, range)| range.as_deref())
+        .collect::<Vec<_>>();
+    assert!(ranges[0].is_some_and(|range| range.starts_with("0-")));
+    assert_eq!(ranges[1], ranges[0]);
+    assert_eq!(
+        ranges[2..],
+        [Some("-4096"), Some("-4096"), None, Some("-4096"), None]
+    );
+
+    assert!(Sniffer::new()
+        .sniff_url("https://example.com/a.csv")
+        .is_err());
+}
+
+#[test]
+fn test_sniff_bytes() {
+    let mut data = "id;name;amount\n".to_string();
+    for i in 0..200 {
+        data.push_str(&format!("{i};name {i};{i}.25\n"));
+    }
+    let metadata = Sniffer::new().sniff_bytes(data.as_bytes()).unwrap();
+    assert_eq!(metadata.dialect.delimiter, b';');
+    assert_eq!(metadata.fields, vec!["id", "name", "amount"]);
+    assert_eq!(
+        metadata.types,
+        vec![Type::Unsigned, Type::Text, Type::Float]
+    );
+
+    // cut off within a record, as when reading the start of an upload
+    let cut = &data.as_bytes()[..data.len() - 7];
+    let metadata = Sniffer::new().sniff_bytes(cut).unwrap();
+    assert_eq!(metadata.dialect.delimiter, b';');
+    assert!(!metadata.dialect.flexible);
+    assert_eq!(
+        metadata.types,
+        vec![Type::Unsigned, Type::Text, Type::Float]
+    );
+}
+
+#[test]
+fn test_utf16_reader() {
+    // as exported by Excel ("Unicode Text"): tab-separated UTF-16LE with a byte order mark
+    let text = "name\tcity\tvisits\r\nRené\tZürich\t1\r\nJosé\tMontréal\t2\r\n李\t北京\t3\r\n";
+    let mut bytes = vec![0xFF, 0xFE];
+    for unit in text.encode_utf16() {
+        bytes.extend(unit.to_le_bytes());
+    }
+    let mut reader = Sniffer::new().open_reader(Cursor::new(&bytes)).unwrap();
+    assert_eq!(reader.headers().unwrap(), vec!["name", "city", "visits"]);
+    let records = reader.records().collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(records.len(), 3);
+    assert_eq!(records[1], vec!["José", "Montréal", "2"]);
+    assert_eq!(records[2], vec!["李", "北京", "3"]);
+
+    // UTF-16BE, without a byte order mark
+    let mut bytes = vec![];
+    for unit in text.encode_utf16() {
+        bytes.extend(unit.to_be_bytes());
+    }
+    let mut reader = Sniffer::new().open_reader(Cursor::new(&bytes)).unwrap();
+    assert_eq!(reader.headers().unwrap(), vec!["name", "city", "visits"]);
+    assert_eq!(reader.records().count(), 3);
+}
+
+#[test]
+fn test_not_textual() {
+    // the signature and header chunk of a PNG image, followed by (pseudo-random) image data
+    let mut png = b"\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR\x00\x00\x01\x00".to_vec();
+    let mut state = 42u32;
+    for _ in 0..4000 {
+        state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+        png.push((state >> 24) as u8);
+    }
+    match Sniffer::new().sniff_bytes(&png) {
+        Err(qsv_sniffer::error::SnifferError::NotTextual(offsets)) => {
+            assert_eq!(offsets[..4], [6, 8, 9, 10]);
+        }
+        result => panic!("expected a not textual error, got {result:?}"),
+    }
+
+    // a stray NUL byte, and ASCII separator characters, are fine
+    let data = b"id\x1fname\x1e1\x1fa\x00\x1e2\x1fb\x1e3\x1fc\x1e4\x1fd\x1e";
+    assert!(Sniffer::new().sniff_bytes(data).is_ok());
+}
+
+#[test]
+fn test_raw_field_names() {
+    // windows-1252 field names
+    let data =
+        b"Stra\xdfe,Gr\xf6\xdfe,Jahr\nHauptstra\xdfe,12.5,2021\nRing,7.25,2022\nAllee,3.5,2023\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(&data)).unwrap();
+    assert_eq!(
+        metadata.fields,
+        vec!["Stra\u{FFFD}e", "Gr\u{FFFD}\u{FFFD}e", "Jahr"]
+    );
+    assert_eq!(
+        metadata.fields_raw,
+        vec![
+            b"Stra\xdfe".to_vec(),
+            b"Gr\xf6\xdfe".to_vec(),
+            b"Jahr".to_vec()
+        ]
+    );
+
+    // no header row, no field names
+    let metadata = Sniffer::new()
+        .header(&Header {
+            has_header_row: false,
+            num_preamble_rows: 0,
+            num_comment_preamble_rows: 0,
+        })
+        .sniff_reader(Cursor::new(b"1,2\n3,4\n5,6\n"))
+        .unwrap();
+    assert!(metadata.fields.is_empty());
+    assert!(metadata.fields_raw.is_empty());
+}
+
+#[cfg(feature = "snappy")]
+#[test]
+fn test_snappy() {
+    // a minimal Snappy compressor: literals, and copies of earlier runs of 4 or more bytes
+    fn compress_block(input: &[u8]) -> Vec<u8> {
+        fn push_literal(block: &mut Vec<u8>, literal: &[u8]) {
+            if literal.is_empty() {
+                return;
+            }
+            let len = literal.len() - 1;
+            if len < 60 {
+                block.push((len as u8) << 2);
+            } else {
+                block.push(61 << 2);
+                block.extend((len as u16).to_le_bytes());
+            }
+            block.extend_from_slice(literal);
+        }
+        let mut block = vec![];
+        let mut len = input.len();
+        while len >= 0x80 {
+            block.push((len as u8) | 0x80);
+            len >>= 7;
+        }
+        block.push(len as u8);
+        let mut last_seen = std::collections::HashMap::new();
+        let (mut pos, mut literal_start) = (0, 0);
+        while pos + 4 <= input.len() {
+            match last_seen.insert(&input[pos..pos + 4], pos) {
+                Some(prev) if pos - prev < 1 << 16 => {
+                    let mut copy_len = 4;
+                    while copy_len < 64
+                        && pos + copy_len < input.len()
+                        && input[prev + copy_len] == input[pos + copy_len]
+                    {
+                        copy_len += 1;
+                    }
+                    push_literal(&mut block, &input[literal_start..pos]);
+                    block.push(((copy_len - 1) as u8) << 2 | 2);
+                    block.extend(((pos - prev) as u16).to_le_bytes());
+                    pos += copy_len;
+                    literal_start = pos;
+                }
+                _ => pos += 1,
+            }
+        }
+        push_literal(&mut block, &input[literal_start..]);
+        block
+    }
+
+    let mut data = "id,name,score\n".to_string();
+    for i in 0..5000 {
+        data.push_str(&format!("{i},name {i},{}.5\n", i % 100));
+    }
+    let mut stream = b"\xff\x06\x00\x00sNaPpY".to_vec();
+    for (i, chunk) in data.as_bytes().chunks(1 << 15).enumerate() {
+        // compressed chunks, with an uncompressed one and some padding
+        let (chunk_type, body) = if i == 1 {
+            (0x01, chunk.to_vec())
+        } else {
+            (0x00, compress_block(chunk))
+        };
+        stream.push(chunk_type);
+        stream.extend(&((body.len() + 4) as u32).to_le_bytes()[..3]);
+        stream.extend([0; 4]);
+        stream.extend(body);
+        stream.extend(b"\xfe\x02\x00\x00\x00\x00");
+    }
+    let path = std::env::temp_dir().join(format!("qsv-sniffer-{}.csv.sz", std::process::id()));
+    std::fs::write(&path, &stream).unwrap();
+
+    for sample_size in [SampleSize::Bytes(1 << 12), SampleSize::All] {
+        let metadata = Sniffer::new()
+            .sample_size(sample_size)
+            .sniff_path(&path)
+            .unwrap();
+        assert_eq!(metadata.dialect.delimiter, b',');
+        assert_eq!(metadata.fields, vec!["id", "name", "score"]);
+        assert_eq!(
+            metadata.types,
+            vec![Type::Unsigned, Type::Text, Type::Float]
+        );
+
+        let mut reader = metadata.dialect.open_snappy_path(&path).unwrap();
+        let records = reader.records().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(records.len(), 5000);
+        assert_eq!(records[4321], vec!["4321", "name 4321", "21.5"]);
+    }
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[cfg(feature = "zip")]
+#[test]
+fn test_zip() {
+    use std::io::Write;
+
+    // a ZIP archive of a readme (stored), a CSV file (deflated) and a TSV file (stored)
+    let mut csv_data = "station,date,rainfall\n".to_string();
+    for i in 0..3000 {
+        csv_data.push_str(&format!(
+            "S{},2024-01-{:02},{}.{}\n",
+            i % 7,
+            i % 28 + 1,
+            i % 40,
+            i % 10
+        ));
+    }
+    let mut deflated = flate2::write::DeflateEncoder::new(vec![], flate2::Compression::default());
+    deflated.write_all(csv_data.as_bytes()).unwrap();
+    let deflated = deflated.finish().unwrap();
+    let entries: [(&str, u16, &[u8], usize); 3] = [
+        ("readme.txt", 0, b"Rainfall by station", 19),
+        ("data/rainfall.CSV", 8, &deflated, csv_data.len()),
+        (
+            "codes.tsv",
+            0,
+            b"code\tname\nS0\tNorth\nS1\tSouth\nS2\tEast\n",
+            36,
+        ),
+    ];
+    let (mut archive, mut directory) = (vec![], vec![]);
+    for (name, method, data, len) in entries {
+        let offset = archive.len() as u32;
+        let header = |signature: u32, central: bool| {
+            let mut header = signature.to_le_bytes().to_vec();
+            if central {
+                header.extend(20u16.to_le_bytes());
+            }
+            header.extend(20u16.to_le_bytes());
+            header.extend(0u16.to_le_bytes());
+            header.extend(method.to_le_bytes());
+            header.extend([0; 8]); // time, date and (unchecked) CRC-32
+            header.extend((data.len() as u32).to_le_bytes());
+            header.extend((len as u32).to_le_bytes());
+            header.extend((name.len() as u16).to_le_bytes());
+            header.extend(0u16.to_le_bytes());
+            if central {
+                header.extend([0; 10]);
+                header.extend(offset.to_le_bytes());
+            }
+            header.extend(name.as_bytes());
+            header
+        };
+        archive.extend(header(0x0403_4b50, false));
+        archive.extend(data);
+        directory.extend(header(0x0201_4b50, true));
+    }
+    let directory_offset = archive.len() as u32;
+    archive.extend(&directory);
+    archive.extend(0x0605_4b50u32.to_le_bytes());
+    archive.extend([0; 4]);
+    archive.extend(3u16.to_le_bytes());
+    archive.extend(3u16.to_le_bytes());
+    archive.extend((directory.len() as u32).to_le_bytes());
+    archive.extend(directory_offset.to_le_bytes());
+    archive.extend(0u16.to_le_bytes());
+    let path = std::env::temp_dir().join(format!("qsv-sniffer-{}.zip", std::process::id()));
+    std::fs::write(&path, &archive).unwrap();
+
+    // the first CSV file, through sniff_path too
+    for metadata in [
+        Sniffer::new().sniff_zip_path(&path, None).unwrap(),
+        Sniffer::new().sniff_path(&path).unwrap(),
+    ] {
+        assert_eq!(metadata.zip_entry.as_deref(), Some("data/rainfall.CSV"));
+        assert_eq!(metadata.dialect.delimiter, b',');
+        assert_eq!(metadata.fields, vec!["station", "date", "rainfall"]);
+        assert_eq!(metadata.types, vec![Type::Text, Type::Date, Type::Float]);
+        let mut reader = metadata
+            .dialect
+            .open_zip_path(&path, "data/rainfall.CSV")
+            .unwrap();
+        assert_eq!(reader.records().count(), 3000);
+    }
+
+    let metadata = Sniffer::new()
+        .sniff_zip_path(&path, Some("codes.tsv"))
+        .unwrap();
+    assert_eq!(metadata.zip_entry.as_deref(), Some("codes.tsv"));
+    assert_eq!(metadata.dialect.delimiter, b'\t');
+    assert_eq!(metadata.fields, vec!["code", "name"]);
+
+    assert!(Sniffer::new()
+        .sniff_zip_path(&path, Some("missing.csv"))
+        .is_err());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_mixed_terminators_normalized() {
+    // records alternately ending with `\r\n`, a bare `\r` and `\n`
+    let mut data = "id,name,score\r\n".to_string();
+    for i in 0..30 {
+        let terminator = ["\r\n", "\r", "\n"][i % 3];
+        data.push_str(&format!("{i},name {i},{i}.5{terminator}"));
+    }
+    let metadata = Sniffer::new().sniff_bytes(data.as_bytes()).unwrap();
+    assert_eq!(metadata.dialect.terminator, Terminator::Mixed);
+    assert!(!metadata.dialect.flexible);
+    assert_eq!(metadata.num_fields, 3);
+    assert_eq!(metadata.fields, vec!["id", "name", "score"]);
+    assert_eq!(
+        metadata.types,
+        vec![Type::Unsigned, Type::Text, Type::Float]
+    );
+
+    let mut reader = metadata.dialect.open_reader(Cursor::new(&data)).unwrap();
+    assert_eq!(reader.records().count(), 30);
+}
+
+#[test]
+fn test_utf8_handling() {
+    // windows-1252 accents
+    let data = b"id,name\n1,Caf\xe9\n2,Caf\xe8\n3,Caf\xe9\n4,Caf\xe8\n";
+
+    // lossily, the names are all the same
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    assert!(!metadata.dialect.is_utf8);
+    assert_eq!(metadata.types, vec![Type::Unsigned, Type::Text]);
+    assert_eq!(
+        metadata.value_ranges[1],
+        Some(ValueRange {
+            min: "Caf\u{FFFD}".to_string(),
+            max: "Caf\u{FFFD}".to_string(),
+        })
+    );
+
+    // byte by byte, they're distinct
+    let metadata = Sniffer::new()
+        .utf8_handling(Utf8Handling::Raw)
+        .sniff_reader(Cursor::new(data))
+        .unwrap();
+    assert!(!metadata.dialect.is_utf8);
+    assert_eq!(metadata.types, vec![Type::Unsigned, Type::Text]);
+    assert_eq!(
+        metadata.value_ranges[1],
+        Some(ValueRange {
+            min: "Cafè".to_string(),
+            max: "Café".to_string(),
+        })
+    );
+
+    match Sniffer::new()
+        .utf8_handling(Utf8Handling::Strict)
+        .sniff_reader(Cursor::new(data))
+    {
+        Err(qsv_sniffer::error::SnifferError::InvalidUtf8(invalid_utf8)) => {
+            assert_eq!(invalid_utf8.count, 4);
+            assert_eq!(invalid_utf8.locations[0].line, 2);
+        }
+        result => panic!("expected an invalid UTF-8 error, got {result:?}"),
+    }
+    // field names are decoded the same way
+    let data = b"id,caf\xe9\n1,a\n2,b\n3,c\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    assert_eq!(metadata.fields, vec!["id", "caf\u{FFFD}"]);
+    let metadata = Sniffer::new()
+        .utf8_handling(Utf8Handling::Raw)
+        .sniff_reader(Cursor::new(data))
+        .unwrap();
+    assert_eq!(metadata.fields, vec!["id", "caf\u{e9}"]);
+
+    // valid UTF-8 is fine
+    assert!(Sniffer::new()
+        .utf8_handling(Utf8Handling::Strict)
+        .sniff_reader(Cursor::new("id,name\n1,Café\n2,Cafè\n"))
+        .is_ok());
+}
+
+#[test]
+fn test_is_ascii() {
+    let metadata = Sniffer::new()
+        .sniff_reader(Cursor::new("id,name\n1,Cafe\n2,Bar\n3,Pub\n"))
+        .unwrap();
+    assert!(metadata.is_ascii);
+
+    let metadata = Sniffer::new()
+        .sniff_reader(Cursor::new("id,name\n1,Café\n2,Bar\n3,Pub\n"))
+        .unwrap();
+    assert!(metadata.dialect.is_utf8);
+    assert!(!metadata.is_ascii);
+
+    // a byte order mark isn't ASCII either
+    let metadata = Sniffer::new()
+        .sniff_reader(Cursor::new("\u{FEFF}id,name\n1,Cafe\n2,Bar\n3,Pub\n"))
+        .unwrap();
+    assert!(!metadata.is_ascii);
+
+    // nor is UTF-16, even of ASCII text
+    let mut data = vec![];
+    for unit in "id,name\n1,Cafe\n2,Bar\n3,Pub\n".encode_utf16() {
+        data.extend(unit.to_le_bytes());
+    }
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    assert!(!metadata.is_ascii);
+}
+
+#[test]
+fn test_windows_1252_curly_quotes() {
+    // a quoted curly quote isn't mistaken for a delimiter between quotes
+    let data = b"id,quote,author\n1,\"He said \x93hi\x94\",Ann\n2,\"\x93\",Bob\n3,\"x\",Cid\n\
+                 4,\"\x94 too\",Dee\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(&data[..])).unwrap();
+    assert!(!metadata.dialect.is_utf8);
+    assert_eq!(metadata.dialect.delimiter, b',');
+    assert_eq!(metadata.num_fields, 3);
+
+    // nor are unquoted curly quotes taken for quote characters
+    let data = b"id;quote;author\n1;\x93Hello\x94;Ann\n2;\x93Bye\x94;Bob\n3;Fine;Cid\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(&data[..])).unwrap();
+    assert_eq!(metadata.dialect.delimiter, b';');
+    assert_eq!(metadata.dialect.quote, Quote::None);
+    assert_eq!(metadata.fields, vec!["id", "quote", "author"]);
+
+    // while balanced straight quotes around curly ones are still detected
+    let data = b"id,quote,author\n1,\"\x93Hi\x94\",\"Ann\"\n2,\"\x93Bye\x94\",\"Bob\"\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(&data[..])).unwrap();
+    assert_eq!(metadata.dialect.quote, Quote::Some(b'"'));
+    assert_eq!(metadata.types, vec![Type::Unsigned, Type::Text, Type::Text]);
+}
+
+#[test]
+fn test_encoding_candidates() {
+    let metadata = Sniffer::new()
+        .sniff_reader(Cursor::new("id,name\n1,Café\n2,Bar\n"))
+        .unwrap();
+    assert_eq!(
+        metadata.encoding_candidates,
+        vec![EncodingCandidate {
+            encoding: Encoding::Utf8,
+            confidence: 1.0,
+        }]
+    );
+
+    // accented letters are the same in windows-1252 and ISO-8859-15
+    let data = b"id,name\n1,Caf\xe9\n2,Z\xfcrich\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(&data[..])).unwrap();
+    assert_eq!(metadata.encoding, Encoding::Windows1252);
+    assert_eq!(
+        metadata
+            .encoding_candidates
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>(),
+        vec!["windows-1252(0.50)", "ISO-8859-15(0.50)"]
+    );
+
+    // 0xA4 is `€` in ISO-8859-15 (and `¤` in windows-1252)
+    let data = b"id,price\n1,\xa45\n2,Caf\xe9\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(&data[..])).unwrap();
+    assert_eq!(metadata.encoding, Encoding::Iso8859_15);
+    assert_eq!(metadata.encoding_candidates.len(), 2);
+    assert!(metadata.encoding_candidates[0].confidence > 0.6);
+    assert_eq!(
+        metadata
+            .open_reader(Cursor::new(&data[..]), true)
+            .unwrap()
+            .records()
+            .next()
+            .unwrap()
+            .unwrap()[1],
+        *"€5"
+    );
+
+    // while curly quotes (0x93 and 0x94) are control characters in ISO-8859-15
+    let data = b"id,quote\n1,\x93Hi\x94\n2,Caf\xe9\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(&data[..])).unwrap();
+    assert_eq!(
+        metadata.encoding_candidates,
+        vec![
+            EncodingCandidate {
+                encoding: Encoding::Windows1252,
+                confidence: 0.75,
+            },
+            EncodingCandidate {
+                encoding: Encoding::Iso8859_15,
+                confidence: 0.25,
+            }
+        ]
+    );
+}
+
+#[test]
+fn test_resniff_appended() {
+    let mut data = b"ts,level,count\n2024-01-01,INFO,3\n2024-01-02,WARN,5\n2024-01-03,INFO,7\n\
+                     2024-01-04,WARN,4\n"
+        .to_vec();
+    let previous = Sniffer::new()
+        .sample_size(SampleSize::All)
+        .sniff_reader(Cursor::new(&data))
+        .unwrap();
+    assert_eq!(previous.types, vec![Type::Date, Type::Text, Type::Unsigned]);
+
+    // nothing appended yet
+    let offset = data.len() as u64;
+    let (metadata, next_offset) = Sniffer::new()
+        .resniff_appended(Cursor::new(&data), &previous, offset)
+        .unwrap();
+    assert_eq!(metadata, previous);
+    assert_eq!(next_offset, offset);
+
+    data.extend_from_slice(b"2024-01-05,ERROR,-2.5\n2024-01-06,INFO,1\n2024-01-07,INFO,2\n");
+    let (metadata, next_offset) = Sniffer::new()
+        .resniff_appended(Cursor::new(&data), &previous, offset)
+        .unwrap();
+    assert_eq!(next_offset, data.len() as u64);
+    assert_eq!(metadata.dialect, previous.dialect);
+    assert_eq!(metadata.fields, vec!["ts", "level", "count"]);
+    assert_eq!(metadata.types, vec![Type::Date, Type::Text, Type::Float]);
+    assert_eq!(metadata.date_formats[0], Some("%Y-%m-%d".to_string()));
+    assert_eq!(
+        metadata.categories[1],
+        Some(vec![
+            "INFO".to_string(),
+            "WARN".to_string(),
+            "ERROR".to_string()
+        ])
+    );
+    assert_eq!(
+        metadata.value_ranges[2],
+        Some(ValueRange {
+            min: "-2.5".to_string(),
+            max: "7".to_string(),
+        })
+    );
+    assert_eq!(
+        metadata.value_ranges[0],
+        Some(ValueRange {
+            min: "2024-01-01".to_string(),
+            max: "2024-01-07".to_string(),
+        })
+    );
+    assert_eq!(metadata.integer_widths[2], None);
+
+    // the data appended after that is merged in turn
+    data.extend_from_slice(b"2024-01-08,INFO,unknown\n");
+    let (metadata, _) = Sniffer::new()
+        .resniff_appended(Cursor::new(&data), &metadata, next_offset)
+        .unwrap();
+    assert_eq!(metadata.types, vec![Type::Date, Type::Text, Type::Text]);
+    assert_eq!(metadata.number_formats[2], None);
+
+    // the sniffer doesn't keep the dialect of the previous metadata
+    let mut sniffer = Sniffer::new();
+    sniffer
+        .resniff_appended(Cursor::new(&data), &previous, offset)
+        .unwrap();
+    let metadata = sniffer
+        .sniff_reader(Cursor::new("id;name\n1;a\n2;b\n3;c\n"))
+        .unwrap();
+    assert_eq!(metadata.dialect.delimiter, b';');
+    assert!(metadata.dialect.header.has_header_row);
+    assert_eq!(metadata.fields, vec!["id", "name"]);
+
+    // appended records with more fields than the header names leave the extra columns unnamed
+    let mut data = b"a,b\n1,2\n3,4\n5,6\n7,8\n".to_vec();
+    let previous = Sniffer::new()
+        .sample_size(SampleSize::All)
+        .sniff_reader(Cursor::new(&data))
+        .unwrap();
+    let offset = data.len() as u64;
+    data.extend_from_slice(b"9,10,11\n12,13,14\n15,16,17\n");
+    let (metadata, _) = Sniffer::new()
+        .resniff_appended(Cursor::new(&data), &previous, offset)
+        .unwrap();
+    assert_eq!(metadata.fields, vec!["a", "b", ""]);
+    assert_eq!(metadata.fields_raw.len(), 3);
+    assert_eq!(metadata.types, vec![Type::Unsigned; 3]);
+    assert_eq!(metadata.value_ranges.len(), 3);
+}
+
+#[test]
+fn test_clean_field_names() {
+    let data =
+        "\u{200B}id,first\u{A0}name,email\u{7}\n1,Ann,ann@example.com\n2,Bob,bob@example.com\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    assert_eq!(
+        metadata.fields,
+        vec!["\u{200B}id", "first\u{A0}name", "email\u{7}"]
+    );
+
+    let metadata = Sniffer::new()
+        .clean_field_names(true)
+        .sniff_reader(Cursor::new(data))
+        .unwrap();
+    assert_eq!(metadata.fields, vec!["id", "first name", "email"]);
+    assert_eq!(metadata.fields_raw[0], "\u{200B}id".as_bytes());
+    assert_eq!(metadata.fields_raw[2], b"email\x07");
+}
+
+#[test]
+fn test_replay_reader() {
+    // a reader without `Seek`, as for standard input
+    struct Pipe<'a>(&'a [u8]);
+    impl std::io::Read for Pipe<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.0.read(buf)
+        }
+    }
+
+    let mut data = "Report: scores\nGenerated: 2024-01-01\nid;name;score\n".to_string();
+    for i in 0..2000 {
+        data.push_str(&format!("{i};name {i};{}\n", i % 7));
+    }
+    let (metadata, replay): (_, ReplayReader<_>) = Sniffer::new()
+        .sample_size(SampleSize::Records(20))
+        .sniff_replayable(Pipe(data.as_bytes()))
+        .unwrap();
+    assert_eq!(metadata.dialect.header.num_preamble_rows, 2);
+    let mut reader = metadata.dialect.open_reader(replay).unwrap();
+    assert_eq!(reader.headers().unwrap(), vec!["id", "name", "score"]);
+    let records = reader.records().collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(records.len(), 2000);
+    assert_eq!(&records[1999][1], "name 1999");
+
+    // once the stream past the sample is read, it can't be rewound
+    let (_, mut replay) = Sniffer::new()
+        .sample_size(SampleSize::Records(20))
+        .sniff_replayable(Pipe(data.as_bytes()))
+        .unwrap();
+    let mut contents = String::new();
+    replay.read_to_string(&mut contents).unwrap();
+    assert_eq!(contents, data);
+    assert!(replay.seek(SeekFrom::Start(0)).is_err());
+}
+
+#[test]
+fn test_replay_reader_sample() {
+    // a reader counting the bytes read from it
+    struct Counting<'a>(&'a [u8], usize);
+    impl std::io::Read for Counting<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n_read = self.0.read(buf)?;
+            self.1 += n_read;
+            Ok(n_read)
+        }
+    }
+
+    let mut data = String::from("id,name\n");
+    for i in 0..20_000 {
+        data.push_str(&format!("{i},name {i}\n"));
+    }
+    let (metadata, replay) = Sniffer::new()
+        .sniff_replayable(Counting(data.as_bytes(), 0))
+        .unwrap();
+    assert_eq!(metadata.types, vec![Type::Unsigned, Type::Text]);
+    let sample = replay.sample().to_vec();
+    assert!(data.as_bytes().starts_with(&sample));
+    assert!(sample.len() < data.len());
+
+    // the sample and the rest of the input make up the data, which is read once
+    let (head, mut rest) = replay.into_parts();
+    assert_eq!(head, sample);
+    let mut contents = head;
+    rest.read_to_end(&mut contents).unwrap();
+    assert_eq!(contents, data.as_bytes());
+    assert_eq!(rest.into_inner().1, data.len());
+
+    // what's been replayed already isn't handed back
+    let (_, mut replay) = Sniffer::new()
+        .sniff_replayable(Counting(data.as_bytes(), 0))
+        .unwrap();
+    let mut start = [0; 8];
+    replay.read_exact(&mut start).unwrap();
+    assert_eq!(&start, b"id,name\n");
+    let (head, _) = replay.into_parts();
+    assert!(data.as_bytes()[8..].starts_with(&head));
+}
+
+#[test]
+fn test_sample_size_records() {
+    // wide records (of about 2 KB), whose last column turns out not to be numeric in record 12
+    let mut data = String::from("id,notes,code\n");
+    for i in 1..=20 {
+        let code = if i == 12 {
+            "n/a-12".to_string()
+        } else {
+            i.to_string()
+        };
+        data.push_str(&format!("{i},{},{code}\n", "x".repeat(2000)));
+    }
+    // 16 KB only holds the first 8 records
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(&data)).unwrap();
+    assert_eq!(metadata.types[2], Type::Unsigned);
+
+    let metadata = Sniffer::new()
+        .sample_size(SampleSize::Records(15))
+        .sniff_reader(Cursor::new(&data))
+        .unwrap();
+    assert_eq!(metadata.types, vec![Type::Unsigned, Type::Text, Type::Text]);
+}
+
+#[test]
+fn test_sample_size_duration() {
+    let data = "id,name\n1,a\n2,b\n3,c\n";
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    assert_eq!(metadata.sampled_bytes, data.len() as u64);
+
+    let mut data = String::from("id,name,score,when\n");
+    for i in 0..200_000 {
+        data.push_str(&format!(
+            "{i},name {i},{}.5,2024-01-{:02}\n",
+            i % 100,
+            i % 28 + 1
+        ));
+    }
+    let start = std::time::Instant::now();
+    let metadata = Sniffer::new()
+        .sample_size(SampleSize::Duration(std::time::Duration::from_millis(50)))
+        .sniff_reader(Cursor::new(&data))
+        .unwrap();
+    // generously, as tests may run slowly (e.g. in debug builds, or in parallel)
+    assert!(start.elapsed() < std::time::Duration::from_secs(5));
+    assert!(metadata.sampled_bytes > 0);
+    assert!(metadata.sampled_bytes < data.len() as u64);
+    assert_eq!(metadata.fields, vec!["id", "name", "score", "when"]);
+    assert_eq!(
+        metadata.types,
+        vec![Type::Unsigned, Type::Text, Type::Float, Type::Date]
+    );
+
+    // the sample is timed with the sniffer's options, so a slow detector shrinks it
+    let metadata = Sniffer::new()
+        .register_type_detector(SlowDetector)
+        .sample_size(SampleSize::Duration(std::time::Duration::from_millis(500)))
+        .sniff_reader(Cursor::new(&data))
+        .unwrap();
+    assert!(metadata.sampled_bytes < 1 << 12);
+}
+
+#[derive(Debug)]
+struct SlowDetector;
+
+impl TypeDetector for SlowDetector {
+    fn name(&self) -> &'static str {
+        "slow"
+    }
+
+    fn matches(&self, _value: &[u8]) -> bool {
+        std::thread::sleep(std::time::Duration::from_millis(1));
+        true
+    }
+}
+
+#[test]
+fn test_sample_size_all_encoding() {
+    // a windows-1252 byte past the start of the file the encoding is otherwise inferred from
+    let mut data = b"id,name\n".to_vec();
+    for i in 0..3000 {
+        data.extend_from_slice(format!("{i},Bob\n").as_bytes());
+    }
+    data.extend_from_slice(b"3000,Jos\xe9\n");
+
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(&data[..])).unwrap();
+    assert_eq!(metadata.encoding, Encoding::Utf8);
+
+    let metadata = Sniffer::new()
+        .sample_size(SampleSize::All)
+        .sniff_reader(Cursor::new(&data[..]))
+        .unwrap();
+    assert_eq!(metadata.encoding, Encoding::Windows1252);
+    assert_eq!(metadata.types, vec![Type::Unsigned, Type::Text]);
+}
+
+#[test]
+fn test_random_samples() {
+    // sorted by date, with amounts which only have decimals (and notes) later on
+    let mut data = String::from("date,amount,note\n");
+    for day in 0..2000 {
+        let date = format!("2020-{:02}-{:02}", day / 28 % 12 + 1, day % 28 + 1);
+        if day < 1000 {
+            data.push_str(&format!("{date},{day},\n"));
+        } else {
+            data.push_str(&format!("{date},{day}.5,late\n"));
+        }
+    }
+
+    let metadata = Sniffer::new()
+        .sample_size(SampleSize::Bytes(1024))
+        .sniff_reader(Cursor::new(&data))
+        .unwrap();
+    assert_eq!(metadata.types, vec![Type::Date, Type::Unsigned, Type::NULL]);
+
+    let metadata = Sniffer::new()
+        .sample_size(SampleSize::Bytes(1024))
+        .random_samples(4)
+        .sniff_reader(Cursor::new(&data))
+        .unwrap();
+    assert_eq!(metadata.types, vec![Type::Date, Type::Float, Type::Text]);
+    assert!(metadata.nullable[2]);
+    assert_eq!(metadata.dialect.header.num_preamble_rows, 0);
+    assert!(metadata.dialect.header.has_header_row);
+}
+
+#[test]
+fn test_reservoir_sample() {
+    let mut data = String::from("id,amount,comment,tag\n");
+    for i in 0..5000 {
+        if i < 4000 {
+            data.push_str(&format!("{i},{i},\"ok\",\"a\"\n"));
+        } else {
+            data.push_str(&format!("{i},{i}.25,\"multi\nline\",\"b\"\n"));
+        }
+    }
+
+    let metadata = Sniffer::new()
+        .sample_size(SampleSize::All)
+        .reservoir_sample(100)
+        .sniff_reader_unseekable(data.as_bytes())
+        .unwrap();
+    assert!(metadata.dialect.header.has_header_row);
+    assert_eq!(metadata.fields, vec!["id", "amount", "comment", "tag"]);
+    assert_eq!(
+        metadata.types,
+        vec![Type::Unsigned, Type::Float, Type::Text, Type::Text]
+    );
+    assert!(metadata.has_multiline_fields);
+    assert!(metadata.sampled_bytes < data.len() as u64 / 10);
+
+    // the first records alone aren't representative
+    let metadata = Sniffer::new()
+        .sample_size(SampleSize::Records(200))
+        .sniff_reader_unseekable(data.as_bytes())
+        .unwrap();
+    assert_eq!(metadata.types[1], Type::Unsigned);
+}
+
+#[test]
+fn test_sample_middle_and_tail() {
+    // codes which turn alphanumeric near the end of the file, followed by a footer row
+    let mut data = String::from("id,code,score\n");
+    for i in 0..3000 {
+        let code = if i < 2980 {
+            i.to_string()
+        } else {
+            format!("X{i}")
+        };
+        data.push_str(&format!("{i},{code},{}\n", i % 7));
+    }
+    data.push_str("Total,,9000\n");
+
+    let metadata = Sniffer::new()
+        .sample_size(SampleSize::Bytes(1024))
+        .sniff_reader(Cursor::new(&data))
+        .unwrap();
+    assert_eq!(metadata.types[1], Type::Unsigned);
+
+    let metadata = Sniffer::new()
+        .sample_size(SampleSize::Bytes(1024))
+        .sample_middle_and_tail(true)
+        .sniff_reader(Cursor::new(&data))
+        .unwrap();
+    assert_eq!(
+        metadata.types,
+        vec![Type::Unsigned, Type::Text, Type::Unsigned]
+    );
+    assert_eq!(metadata.num_footer_rows, 1);
+}
+
+#[test]
+fn test_sample_size_percent() {
+    let mut data = String::from("id,value\n");
+    for i in 0..10_000 {
+        // a float 40% of the way through the file
+        let value = if i == 4000 {
+            "0.5".to_string()
+        } else {
+            i.to_string()
+        };
+        data.push_str(&format!("{i},{value}\n"));
+    }
+
+    let metadata = Sniffer::new()
+        .sample_size(SampleSize::Percent(10.0))
+        .sniff_reader(Cursor::new(&data))
+        .unwrap();
+    let tenth = data.len() as u64 / 10;
+    // within a record of a tenth of the file
+    assert!(metadata.sampled_bytes.abs_diff(tenth) < 16);
+    assert_eq!(metadata.types, vec![Type::Unsigned, Type::Unsigned]);
+
+    let metadata = Sniffer::new()
+        .sample_size(SampleSize::Percent(50.0))
+        .sniff_reader(Cursor::new(&data))
+        .unwrap();
+    assert_eq!(metadata.types, vec![Type::Unsigned, Type::Float]);
+
+    let metadata = Sniffer::new()
+        .sample_size(SampleSize::Percent(100.0))
+        .sniff_reader(Cursor::new(&data))
+        .unwrap();
+    assert_eq!(metadata.sampled_bytes, data.len() as u64);
+}
+
+#[test]
+fn test_skip_start() {
+    // a license header longer than the sample
+    let mut license = String::new();
+    for i in 0..200 {
+        license.push_str(&format!("Clause {i}: use, copy; modify | merge (as is).\n"));
+    }
+    let data = format!("{license}id;name;score\n1;Ann;4.5\n2;Bob;3.0\n3;Cy;2.5\n");
+
+    for skip in [
+        SkipStart::Rows(200),
+        SkipStart::Bytes(license.len() as u64),
+        SkipStart::Bytes(license.len() as u64 - 20),
+    ] {
+        let metadata = Sniffer::new()
+            .sample_size(SampleSize::Bytes(1024))
+            .skip_start(skip)
+            .sniff_reader(Cursor::new(&data))
+            .unwrap();
+        assert_eq!(metadata.dialect.delimiter, b';');
+        assert_eq!(metadata.dialect.header.num_preamble_rows, 200);
+        assert_eq!(metadata.fields, vec!["id", "name", "score"]);
+        assert_eq!(
+            metadata.types,
+            vec![Type::Unsigned, Type::Text, Type::Float]
+        );
+
+        let mut reader = metadata.open_reader(Cursor::new(&data), false).unwrap();
+        let records = reader.records().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(&records[1][1], "Bob");
+    }
+
+    // a stream is read past the skipped lines
+    let metadata = Sniffer::new()
+        .skip_start(SkipStart::Rows(200))
+        .sniff_reader_unseekable(data.as_bytes())
+        .unwrap();
+    assert_eq!(metadata.dialect.delimiter, b';');
+    assert_eq!(metadata.dialect.header.num_preamble_rows, 0);
+}
+
+#[test]
+fn test_preview_records() {
+    let data =
+        "# exported\nid;name;city\n1;\"Ann\";\"Z\u{fc}rich; CH\"\n2;\"Bob\";Bern\n3;\"Cy\";Basel\n";
+    let metadata = Sniffer::new()
+        .preview_records(2)
+        .sniff_reader(Cursor::new(data))
+        .unwrap();
+    assert_eq!(
+        metadata.preview,
+        vec![vec!["1", "Ann", "Zürich; CH"], vec!["2", "Bob", "Bern"]]
+    );
+
+    // none by default, and decoded from the sniffed encoding
+    let metadata = Sniffer::new().sniff_reader(Cursor::new(data)).unwrap();
+    assert!(metadata.preview.is_empty());
+    let data = b"id,name\n1,Caf\xe9\n2,Bar\n";
+    let metadata = Sniffer::new()
+        .preview_records(5)
+        .sniff_reader(Cursor::new(&data[..]))
+        .unwrap();
+    assert_eq!(metadata.encoding, Encoding::Windows1252);
+    assert_eq!(metadata.preview, vec![vec!["1", "Café"], vec!["2", "Bar"]]);
+}
+
+#[test]
+fn test_sample_size_adaptive() {
+    // the header is only told apart from the first (long) records once short ones are sampled
+    let mut data = String::from("id;comment;score\n");
+    for i in 0..2000 {
+        let comment = "word, ".repeat(if i < 40 { 60 } else { 3 });
+        data.push_str(&format!("{i};{comment};{}\n", i % 90));
+    }
+    let adaptive = |min_confidence, max_bytes| {
+        Sniffer::new()
+            .sample_size(SampleSize::Adaptive {
+                min_confidence,
+                max_bytes,
+            })
+            .sniff_reader(Cursor::new(&data))
+            .unwrap()
+    };
+    let metadata = adaptive(0.5, 1 << 20);
+    assert!(metadata.sampled_bytes <= 1 << 12);
+    let metadata = adaptive(0.8, 1 << 20);
+    assert!(metadata.sampled_bytes > 1 << 13 && metadata.sampled_bytes <= 1 << 14);
+    assert!(metadata.confidence.header >= 0.8);
+    assert_eq!(metadata.dialect.delimiter, b';');
+    assert_eq!(metadata.fields, vec!["id", "comment", "score"]);
+
+    // up to the largest sample, if the confidence isn't reached
+    let metadata = adaptive(0.9, 1 << 15);
+    assert!(metadata.sampled_bytes > 1 << 14 && metadata.sampled_bytes <= 1 << 15);
+    let metadata = adaptive(0.9, 1 << 30);
+    assert_eq!(metadata.sampled_bytes, data.len() as u64);
+
+    // the sample sizes are tried with the sniffer's options: a known header is certain
+    let metadata = Sniffer::new()
+        .header(&Header {
+            has_header_row: true,
+            num_preamble_rows: 0,
+            num_comment_preamble_rows: 0,
+        })
+        .sample_size(SampleSize::Adaptive {
+            min_confidence: 0.9,
+            max_bytes: 1 << 20,
+        })
+        .sniff_reader(Cursor::new(&data))
+        .unwrap();
+    assert!(metadata.sampled_bytes <= 1 << 12);
+    assert_eq!(metadata.confidence.header, 1.0);
+}
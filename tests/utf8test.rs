@@ -17,48 +17,64 @@ fn test_utf8() {
         .sniff_path(data_filepath)
         .unwrap();
     assert_eq!(
-        metadata,
-        Metadata {
-            dialect: Dialect {
-                delimiter: b';',
-                header: Header {
-                    has_header_row: true,
-                    num_preamble_rows: 0
-                },
-                quote: Quote::None,
-                flexible: false,
-                is_utf8: false
+        metadata.dialect,
+        Dialect {
+            delimiter: b';',
+            multibyte_delimiter: None,
+            single_column: false,
+            header: Header {
+                has_header_row: true,
+                num_preamble_rows: 0,
+                num_comment_preamble_rows: 0
             },
-            avg_record_len: 137,
-            num_fields: 11,
-            fields: vec![
-                "DIA.DESEMB".to_string(),
-                "COD.SUBITEM.NCM".to_string(),
-                "VMLE.DOLAR.BAL.EXP".to_string(),
-                "PESO.LIQ.MERC.BAL.EXP".to_string(),
-                "COD.IMPDR.EXPDR".to_string(),
-                "NOME.IMPDR.EXPDR".to_string(),
-                "PAIS.ORIGEM.DESTINO".to_string(),
-                "UA.LOCAL.DESBQ.EMBQ".to_string(),
-                "NOME.IMPORTADOR.ESTRANGEIRO".to_string(),
-                "NUM.DDE".to_string(),
-                "NUM.RE".to_string()
-            ],
-            types: vec![
-                Type::Text,
-                Type::Unsigned,
-                Type::Float,
-                Type::Float,
-                Type::Text,
-                Type::Text,
-                Type::Text,
-                Type::Text,
-                Type::Text,
-                Type::Text,
-                Type::Text
-            ]
+            quote: Quote::None,
+            quote_style: QuoteStyle::Never,
+            escape: Escape::Disabled,
+            double_quote: true,
+            comment: Comment::Disabled,
+            terminator: Terminator::LF,
+            trailing_delimiter: false,
+            embedded_escapes: false,
+            trim: Trim::None,
+            blank_lines: BlankLinePolicy::Skip,
+            flexible: false,
+            is_utf8: false
         }
     );
+    assert_eq!(metadata.avg_record_len, 137);
+    assert_eq!(metadata.num_fields, 11);
+    assert_eq!(
+        metadata.fields,
+        vec![
+            "DIA.DESEMB".to_string(),
+            "COD.SUBITEM.NCM".to_string(),
+            "VMLE.DOLAR.BAL.EXP".to_string(),
+            "PESO.LIQ.MERC.BAL.EXP".to_string(),
+            "COD.IMPDR.EXPDR".to_string(),
+            "NOME.IMPDR.EXPDR".to_string(),
+            "PAIS.ORIGEM.DESTINO".to_string(),
+            "UA.LOCAL.DESBQ.EMBQ".to_string(),
+            "NOME.IMPORTADOR.ESTRANGEIRO".to_string(),
+            "NUM.DDE".to_string(),
+            "NUM.RE".to_string()
+        ]
+    );
+    assert_eq!(
+        metadata.types,
+        vec![
+            Type::Text,
+            Type::Unsigned,
+            Type::Float,
+            Type::Float,
+            Type::Text,
+            Type::Text,
+            Type::Text,
+            Type::Text,
+            Type::Text,
+            Type::Text,
+            Type::Text
+        ]
+    );
 }
 
 #[test]
@@ -72,40 +88,56 @@ fn test_flexible_again() {
         .sniff_path(data_filepath)
         .unwrap();
     assert_eq!(
-        metadata,
-        Metadata {
-            dialect: Dialect {
-                delimiter: b',',
-                header: Header {
-                    has_header_row: true,
-                    num_preamble_rows: 0,
-                },
-                quote: Quote::None,
-                flexible: true,
-                is_utf8: true,
+        metadata.dialect,
+        Dialect {
+            delimiter: b',',
+            multibyte_delimiter: None,
+            single_column: false,
+            header: Header {
+                has_header_row: true,
+                num_preamble_rows: 0,
+                num_comment_preamble_rows: 0,
             },
-            avg_record_len: 112,
-            num_fields: 7,
-            fields: vec![
-                "municipality".to_string(),
-                "agency_id".to_string(),
-                "route_id".to_string(),
-                "route_short_name".to_string(),
-                "route_long_name".to_string(),
-                "min_headway_minutes".to_string(),
-                "route_url".to_string()
-            ],
-            types: vec![
-                Type::Text,
-                Type::Unsigned,
-                Type::Unsigned,
-                Type::Text,
-                Type::Text,
-                Type::Unsigned,
-                Type::Text
-            ]
+            quote: Quote::None,
+            quote_style: QuoteStyle::Never,
+            escape: Escape::Disabled,
+            double_quote: true,
+            comment: Comment::Disabled,
+            terminator: Terminator::LF,
+            trailing_delimiter: false,
+            embedded_escapes: false,
+            trim: Trim::None,
+            blank_lines: BlankLinePolicy::Skip,
+            flexible: true,
+            is_utf8: true,
         }
     );
+    assert_eq!(metadata.avg_record_len, 112);
+    assert_eq!(metadata.num_fields, 7);
+    assert_eq!(
+        metadata.fields,
+        vec![
+            "municipality".to_string(),
+            "agency_id".to_string(),
+            "route_id".to_string(),
+            "route_short_name".to_string(),
+            "route_long_name".to_string(),
+            "min_headway_minutes".to_string(),
+            "route_url".to_string()
+        ]
+    );
+    assert_eq!(
+        metadata.types,
+        vec![
+            Type::Text,
+            Type::Unsigned,
+            Type::Unsigned,
+            Type::Text,
+            Type::Text,
+            Type::Unsigned,
+            Type::Url
+        ]
+    );
 }
 
 #[test]
@@ -120,26 +152,42 @@ fn test_date_sniffing_dmy() {
         .sniff_path(data_filepath)
         .unwrap();
     assert_eq!(
-        metadata,
-        Metadata {
-            dialect: Dialect {
-                delimiter: b',',
-                header: Header {
-                    has_header_row: true,
-                    num_preamble_rows: 0
-                },
-                quote: Quote::None,
-                flexible: false,
-                is_utf8: true
+        metadata.dialect,
+        Dialect {
+            delimiter: b',',
+            multibyte_delimiter: None,
+            single_column: false,
+            header: Header {
+                has_header_row: true,
+                num_preamble_rows: 0,
+                num_comment_preamble_rows: 0
             },
-            avg_record_len: 11,
-            num_fields: 3,
-            fields: vec![
-                "starttime".to_string(),
-                "letter".to_string(),
-                "number".to_string(),
-            ],
-            types: vec![Type::Date, Type::Text, Type::Unsigned,]
+            quote: Quote::None,
+            quote_style: QuoteStyle::Never,
+            escape: Escape::Disabled,
+            double_quote: true,
+            comment: Comment::Disabled,
+            terminator: Terminator::LF,
+            trailing_delimiter: false,
+            embedded_escapes: false,
+            trim: Trim::None,
+            blank_lines: BlankLinePolicy::Skip,
+            flexible: false,
+            is_utf8: true
         }
     );
+    assert_eq!(metadata.avg_record_len, 11);
+    assert_eq!(metadata.num_fields, 3);
+    assert_eq!(
+        metadata.fields,
+        vec![
+            "starttime".to_string(),
+            "letter".to_string(),
+            "number".to_string(),
+        ]
+    );
+    assert_eq!(
+        metadata.types,
+        vec![Type::Date, Type::Text, Type::Unsigned,]
+    );
 }